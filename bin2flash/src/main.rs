@@ -4,9 +4,11 @@
 //! Converts BIN images into WallCalendar flash file format
 //!
 //! Usage:
-//! `bin2flash [--small] <input>` - will scan images directory and pack the into flash images.
+//! `bin2flash [--small] [--timezone <tz>] <input>` - will scan images directory and pack the into flash images.
 //! Parameters `--small` will enforce using just a first two months data for all images data
 //! to produce flash blob that will fit into MCU for debug purposes.
+//! Parameter `--timezone` sets device timezone as a POSIX TZ string, Finnish time
+//! `EET-2EEST,M3.5.0/3,M10.5.0/4` is used by default.
 //!
 //! Image directory is expected to have particular subdirectories with particular files in them:
 //!
@@ -19,6 +21,10 @@
 //! -- /moon/moon{1..8}.bin - Mandatory, moon phases, with full moon at moon1 (16x1)
 //! -- /small_digits/{0..9}.bin - Mandatory, contains big digits (16x16)
 //! -- /weekdays/{fri,mon..wed}.bin - Mandatory, weekdays names (192x30)
+//! -- /holidays.csv - Optional, holidays rules, built-in Finnish holidays are used if missing
+//! -- /namedays/%locale%.csv - Optional, name days for the locale, selected with `--locale` (`fi` by default)
+//! -- /font.bin - Optional, text font, produced by `png2bin` from a glyph sheet
+//! -- /tzmap.bin - Optional, timezone map, produced by `tz2bin` from timezone boundaries
 //!
 //! Format specification:
 //! File begins with directory of entries. Each entry is u32, pointing to the first byte of
@@ -31,10 +37,31 @@
 //! * 7 weekdays entries
 //! * 8 moon phase entries
 //! * 366 triplets of a side black, a side red and b side red images for each day, starting from 1st of January
+//! * holidays rules
+//! * name days
+//! * text font
+//! * timezone
+//! * timezone map
 //!
-//!The directory takes 1 + 366*3 + 8 + 7 + 12 + 10 +10 = 1146 entries or 1146*4=4584 bytes. First images starts
+//!The directory takes 1 + 366*3 + 8 + 7 + 12 + 10 + 10 + 5 = 1151 entries or 1151*4=4604 bytes. First images starts
 //! exactly after directory
 //!
+//! Holidays file is a CSV file with a header and `name,kind,rule,substitution,since` columns,
+//! where `kind` is one of `public`, `flag` or `observance`, `rule` is a textual rule
+//! like `fixed 12-06`, `nth 2 sun 05`, `last mon 05`, `easter -2` or `weekday-from sat 06-20`,
+//! `substitution` is empty, `next-free-weekday` or `nearest-weekday` and `since` is
+//! an optional first year of the holiday. Rules are stored as a sequence of binary records
+//! with u16 length prefix, same as images.
+//!
+//! Name days file is a CSV file with a header and `date,names` columns, where `date` is `MM-DD`
+//! and `names` is a UTF-8 text to be printed for that day. Name days lists are usually copyrighted
+//! by their publishers, so they are not included and have to be supplied by the user.
+//! Name days are stored as 366 records, starting from 1st of January and including 29th of February.
+//! Each record is u8 length, followed by UTF-8 names, with u16 length prefix for the whole section.
+//!
+//! Timezone is stored as a POSIX TZ string with u16 length prefix. When timezone map is present,
+//! device looks up its timezone by the GPS position and configured timezone is only used outside of the map.
+//!
 
 use std::fs::{File, OpenOptions};
 use std::io::{Seek, SeekFrom, Write};
@@ -42,11 +69,36 @@ use std::path::{Path, PathBuf};
 use byteorder::{LittleEndian, WriteBytesExt};
 use clap::{Parser};
 use humansize::{file_size_opts as options, FileSize};
-use anyhow::Result;
+use anyhow::{Context, Result};
+use celestial::TimeZone;
+use holidays::{Holiday, HolidayKind, Rule, Substitution};
+use thiserror::Error;
 
 #[macro_use]
 extern crate log;
 
+#[derive(Error, Debug)]
+enum DataFileError {
+    #[error("Line {0}: unknown holiday kind")]
+    Kind(usize),
+    #[error("Line {0}: malformed rule")]
+    Rule(usize),
+    #[error("Line {0}: unknown substitution")]
+    Substitution(usize),
+    #[error("Line {0}: malformed year")]
+    Year(usize),
+    #[error("Line {0}: holiday can not be encoded")]
+    Encoding(usize),
+    #[error("Line {0}: malformed date")]
+    Date(usize),
+    #[error("Line {0}: names are longer than 255 bytes")]
+    Names(usize),
+    #[error("Section is larger than 64KiB")]
+    TooLarge,
+    #[error("Malformed or unsupported POSIX TZ string: {0}")]
+    TimeZone(String),
+}
+
 #[derive(Parser, Debug)]
 #[clap(version = "1.0", author = "Denis Chaplygin <akashihi@gmail.com>")]
 struct Opts {
@@ -55,6 +107,12 @@ struct Opts {
     /// Generate smaller flash image for debug purposes
     #[clap(short, long)]
     short: bool,
+    /// Name days locale
+    #[clap(short, long, default_value = "fi")]
+    locale: String,
+    /// Device timezone as a POSIX TZ string
+    #[clap(short, long, default_value = "EET-2EEST,M3.5.0/3,M10.5.0/4")]
+    timezone: String,
 }
 
 fn add_file_to_flash(fname: &Path, offset: u32, entries_directory: &mut[u32], directory_index: usize, output_file: &mut File) -> Result<u32> {
@@ -67,6 +125,101 @@ fn add_file_to_flash(fname: &Path, offset: u32, entries_directory: &mut[u32], di
     Ok(offset + bytes.len() as u32)
 }
 
+/// Reads CSV data file, lines starting with `#` are comments
+fn read_data_file(fname: &Path) -> Result<String> {
+    std::fs::read_to_string(fname).with_context(|| format!("Failed to read {}", fname.display()))
+}
+
+fn data_file_reader(content: &str) -> csv::Reader<&[u8]> {
+    csv::ReaderBuilder::new().trim(csv::Trim::All).comment(Some(b'#')).from_reader(content.as_bytes())
+}
+
+/// Line of the record in the data file.
+/// Reader positions the record right after the previous one, so skipped comment and empty lines are counted here.
+fn record_line(content: &str, row: &csv::StringRecord) -> usize {
+    let start = row.position().map(|p| p.byte() as usize).unwrap_or(0);
+    let skipped = content[start..].lines().take_while(|l| l.trim().is_empty() || l.starts_with('#')).count();
+    content[..start].matches('\n').count() + skipped + 1
+}
+
+fn encode_holidays(fname: &Path) -> Result<Vec<u8>> {
+    let content = read_data_file(fname)?;
+    let mut reader = data_file_reader(&content);
+    let mut records: Vec<u8> = Vec::new();
+    for row in reader.records() {
+        let row = row?;
+        let line = record_line(&content, &row);
+        let year = row.get(4).unwrap_or("");
+        let holiday = Holiday {
+            name: row.get(0).unwrap_or(""),
+            kind: HolidayKind::parse(row.get(1).unwrap_or("")).ok_or(DataFileError::Kind(line))?,
+            rule: Rule::parse(row.get(2).unwrap_or("")).ok_or(DataFileError::Rule(line))?,
+            substitution: Substitution::parse(row.get(3).unwrap_or("")).ok_or(DataFileError::Substitution(line))?,
+            since: if year.is_empty() { None } else { Some(year.parse().map_err(|_| DataFileError::Year(line))?) },
+        };
+        let mut buffer = vec![0u8; holiday.encoded_size()];
+        holiday.encode(&mut buffer).ok_or(DataFileError::Encoding(line))?;
+        records.extend_from_slice(&buffer);
+    }
+    with_length_prefix(&records)
+}
+
+fn encode_name_days(fname: &Path) -> Result<Vec<u8>> {
+    let content = read_data_file(fname)?;
+    let mut reader = data_file_reader(&content);
+    let mut days: Vec<String> = vec![String::new(); 366];
+    for row in reader.records() {
+        let row = row?;
+        let line = record_line(&content, &row);
+        let index = row.get(0).and_then(leap_year_day_index).ok_or(DataFileError::Date(line))?;
+        let names = row.get(1).unwrap_or("");
+        if names.len() > u8::MAX as usize {
+            return Err(DataFileError::Names(line).into());
+        }
+        days[index] = names.to_string();
+    }
+    let mut records: Vec<u8> = Vec::new();
+    for names in days {
+        records.push(names.len() as u8);
+        records.extend_from_slice(names.as_bytes());
+    }
+    with_length_prefix(&records)
+}
+
+/// Converts `MM-DD` date to the index of the day in a leap year, starting with 0
+fn leap_year_day_index(date: &str) -> Option<usize> {
+    const DAYS_IN_MONTH: [usize; 12] = [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+    let (month, day) = date.split_once('-')?;
+    let month = month.parse::<usize>().ok().filter(|m| (1..=12).contains(m))?;
+    let day = day.parse::<usize>().ok().filter(|d| (1..=DAYS_IN_MONTH[month - 1]).contains(d))?;
+    Some(DAYS_IN_MONTH[..month - 1].iter().sum::<usize>() + day - 1)
+}
+
+fn encode_timezone(timezone: &str) -> Result<Vec<u8>> {
+    if TimeZone::parse(timezone).is_none() {
+        return Err(DataFileError::TimeZone(timezone.to_string()).into());
+    }
+    with_length_prefix(timezone.trim().as_bytes())
+}
+
+fn with_length_prefix(records: &[u8]) -> Result<Vec<u8>> {
+    if records.len() > u16::MAX as usize {
+        return Err(DataFileError::TooLarge.into());
+    }
+    let mut section: Vec<u8> = Vec::with_capacity(records.len() + 2);
+    section.write_u16::<LittleEndian>(records.len() as u16)?;
+    section.extend_from_slice(records);
+    Ok(section)
+}
+
+fn add_section_to_flash(fname: &Path, section: &[u8], offset: u32, entries_directory: &mut[u32], directory_index: usize, output_file: &mut File) -> Result<u32> {
+    info!("{}, size: {}", fname.as_os_str().to_str().unwrap(),
+            section.len().file_size(options::CONVENTIONAL).unwrap_or_else(|_| "Unknown".to_string()));
+    entries_directory[directory_index] = offset;
+    output_file.write_all(section)?;
+    Ok(offset + section.len() as u32)
+}
+
 fn dump_directory(output_file: &mut File, entries_directory: &[u32]) -> Result<u32> {
     let mut offset = 0;
     output_file.seek(SeekFrom::Start(0))?;
@@ -86,7 +239,7 @@ fn main() {
     let opts: Opts = Opts::parse();
     info!("Input directory: {}", opts.input);
 
-    let mut entries_directory: [u32; 1146] = [0; 1146];
+    let mut entries_directory: [u32; 1151] = [0; 1151];
     let mut directory_index=0;
 
     let mut output_file = OpenOptions::new().write(true).create(true).truncate(true).open("spiflash.bin").unwrap();
@@ -169,6 +322,96 @@ fn main() {
         }
     }
 
+    //Add holidays
+    let holidays_fname: PathBuf = [&opts.input, "holidays.csv"].iter().collect();
+    if holidays_fname.exists() {
+        let section = encode_holidays(&holidays_fname).unwrap();
+        offset = add_section_to_flash(&holidays_fname, &section, offset, &mut entries_directory, directory_index, &mut output_file).unwrap();
+    } else {
+        entries_directory[directory_index] = 0;
+    }
+    directory_index+=1;
+
+    //Add name days
+    let name_days_fname: PathBuf = [&opts.input, "namedays", &format!("{}.csv", opts.locale)].iter().collect();
+    if name_days_fname.exists() {
+        let section = encode_name_days(&name_days_fname).unwrap();
+        offset = add_section_to_flash(&name_days_fname, &section, offset, &mut entries_directory, directory_index, &mut output_file).unwrap();
+    } else {
+        warn!("Name days file {} is missing", name_days_fname.display());
+        entries_directory[directory_index] = 0;
+    }
+    directory_index+=1;
+
+    //Add font
+    let font_fname: PathBuf = [&opts.input, "font.bin"].iter().collect();
+    if font_fname.exists() {
+        offset = add_file_to_flash(&font_fname, offset, &mut entries_directory, directory_index, &mut output_file).unwrap();
+    } else {
+        entries_directory[directory_index] = 0;
+    }
+    directory_index+=1;
+
+    //Add timezone
+    let section = encode_timezone(&opts.timezone).unwrap();
+    offset = add_section_to_flash(Path::new("timezone"), &section, offset, &mut entries_directory, directory_index, &mut output_file).unwrap();
+    directory_index+=1;
+
+    //Add timezone map
+    let tzmap_fname: PathBuf = [&opts.input, "tzmap.bin"].iter().collect();
+    if tzmap_fname.exists() {
+        add_file_to_flash(&tzmap_fname, offset, &mut entries_directory, directory_index, &mut output_file).unwrap();
+    } else {
+        entries_directory[directory_index] = 0;
+    }
+
     dump_directory(&mut output_file, &entries_directory).unwrap();
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes data file into the temporary directory
+    fn data_file(name: &str, content: &str) -> PathBuf {
+        let fname = std::env::temp_dir().join(format!("bin2flash-test-{}-{}", std::process::id(), name));
+        std::fs::write(&fname, content).unwrap();
+        fname
+    }
+
+    fn error<T>(result: Result<T>) -> DataFileError {
+        result.err().and_then(|e| e.downcast::<DataFileError>().ok()).unwrap()
+    }
+
+    #[test]
+    fn holiday_error_line() {
+        let fname = data_file(
+            "holidays.csv",
+            "# Comment\nname,kind,rule,substitution,since\nNew Year's Day,public,fixed 01-01,,\n# Broken\nEpiphany,public,fixed 13-06,,\n",
+        );
+        assert!(matches!(error(encode_holidays(&fname)), DataFileError::Rule(5)));
+        std::fs::write(&fname, "# Comment\nname,kind,rule,substitution,since\n\nNew Year's Day,official,fixed 01-01,,\n").unwrap();
+        assert!(matches!(error(encode_holidays(&fname)), DataFileError::Kind(4)));
+        std::fs::remove_file(&fname).unwrap();
+        assert!(encode_holidays(Path::new("../images/holidays.csv")).is_ok());
+    }
+
+    #[test]
+    fn name_days_error_line() {
+        let fname = data_file("namedays.csv", "# Comment\n# Another one\ndate,names\n01-02,Aapeli\n02-30,Nobody\n");
+        assert!(matches!(error(encode_name_days(&fname)), DataFileError::Date(5)));
+        std::fs::write(&fname, format!("date,names\n# Comment\n01-02,{}\n", "a".repeat(256))).unwrap();
+        assert!(matches!(error(encode_name_days(&fname)), DataFileError::Names(3)));
+        std::fs::remove_file(&fname).unwrap();
+    }
+
+    #[test]
+    fn commented_name_days() {
+        let fname = data_file("commented.csv", "# Comment\ndate,names\n# 01-01 has no names\n01-02,Aapeli\n");
+        let name_days = encode_name_days(&fname).unwrap();
+        // Length prefix, empty first day, then the second one
+        assert_eq!(name_days[2..10], [0, 6, b'A', b'a', b'p', b'e', b'l', b'i']);
+        std::fs::remove_file(&fname).unwrap();
+    }
+}
@@ -0,0 +1,73 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Common helpers for the double precision calculations
+
+use libm::{cos, sin};
+
+/// Julian Day of J2000.0 epoch
+pub const J2000: f64 = 2451545.0;
+
+/// Converts degrees to radians
+pub fn deg_to_rad(deg: f64) -> f64 {
+    deg * (core::f64::consts::PI / 180.0)
+}
+
+/// Sine of an angle in degrees
+pub fn sin_deg(deg: f64) -> f64 {
+    sin(deg_to_rad(deg))
+}
+
+/// Cosine of an angle in degrees
+pub fn cos_deg(deg: f64) -> f64 {
+    cos(deg_to_rad(deg))
+}
+
+/// Reduces angle to 0..360 range
+pub fn normalize_degrees(deg: f64) -> f64 {
+    let reduced = deg % 360.0;
+    if reduced < 0.0 { reduced + 360.0 } else { reduced }
+}
+
+/// Julian centuries since J2000.0
+pub fn julian_centuries(jd: f64) -> f64 {
+    (jd - J2000) / 36525.0
+}
+
+/// Difference between Terrestrial Time and Universal Time in seconds
+///
+/// Uses polynomial expressions by Espenak and Meeus
+pub fn delta_t(year: f64) -> f64 {
+    if (1900.0..1920.0).contains(&year) {
+        let t = year - 1900.0;
+        -2.79 + 1.494119 * t - 0.0598939 * t * t + 0.0061966 * t * t * t - 0.000197 * t * t * t * t
+    } else if (1920.0..1941.0).contains(&year) {
+        let t = year - 1920.0;
+        21.20 + 0.84493 * t - 0.076100 * t * t + 0.0020936 * t * t * t
+    } else if (1941.0..1961.0).contains(&year) {
+        let t = year - 1950.0;
+        29.07 + 0.407 * t - t * t / 233.0 + t * t * t / 2547.0
+    } else if (1961.0..1986.0).contains(&year) {
+        let t = year - 1975.0;
+        45.45 + 1.067 * t - t * t / 260.0 - t * t * t / 718.0
+    } else if (1986.0..2005.0).contains(&year) {
+        let t = year - 2000.0;
+        63.86 + 0.3345 * t - 0.060374 * t * t + 0.0017275 * t * t * t + 0.000651814 * t * t * t * t
+            + 0.00002373599 * t * t * t * t * t
+    } else if (2005.0..2050.0).contains(&year) {
+        let t = year - 2000.0;
+        62.92 + 0.32217 * t + 0.005589 * t * t
+    } else if (2050.0..2150.0).contains(&year) {
+        let u = (year - 1820.0) / 100.0;
+        -20.0 + 32.0 * u * u - 0.5628 * (2150.0 - year)
+    } else {
+        let u = (year - 1820.0) / 100.0;
+        -20.0 + 32.0 * u * u
+    }
+}
+
+/// Converts Julian Ephemeris Day (TT) to Julian Day (UT)
+pub fn jde_to_jd(jde: f64) -> f64 {
+    let year = 2000.0 + (jde - J2000) / 365.25;
+    jde - delta_t(year) / 86400.0
+}
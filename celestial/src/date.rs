@@ -0,0 +1,342 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Validated calendar date
+
+/// Earliest supported year
+pub const MIN_YEAR: u32 = 1;
+/// Latest supported year
+pub const MAX_YEAR: u32 = 9999;
+
+const DAYS_BEFORE_MONTH: [u16; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+
+/// Gregorian calendar date
+///
+/// It could only be constructed for an existing date, so
+/// functions accepting it do not need to care about 31st of February.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CalendarDate {
+    // Field order matters, derived ordering compares year first
+    year: u32,
+    month: u32,
+    day: u32,
+}
+
+/// Checks if year is a leap year in the Gregorian calendar
+pub fn is_leap_year(year: u32) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+/// Number of days in a month of the year, None for non-existing month
+pub fn days_in_month(month: u32, year: u32) -> Option<u32> {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => Some(31),
+        4 | 6 | 9 | 11 => Some(30),
+        2 => if is_leap_year(year) { Some(29) } else { Some(28) },
+        _ => None
+    }
+}
+
+impl CalendarDate {
+    /// Creates a date, returns None if there is no such date
+    pub fn new(day: u32, month: u32, year: u32) -> Option<Self> {
+        if !(MIN_YEAR..=MAX_YEAR).contains(&year) {
+            return None
+        }
+        let month_length = days_in_month(month, year)?;
+        if day == 0 || day > month_length {
+            None
+        } else {
+            Some(CalendarDate { year, month, day })
+        }
+    }
+
+    /// Creates a date from a day number in the year, starting with 1
+    pub fn from_day_of_year(day_of_year: u16, year: u32) -> Option<Self> {
+        let leap_shift = if is_leap_year(year) { 1 } else { 0 };
+        let mut month = 12;
+        while month > 1 {
+            let month_start = DAYS_BEFORE_MONTH[month - 1] + if month > 2 { leap_shift } else { 0 };
+            if day_of_year > month_start {
+                return Self::new((day_of_year - month_start) as u32, month as u32, year)
+            }
+            month -= 1;
+        }
+        Self::new(day_of_year as u32, 1, year)
+    }
+
+    /// Creates a date from a Julian Day Number
+    pub fn from_julian_day_number(jdn: i32) -> Option<Self> {
+        // Richards algorithm, valid for any positive JDN
+        if jdn < 0 {
+            return None
+        }
+        let a = jdn as i64 + 32044;
+        let b = (4 * a + 3) / 146097;
+        let c = a - 146097 * b / 4;
+        let d = (4 * c + 3) / 1461;
+        let e = c - 1461 * d / 4;
+        let m = (5 * e + 2) / 153;
+        let day = e - (153 * m + 2) / 5 + 1;
+        let month = m + 3 - 12 * (m / 10);
+        let year = 100 * b + d - 4800 + m / 10;
+        if year < 0 {
+            None
+        } else {
+            Self::new(day as u32, month as u32, year as u32)
+        }
+    }
+
+    /// Day of month starting with 1
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    /// Month of year starting with 1
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    /// Year
+    pub fn year(&self) -> u32 {
+        self.year
+    }
+
+    /// Checks if date belongs to a leap year
+    pub fn is_leap_year(&self) -> bool {
+        is_leap_year(self.year)
+    }
+
+    /// Number of days in the date's month
+    pub fn days_in_month(&self) -> u32 {
+        days_in_month(self.month, self.year).unwrap_or(0)
+    }
+
+    /// Number of a day in the year, starting with 1
+    pub fn day_of_year(&self) -> u16 {
+        let leap_shift = if self.month > 2 && self.is_leap_year() { 1 } else { 0 };
+        DAYS_BEFORE_MONTH[(self.month - 1) as usize] + leap_shift + self.day as u16
+    }
+
+    /// Julian Day Number, i.e. number of days since noon of 1st of January 4713 BC
+    pub fn julian_day_number(&self) -> i32 {
+        let a = (14 - self.month as i32) / 12;
+        let y = self.year as i32 + 4800 - a;
+        let m = self.month as i32 + 12 * a - 3;
+        self.day as i32 + (153 * m + 2) / 5 + 365 * y + y / 4 - y / 100 + y / 400 - 32045
+    }
+
+    /// Following date, None after the last supported date
+    pub fn next_day(&self) -> Option<Self> {
+        if self.day < self.days_in_month() {
+            Some(CalendarDate { day: self.day + 1, ..*self })
+        } else if self.month < 12 {
+            Some(CalendarDate { day: 1, month: self.month + 1, ..*self })
+        } else {
+            Self::new(1, 1, self.year + 1)
+        }
+    }
+
+    /// Preceding date, None before the first supported date
+    pub fn prev_day(&self) -> Option<Self> {
+        if self.day > 1 {
+            Some(CalendarDate { day: self.day - 1, ..*self })
+        } else if self.month > 1 {
+            let month = self.month - 1;
+            days_in_month(month, self.year).map(|day| CalendarDate { day, month, ..*self })
+        } else {
+            Self::new(31, 12, self.year - 1)
+        }
+    }
+
+    /// Shifts date by the specified number of days, negative values go to the past
+    pub fn add_days(&self, days: i32) -> Option<Self> {
+        self.julian_day_number().checked_add(days).and_then(Self::from_julian_day_number)
+    }
+}
+
+/// Point in time in UTC with a second resolution
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UtcDateTime {
+    date: CalendarDate,
+    seconds: u32,
+}
+
+impl UtcDateTime {
+    /// Creates a timestamp, returns None for non-existing time of day
+    pub fn new(date: CalendarDate, hour: u32, minute: u32, second: u32) -> Option<Self> {
+        if hour > 23 || minute > 59 || second > 59 {
+            None
+        } else {
+            Some(UtcDateTime { date, seconds: hour * 3600 + minute * 60 + second })
+        }
+    }
+
+    /// Creates a timestamp from a Julian Day, rounding it to the nearest second
+    pub fn from_julian_day(jd: f64) -> Option<Self> {
+        let shifted = jd + 0.5;
+        let mut jdn = libm::floor(shifted);
+        let mut seconds = libm::round((shifted - jdn) * 86400.0) as u32;
+        if seconds >= 86400 {
+            jdn += 1.0;
+            seconds -= 86400;
+        }
+        CalendarDate::from_julian_day_number(jdn as i32).map(|date| UtcDateTime { date, seconds })
+    }
+
+    /// Calendar date
+    pub fn date(&self) -> CalendarDate {
+        self.date
+    }
+
+    /// Hour starting with 0
+    pub fn hour(&self) -> u32 {
+        self.seconds / 3600
+    }
+
+    /// Minute starting with 0
+    pub fn minute(&self) -> u32 {
+        self.seconds / 60 % 60
+    }
+
+    /// Second starting with 0
+    pub fn second(&self) -> u32 {
+        self.seconds % 60
+    }
+
+    /// Minutes since midnight, same units as sunrise/sunset
+    pub fn minute_of_day(&self) -> u16 {
+        (self.seconds / 60) as u16
+    }
+
+    /// Julian Day, i.e. fractional number of days since noon of 1st of January 4713 BC
+    pub fn julian_day(&self) -> f64 {
+        self.date.julian_day_number() as f64 - 0.5 + self.seconds as f64 / 86400.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::date::{days_in_month, is_leap_year, CalendarDate, UtcDateTime};
+
+    #[test]
+    fn valid_date() {
+        let date = CalendarDate::new(3, 11, 2021).unwrap();
+        assert_eq!(date.day(), 3);
+        assert_eq!(date.month(), 11);
+        assert_eq!(date.year(), 2021);
+    }
+
+    #[test]
+    fn invalid_dates() {
+        assert!(CalendarDate::new(31, 2, 2021).is_none());
+        assert!(CalendarDate::new(29, 2, 2021).is_none());
+        assert!(CalendarDate::new(31, 4, 2021).is_none());
+        assert!(CalendarDate::new(0, 1, 2021).is_none());
+        assert!(CalendarDate::new(1, 13, 2021).is_none());
+        assert!(CalendarDate::new(1, 0, 2021).is_none());
+        assert!(CalendarDate::new(1, 1, 0).is_none());
+    }
+
+    #[test]
+    fn leap_years() {
+        assert!(is_leap_year(2020));
+        assert!(is_leap_year(2000));
+        assert!(!is_leap_year(1900));
+        assert!(!is_leap_year(2021));
+        assert_eq!(days_in_month(2, 2020), Some(29));
+        assert_eq!(days_in_month(2, 2100), Some(28));
+        assert!(CalendarDate::new(29, 2, 2020).is_some());
+    }
+
+    #[test]
+    fn day_of_year() {
+        assert_eq!(CalendarDate::new(1, 1, 2021).unwrap().day_of_year(), 1);
+        assert_eq!(CalendarDate::new(31, 12, 2021).unwrap().day_of_year(), 365);
+        assert_eq!(CalendarDate::new(31, 12, 2020).unwrap().day_of_year(), 366);
+        assert_eq!(CalendarDate::new(1, 3, 2020).unwrap().day_of_year(), 61);
+    }
+
+    #[test]
+    fn from_day_of_year() {
+        assert_eq!(CalendarDate::from_day_of_year(1, 2021), CalendarDate::new(1, 1, 2021));
+        assert_eq!(CalendarDate::from_day_of_year(60, 2020), CalendarDate::new(29, 2, 2020));
+        assert_eq!(CalendarDate::from_day_of_year(60, 2021), CalendarDate::new(1, 3, 2021));
+        assert_eq!(CalendarDate::from_day_of_year(366, 2020), CalendarDate::new(31, 12, 2020));
+        assert!(CalendarDate::from_day_of_year(366, 2021).is_none());
+        assert!(CalendarDate::from_day_of_year(0, 2021).is_none());
+    }
+
+    #[test]
+    fn julian_day_number() {
+        assert_eq!(CalendarDate::new(1, 1, 2000).unwrap().julian_day_number(), 2451545);
+        assert_eq!(CalendarDate::new(3, 11, 2021).unwrap().julian_day_number(), 2459522);
+        assert_eq!(CalendarDate::from_julian_day_number(2451545), CalendarDate::new(1, 1, 2000));
+        assert_eq!(CalendarDate::from_julian_day_number(2459522), CalendarDate::new(3, 11, 2021));
+    }
+
+    #[test]
+    fn julian_day_number_round_trip() {
+        let mut date = CalendarDate::new(1, 1, 1899).unwrap();
+        for _ in 0..(366 * 4) {
+            assert_eq!(CalendarDate::from_julian_day_number(date.julian_day_number()), Some(date));
+            date = date.add_days(53).unwrap();
+        }
+    }
+
+    #[test]
+    fn next_day() {
+        assert_eq!(CalendarDate::new(28, 2, 2020).unwrap().next_day(), CalendarDate::new(29, 2, 2020));
+        assert_eq!(CalendarDate::new(28, 2, 2021).unwrap().next_day(), CalendarDate::new(1, 3, 2021));
+        assert_eq!(CalendarDate::new(31, 12, 2021).unwrap().next_day(), CalendarDate::new(1, 1, 2022));
+        assert!(CalendarDate::new(31, 12, 9999).unwrap().next_day().is_none());
+    }
+
+    #[test]
+    fn prev_day() {
+        assert_eq!(CalendarDate::new(1, 3, 2020).unwrap().prev_day(), CalendarDate::new(29, 2, 2020));
+        assert_eq!(CalendarDate::new(1, 5, 2021).unwrap().prev_day(), CalendarDate::new(30, 4, 2021));
+        assert_eq!(CalendarDate::new(1, 1, 2022).unwrap().prev_day(), CalendarDate::new(31, 12, 2021));
+        assert!(CalendarDate::new(1, 1, 1).unwrap().prev_day().is_none());
+    }
+
+    #[test]
+    fn add_days() {
+        let date = CalendarDate::new(3, 11, 2021).unwrap();
+        assert_eq!(date.add_days(0), Some(date));
+        assert_eq!(date.add_days(59), CalendarDate::new(1, 1, 2022));
+        assert_eq!(date.add_days(-307), CalendarDate::new(31, 12, 2020));
+        assert_eq!(date.add_days(365 * 3 + 1), CalendarDate::new(3, 11, 2024));
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(CalendarDate::new(31, 12, 2020) < CalendarDate::new(1, 1, 2021));
+        assert!(CalendarDate::new(1, 2, 2021) > CalendarDate::new(31, 1, 2021));
+    }
+
+    #[test]
+    fn utc_date_time() {
+        let date = CalendarDate::new(19, 11, 2021).unwrap();
+        let time = UtcDateTime::new(date, 8, 57, 30).unwrap();
+        assert_eq!(time.hour(), 8);
+        assert_eq!(time.minute(), 57);
+        assert_eq!(time.second(), 30);
+        assert_eq!(time.minute_of_day(), 537);
+        assert!(UtcDateTime::new(date, 24, 0, 0).is_none());
+        assert!(UtcDateTime::new(date, 0, 60, 0).is_none());
+    }
+
+    #[test]
+    fn utc_julian_day() {
+        let noon = UtcDateTime::new(CalendarDate::new(1, 1, 2000).unwrap(), 12, 0, 0).unwrap();
+        assert_eq!(noon.julian_day(), 2451545.0);
+        let midnight = UtcDateTime::new(CalendarDate::new(1, 1, 2000).unwrap(), 0, 0, 0).unwrap();
+        assert_eq!(midnight.julian_day(), 2451544.5);
+        assert_eq!(UtcDateTime::from_julian_day(2451545.0), Some(noon));
+        assert_eq!(UtcDateTime::from_julian_day(2451544.5), Some(midnight));
+        let late = UtcDateTime::new(CalendarDate::new(31, 12, 1999).unwrap(), 23, 59, 59).unwrap();
+        assert_eq!(UtcDateTime::from_julian_day(late.julian_day()), Some(late));
+        assert_eq!(UtcDateTime::from_julian_day(2451544.499999), Some(midnight));
+    }
+}
@@ -0,0 +1,201 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Easter computus and the feasts, that depend on it
+
+use crate::date::CalendarDate;
+use crate::weekday::weekday;
+
+/// Church tradition, that defines the Easter date
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Church {
+    /// Western churches, Easter is calculated in the Gregorian calendar
+    Western,
+    /// Orthodox churches, Easter is calculated in the Julian calendar
+    Orthodox,
+}
+
+/// Feast, that moves together with Easter
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MovableFeast {
+    /// Friday before Easter
+    GoodFriday,
+    /// Easter itself
+    EasterSunday,
+    /// Monday after Easter
+    EasterMonday,
+    /// Thursday, 39 days after Easter
+    Ascension,
+    /// Pentecost or Whitsun, 7th Sunday after Easter
+    Pentecost,
+    /// Monday after Pentecost
+    WhitMonday,
+}
+
+impl MovableFeast {
+    /// Days between Easter and the feast
+    fn offset(self) -> i32 {
+        match self {
+            MovableFeast::GoodFriday => -2,
+            MovableFeast::EasterSunday => 0,
+            MovableFeast::EasterMonday => 1,
+            MovableFeast::Ascension => 39,
+            MovableFeast::Pentecost => 49,
+            MovableFeast::WhitMonday => 50,
+        }
+    }
+}
+
+/// Gregorian Easter, anonymous algorithm as given by Meeus
+fn western_easter(year: u32) -> Option<CalendarDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    CalendarDate::new(day, month, year)
+}
+
+/// Julian Easter, Meeus algorithm, converted to the Gregorian calendar
+fn orthodox_easter(year: u32) -> Option<CalendarDate> {
+    let a = year % 4;
+    let b = year % 7;
+    let c = year % 19;
+    let d = (19 * c + 15) % 30;
+    let e = (2 * a + 4 * b + 34 - d) % 7;
+    let month = (d + e + 114) / 31;
+    let day = (d + e + 114) % 31 + 1;
+    CalendarDate::from_julian_day_number(julian_calendar_day_number(day, month, year))
+}
+
+/// Julian Day Number of a date in the Julian calendar
+fn julian_calendar_day_number(day: u32, month: u32, year: u32) -> i32 {
+    let a = (14 - month as i32) / 12;
+    let y = year as i32 + 4800 - a;
+    let m = month as i32 + 12 * a - 3;
+    day as i32 + (153 * m + 2) / 5 + 365 * y + y / 4 - 32083
+}
+
+/// Calculates Easter Sunday of the year, as a Gregorian calendar date
+pub fn easter_sunday(year: u32, church: Church) -> Option<CalendarDate> {
+    match church {
+        Church::Western => western_easter(year),
+        Church::Orthodox => orthodox_easter(year),
+    }
+}
+
+/// Calculates date of the movable feast in the year
+pub fn movable_feast(feast: MovableFeast, year: u32, church: Church) -> Option<CalendarDate> {
+    easter_sunday(year, church)?.add_days(feast.offset())
+}
+
+/// Finds first Saturday on or after the date
+fn saturday_from(date: CalendarDate) -> Option<CalendarDate> {
+    date.add_days((13 - weekday(date) as i32) % 7)
+}
+
+/// Calculates Nordic Midsummer Day, Saturday between 20th and 26th of June
+///
+/// Midsummer Eve is the Friday before
+pub fn midsummer_day(year: u32) -> Option<CalendarDate> {
+    saturday_from(CalendarDate::new(20, 6, year)?)
+}
+
+/// Calculates Nordic All Saints' Day, Saturday between 31st of October and 6th of November
+pub fn all_saints_day(year: u32) -> Option<CalendarDate> {
+    saturday_from(CalendarDate::new(31, 10, year)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32, month: u32, year: u32) -> Option<CalendarDate> {
+        CalendarDate::new(day, month, year)
+    }
+
+    #[test]
+    fn western_easter_dates() {
+        assert_eq!(easter_sunday(2000, Church::Western), date(23, 4, 2000));
+        assert_eq!(easter_sunday(2021, Church::Western), date(4, 4, 2021));
+        assert_eq!(easter_sunday(2022, Church::Western), date(17, 4, 2022));
+        assert_eq!(easter_sunday(2023, Church::Western), date(9, 4, 2023));
+        assert_eq!(easter_sunday(2024, Church::Western), date(31, 3, 2024));
+        assert_eq!(easter_sunday(2025, Church::Western), date(20, 4, 2025));
+    }
+
+    #[test]
+    fn western_easter_limits() {
+        // Earliest and latest possible dates
+        assert_eq!(easter_sunday(1818, Church::Western), date(22, 3, 1818));
+        assert_eq!(easter_sunday(2285, Church::Western), date(22, 3, 2285));
+        assert_eq!(easter_sunday(1943, Church::Western), date(25, 4, 1943));
+        assert_eq!(easter_sunday(2038, Church::Western), date(25, 4, 2038));
+    }
+
+    #[test]
+    fn orthodox_easter_dates() {
+        assert_eq!(easter_sunday(2021, Church::Orthodox), date(2, 5, 2021));
+        assert_eq!(easter_sunday(2022, Church::Orthodox), date(24, 4, 2022));
+        assert_eq!(easter_sunday(2023, Church::Orthodox), date(16, 4, 2023));
+        assert_eq!(easter_sunday(2024, Church::Orthodox), date(5, 5, 2024));
+        assert_eq!(easter_sunday(2025, Church::Orthodox), date(20, 4, 2025));
+    }
+
+    #[test]
+    fn easter_is_always_sunday() {
+        for year in 1900..2100 {
+            assert_eq!(weekday(easter_sunday(year, Church::Western).unwrap()), 7);
+            assert_eq!(weekday(easter_sunday(year, Church::Orthodox).unwrap()), 7);
+        }
+    }
+
+    #[test]
+    fn feasts_of_2022() {
+        assert_eq!(movable_feast(MovableFeast::GoodFriday, 2022, Church::Western), date(15, 4, 2022));
+        assert_eq!(movable_feast(MovableFeast::EasterMonday, 2022, Church::Western), date(18, 4, 2022));
+        assert_eq!(movable_feast(MovableFeast::Ascension, 2022, Church::Western), date(26, 5, 2022));
+        assert_eq!(movable_feast(MovableFeast::Pentecost, 2022, Church::Western), date(5, 6, 2022));
+        assert_eq!(movable_feast(MovableFeast::WhitMonday, 2022, Church::Western), date(6, 6, 2022));
+        assert_eq!(movable_feast(MovableFeast::GoodFriday, 2022, Church::Orthodox), date(22, 4, 2022));
+    }
+
+    #[test]
+    fn feasts_across_month_boundary() {
+        assert_eq!(movable_feast(MovableFeast::GoodFriday, 2024, Church::Western), date(29, 3, 2024));
+        assert_eq!(movable_feast(MovableFeast::EasterMonday, 2024, Church::Western), date(1, 4, 2024));
+    }
+
+    #[test]
+    fn midsummer() {
+        assert_eq!(midsummer_day(2022), date(25, 6, 2022));
+        assert_eq!(midsummer_day(2023), date(24, 6, 2023));
+        assert_eq!(midsummer_day(2024), date(22, 6, 2024));
+        assert_eq!(midsummer_day(2021), date(26, 6, 2021));
+        assert_eq!(midsummer_day(2026), date(20, 6, 2026));
+    }
+
+    #[test]
+    fn all_saints() {
+        assert_eq!(all_saints_day(2022), date(5, 11, 2022));
+        assert_eq!(all_saints_day(2023), date(4, 11, 2023));
+        assert_eq!(all_saints_day(2024), date(2, 11, 2024));
+        assert_eq!(all_saints_day(2020), date(31, 10, 2020));
+        assert_eq!(all_saints_day(2021), date(6, 11, 2021));
+    }
+
+    #[test]
+    fn out_of_range_years() {
+        assert!(easter_sunday(0, Church::Western).is_none());
+        assert!(midsummer_day(10000).is_none());
+    }
+}
@@ -4,16 +4,63 @@
 
 //! Celestial calculations support
 //!
-//! * weekday
-//! * sunrise/sunset
-//! * moon phase
+//! * validated calendar date
+//! * Easter and movable feasts
+//! * weekday, ISO week number and day of the quarter
+//! * sunrise/sunset, twilight, solar noon and day length
+//! * moon phase, age, illumination and phase events
+//! * moonrise/moonset
+//! * equinoxes and solstices
+//! * time zones with daylight saving time
+//! * precise solar position, equation of time and sunrise/sunset (`precise` feature)
 
+mod astro;
+mod date;
+mod easter;
 mod moon;
+mod moonrise;
+mod season;
+#[cfg(feature = "precise")]
+mod solar;
 mod sun;
+mod timezone;
 mod weekday;
 
+pub use date::CalendarDate as CalendarDate;
+pub use date::UtcDateTime as UtcDateTime;
+pub use date::is_leap_year as is_leap_year;
+pub use date::days_in_month as days_in_month;
+pub use easter::easter_sunday as easter_sunday;
+pub use easter::movable_feast as movable_feast;
+pub use easter::midsummer_day as midsummer_day;
+pub use easter::all_saints_day as all_saints_day;
+pub use easter::{Church, MovableFeast};
 pub use moon::moon_phase as moon_phase;
+pub use moon::moon_state as moon_state;
+pub use moon::next_lunar_phase as next_lunar_phase;
+pub use moon::previous_lunar_phase as previous_lunar_phase;
+pub use moon::lunar_phase_on as lunar_phase_on;
+pub use moon::{LunarPhase, MoonState, MoonTrend};
+pub use moonrise::moonrise as moonrise;
+pub use moonrise::moonset as moonset;
+pub use moonrise::MoonEvent as MoonEvent;
+pub use season::season_event as season_event;
+pub use season::season_event_on as season_event_on;
+pub use season::SeasonEvent as SeasonEvent;
 pub use weekday::weekday as weekday;
+pub use weekday::iso_week as iso_week;
+pub use weekday::weeks_in_year as weeks_in_year;
+pub use weekday::first_day_of_week as first_day_of_week;
+pub use weekday::day_of_quarter as day_of_quarter;
 pub use sun::sunrise as sunrise;
 pub use sun::sunset as sunset;
-pub use sun::day_of_the_year as day_of_the_year;
\ No newline at end of file
+pub use sun::solar_noon as solar_noon;
+pub use sun::day_length as day_length;
+pub use sun::day_length_change as day_length_change;
+pub use sun::sun_events as sun_events;
+pub use sun::Horizon as Horizon;
+pub use sun::SunEvents as SunEvents;
+pub use sun::day_of_the_year as day_of_the_year;
+pub use timezone::{DaylightSaving, LocalDateTime, TimeZone, Transition};
+#[cfg(feature = "precise")]
+pub use solar::{equation_of_time, precise_sunrise, precise_sunset, solar_declination, solar_position, SolarPosition};
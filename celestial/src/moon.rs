@@ -1,51 +1,347 @@
 #![deny(missing_docs)]
 #![deny(unsafe_code)]
 
-//! Moon phase calculation using Julian Day approach
+//! Moon phase calculation
+//!
+//! Phase events are calculated with Meeus' "Astronomical Algorithms" chapter 49,
+//! illuminated fraction is calculated with the chapter 48 low precision method.
+
+use crate::astro::{cos_deg, jde_to_jd, julian_centuries, normalize_degrees, sin_deg};
+use crate::date::{CalendarDate, UtcDateTime};
+use libm::floor;
+
+const SYNODIC_MONTH: f64 = 29.530588861;
+// Timestamps are rounded to a second, so events are compared with half a second tolerance
+const HALF_SECOND: f64 = 0.5 / 86400.0;
+
+/// Principal phase of the moon
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LunarPhase {
+    /// New moon
+    NewMoon,
+    /// First quarter
+    FirstQuarter,
+    /// Full moon
+    FullMoon,
+    /// Last quarter
+    LastQuarter,
+}
+
+/// Direction of the illuminated fraction change
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoonTrend {
+    /// Illuminated fraction grows
+    Waxing,
+    /// Illuminated fraction shrinks
+    Waning,
+}
+
+/// Moon state at some moment
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MoonState {
+    /// Days since the previous new moon
+    pub age: f32,
+    /// Illuminated fraction of the disk, 0.0 - new moon, 1.0 - full moon
+    pub illumination: f32,
+    /// Whether the moon is waxing or waning
+    pub trend: MoonTrend,
+}
+
+impl LunarPhase {
+    fn cycle_fraction(self) -> f64 {
+        match self {
+            LunarPhase::NewMoon => 0.0,
+            LunarPhase::FirstQuarter => 0.25,
+            LunarPhase::FullMoon => 0.5,
+            LunarPhase::LastQuarter => 0.75,
+        }
+    }
+}
+
+/// Julian Day (UT) of the phase for the lunation number k, k = 0 is the new moon of 6th of January 2000
+fn phase_julian_day(k: i32, phase: LunarPhase) -> f64 {
+    let k = k as f64 + phase.cycle_fraction();
+    let t = k / 1236.85;
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let t4 = t3 * t;
+    let mean = 2451550.09766 + SYNODIC_MONTH * k + 0.00015437 * t2 - 0.000000150 * t3 + 0.00000000073 * t4;
+    let e = 1.0 - 0.002516 * t - 0.0000074 * t2;
+    let m = 2.5534 + 29.10535670 * k - 0.0000014 * t2 - 0.00000011 * t3;
+    let mp = 201.5643 + 385.81693528 * k + 0.0107582 * t2 + 0.00001238 * t3 - 0.000000058 * t4;
+    let f = 160.7108 + 390.67050284 * k - 0.0016118 * t2 - 0.00000227 * t3 + 0.000000011 * t4;
+    let omega = 124.7746 - 1.56375588 * k + 0.0020672 * t2 + 0.00000215 * t3;
+
+    let correction = match phase {
+        LunarPhase::NewMoon | LunarPhase::FullMoon => {
+            let (c1, c2, c3, c4, c5, c6, c7) = if phase == LunarPhase::NewMoon {
+                (-0.40720, 0.17241, 0.01608, 0.01039, 0.00739, -0.00514, 0.00208)
+            } else {
+                (-0.40614, 0.17302, 0.01614, 0.01043, 0.00734, -0.00515, 0.00209)
+            };
+            c1 * sin_deg(mp)
+                + c2 * e * sin_deg(m)
+                + c3 * sin_deg(2.0 * mp)
+                + c4 * sin_deg(2.0 * f)
+                + c5 * e * sin_deg(mp - m)
+                + c6 * e * sin_deg(mp + m)
+                + c7 * e * e * sin_deg(2.0 * m)
+                - 0.00111 * sin_deg(mp - 2.0 * f)
+                - 0.00057 * sin_deg(mp + 2.0 * f)
+                + 0.00056 * e * sin_deg(2.0 * mp + m)
+                - 0.00042 * sin_deg(3.0 * mp)
+                + 0.00042 * e * sin_deg(m + 2.0 * f)
+                + 0.00038 * e * sin_deg(m - 2.0 * f)
+                - 0.00024 * e * sin_deg(2.0 * mp - m)
+                - 0.00017 * sin_deg(omega)
+                - 0.00007 * sin_deg(mp + 2.0 * m)
+                + 0.00004 * sin_deg(2.0 * mp - 2.0 * f)
+                + 0.00004 * sin_deg(3.0 * m)
+                + 0.00003 * sin_deg(mp + m - 2.0 * f)
+                + 0.00003 * sin_deg(2.0 * mp + 2.0 * f)
+                - 0.00003 * sin_deg(mp + m + 2.0 * f)
+                + 0.00003 * sin_deg(mp - m + 2.0 * f)
+                - 0.00002 * sin_deg(mp - m - 2.0 * f)
+                - 0.00002 * sin_deg(3.0 * mp + m)
+                + 0.00002 * sin_deg(4.0 * mp)
+        }
+        LunarPhase::FirstQuarter | LunarPhase::LastQuarter => {
+            let quarter = -0.62801 * sin_deg(mp)
+                + 0.17172 * e * sin_deg(m)
+                - 0.01183 * e * sin_deg(mp + m)
+                + 0.00862 * sin_deg(2.0 * mp)
+                + 0.00804 * sin_deg(2.0 * f)
+                + 0.00454 * e * sin_deg(mp - m)
+                + 0.00204 * e * e * sin_deg(2.0 * m)
+                - 0.00180 * sin_deg(mp - 2.0 * f)
+                - 0.00070 * sin_deg(mp + 2.0 * f)
+                - 0.00040 * sin_deg(3.0 * mp)
+                - 0.00034 * e * sin_deg(2.0 * mp - m)
+                + 0.00032 * e * sin_deg(m + 2.0 * f)
+                + 0.00032 * e * sin_deg(m - 2.0 * f)
+                - 0.00028 * e * e * sin_deg(mp + 2.0 * m)
+                + 0.00027 * e * sin_deg(2.0 * mp + m)
+                - 0.00017 * sin_deg(omega)
+                - 0.00005 * sin_deg(mp - m - 2.0 * f)
+                + 0.00004 * sin_deg(2.0 * mp + 2.0 * f)
+                - 0.00004 * sin_deg(mp + m + 2.0 * f)
+                + 0.00004 * sin_deg(mp - 2.0 * m)
+                + 0.00003 * sin_deg(mp + m - 2.0 * f)
+                + 0.00003 * sin_deg(3.0 * m)
+                + 0.00002 * sin_deg(2.0 * mp - 2.0 * f)
+                + 0.00002 * sin_deg(mp - m + 2.0 * f)
+                - 0.00002 * sin_deg(3.0 * mp + m);
+            let w = 0.00306 - 0.00038 * e * cos_deg(m) + 0.00026 * cos_deg(mp) - 0.00002 * cos_deg(mp - m)
+                + 0.00002 * cos_deg(mp + m)
+                + 0.00002 * cos_deg(2.0 * f);
+            if phase == LunarPhase::FirstQuarter { quarter + w } else { quarter - w }
+        }
+    };
+
+    // Planetary arguments, common for all phases
+    const PLANETARY: [(f64, f64, f64); 14] = [
+        (0.000325, 299.77, 0.107408),
+        (0.000165, 251.88, 0.016321),
+        (0.000164, 251.83, 26.651886),
+        (0.000126, 349.42, 36.412478),
+        (0.000110, 84.66, 18.206239),
+        (0.000062, 141.74, 53.303771),
+        (0.000060, 207.14, 2.453732),
+        (0.000056, 154.84, 7.306860),
+        (0.000047, 34.52, 27.261239),
+        (0.000042, 207.19, 0.121824),
+        (0.000040, 291.34, 1.844379),
+        (0.000037, 161.72, 24.198154),
+        (0.000035, 239.56, 25.513099),
+        (0.000023, 331.55, 3.592518),
+    ];
+    let planetary: f64 = PLANETARY
+        .iter()
+        .enumerate()
+        .map(|(index, (coefficient, base, rate))| {
+            let argument = base + rate * k - if index == 0 { 0.009173 * t2 } else { 0.0 };
+            coefficient * sin_deg(argument)
+        })
+        .sum();
+
+    jde_to_jd(mean + correction + planetary)
+}
+
+fn lunation_estimate(jd: f64, phase: LunarPhase) -> i32 {
+    floor((jd - 2451550.09766) / SYNODIC_MONTH - phase.cycle_fraction()) as i32
+}
+
+fn next_phase_julian_day(phase: LunarPhase, jd: f64) -> f64 {
+    let mut k = lunation_estimate(jd, phase) - 1;
+    let mut event = phase_julian_day(k, phase);
+    while event <= jd {
+        k += 1;
+        event = phase_julian_day(k, phase);
+    }
+    event
+}
+
+fn previous_phase_julian_day(phase: LunarPhase, jd: f64) -> f64 {
+    let mut k = lunation_estimate(jd, phase) + 1;
+    let mut event = phase_julian_day(k, phase);
+    while event > jd {
+        k -= 1;
+        event = phase_julian_day(k, phase);
+    }
+    event
+}
+
+/// Finds the first moment of the phase strictly after the specified moment
+pub fn next_lunar_phase(phase: LunarPhase, after: UtcDateTime) -> Option<UtcDateTime> {
+    UtcDateTime::from_julian_day(next_phase_julian_day(phase, after.julian_day() + HALF_SECOND))
+}
+
+/// Finds the last moment of the phase at or before the specified moment
+pub fn previous_lunar_phase(phase: LunarPhase, before: UtcDateTime) -> Option<UtcDateTime> {
+    UtcDateTime::from_julian_day(previous_phase_julian_day(phase, before.julian_day() + HALF_SECOND))
+}
+
+/// Finds a principal phase, happening during the specified UTC day
+pub fn lunar_phase_on(date: CalendarDate) -> Option<(LunarPhase, UtcDateTime)> {
+    let day_start = date.julian_day_number() as f64 - 0.5;
+    [LunarPhase::NewMoon, LunarPhase::FirstQuarter, LunarPhase::FullMoon, LunarPhase::LastQuarter]
+        .iter()
+        .filter_map(|phase| {
+            UtcDateTime::from_julian_day(next_phase_julian_day(*phase, day_start - HALF_SECOND))
+                .map(|event| (*phase, event))
+        })
+        .find(|(_, event)| event.date() == date)
+}
+
+/// Calculates moon age, illumination and trend at the specified moment
+pub fn moon_state(instant: UtcDateTime) -> MoonState {
+    let jd = instant.julian_day();
+    let age = jd - previous_phase_julian_day(LunarPhase::NewMoon, jd);
+
+    let t = julian_centuries(jd);
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let t4 = t3 * t;
+    let d = normalize_degrees(297.8501921 + 445267.1114034 * t - 0.0018819 * t2 + t3 / 545868.0 - t4 / 113065000.0);
+    let m = 357.5291092 + 35999.0502909 * t - 0.0001536 * t2 + t3 / 24490000.0;
+    let mp = 134.9633964 + 477198.8675055 * t + 0.0087414 * t2 + t3 / 69699.0 - t4 / 14712000.0;
+    let phase_angle = 180.0 - d - 6.289 * sin_deg(mp) + 2.100 * sin_deg(m) - 1.274 * sin_deg(2.0 * d - mp)
+        - 0.658 * sin_deg(2.0 * d)
+        - 0.214 * sin_deg(2.0 * mp)
+        - 0.110 * sin_deg(d);
+    let illumination = (1.0 + cos_deg(phase_angle)) / 2.0;
+
+    MoonState {
+        age: age as f32,
+        illumination: illumination as f32,
+        // Elongation grows from 0 to 180 degrees between new and full moon
+        trend: if d < 180.0 { MoonTrend::Waxing } else { MoonTrend::Waning },
+    }
+}
 
 /// Calculates moon phase for a date.
 /// 1 - New moon, 5 - Full moon, 8 - Waning Crescent
-pub fn moon_phase(day: u32, month: u32, year: u32) -> u8 {
-    let a = (year/100) as i32;
-    let b = (a/4) as i32;
-    let c = 2 - a + b;
-    let e = (365.25 * (year as f32 + 4716.0)) as i32;
-    let f = (30.6001 * (month as f32 + 1.0)) as i32;
-    let jd = (c + day as i32 + e + f) as f32 - 1524.5;
-    let new_moons = jd/29.53;
-    let cycle_length = new_moons - (new_moons as i32) as f32;
-    if cycle_length >= 0.0 && cycle_length < 0.125 { 1 }
-    else if cycle_length>=0.125 && cycle_length < 0.25 { 2 }
-    else if cycle_length>=0.25 && cycle_length < 0.375 { 3 }
-    else if cycle_length>=0.375 && cycle_length < 0.5 { 4 }
-    else if cycle_length>=0.5 && cycle_length < 0.625 { 5 }
-    else if cycle_length>=0.625 && cycle_length < 0.75 { 6 }
-    else if cycle_length>=0.75 && cycle_length < 0.875 { 7 }
-    else /*if cycle_length>=0.875*/ { 8 }
+pub fn moon_phase(date: CalendarDate) -> u8 {
+    // Day is represented by its noon
+    let jd = date.julian_day_number() as f64;
+    let previous = previous_phase_julian_day(LunarPhase::NewMoon, jd);
+    let next = next_phase_julian_day(LunarPhase::NewMoon, jd);
+    let cycle_length = (jd - previous) / (next - previous);
+    // Each of 8 phases takes 1/8 of the cycle
+    (floor(cycle_length * 8.0) as u8).min(7) + 1
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::moon::moon_phase;
+    use crate::date::{CalendarDate, UtcDateTime};
+    use crate::moon::{lunar_phase_on, moon_phase, moon_state, next_lunar_phase, previous_lunar_phase, LunarPhase, MoonTrend};
+
+    fn at(day: u32, month: u32, year: u32, hour: u32, minute: u32) -> UtcDateTime {
+        UtcDateTime::new(CalendarDate::new(day, month, year).unwrap(), hour, minute, 0).unwrap()
+    }
+
+    fn assert_close(actual: UtcDateTime, expected: UtcDateTime) {
+        let difference = (actual.julian_day() - expected.julian_day()) * 1440.0;
+        assert!(difference.abs() <= 1.0, "{:?} is too far from {:?}", actual, expected);
+    }
 
     #[test]
     fn new_moon() {
-        assert_eq!(moon_phase(06, 11, 2021), 1);
+        assert_eq!(moon_phase(CalendarDate::new(6, 11, 2021).unwrap()), 1);
     }
 
     #[test]
     fn first_quarter() {
-        assert_eq!(moon_phase(14, 11, 2021), 3);
+        assert_eq!(moon_phase(CalendarDate::new(14, 11, 2021).unwrap()), 3);
     }
 
     #[test]
     fn full_moon() {
-        assert_eq!(moon_phase(21, 11, 2021), 5);
+        assert_eq!(moon_phase(CalendarDate::new(21, 11, 2021).unwrap()), 5);
     }
 
     #[test]
     fn third_quarter() {
-        assert_eq!(moon_phase(29, 11, 2021), 7);
+        assert_eq!(moon_phase(CalendarDate::new(29, 11, 2021).unwrap()), 7);
+    }
+
+    #[test]
+    fn meeus_new_moon_example() {
+        // Example 49.a, 1977 Feb 18 3:37:42 TD, that is 3:36:54 UT
+        let event = next_lunar_phase(LunarPhase::NewMoon, at(10, 2, 1977, 0, 0)).unwrap();
+        assert_close(event, at(18, 2, 1977, 3, 37));
+    }
+
+    #[test]
+    fn phases_of_november_2021() {
+        let start = at(1, 11, 2021, 0, 0);
+        assert_close(next_lunar_phase(LunarPhase::NewMoon, start).unwrap(), at(4, 11, 2021, 21, 14));
+        assert_close(next_lunar_phase(LunarPhase::FirstQuarter, start).unwrap(), at(11, 11, 2021, 12, 46));
+        assert_close(next_lunar_phase(LunarPhase::FullMoon, start).unwrap(), at(19, 11, 2021, 8, 57));
+        assert_close(next_lunar_phase(LunarPhase::LastQuarter, start).unwrap(), at(27, 11, 2021, 12, 28));
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn previous_phase() {
+        let full_moon = previous_lunar_phase(LunarPhase::FullMoon, at(1, 12, 2021, 0, 0)).unwrap();
+        assert_close(full_moon, at(19, 11, 2021, 8, 57));
+        assert_eq!(previous_lunar_phase(LunarPhase::FullMoon, full_moon), Some(full_moon));
+        assert!(next_lunar_phase(LunarPhase::FullMoon, full_moon).unwrap() > full_moon);
+    }
+
+    #[test]
+    fn phase_on_the_day() {
+        let (phase, time) = lunar_phase_on(CalendarDate::new(19, 11, 2021).unwrap()).unwrap();
+        assert_eq!(phase, LunarPhase::FullMoon);
+        assert_eq!(time.hour(), 8);
+        assert!(lunar_phase_on(CalendarDate::new(20, 11, 2021).unwrap()).is_none());
+        let (phase, _) = lunar_phase_on(CalendarDate::new(4, 11, 2021).unwrap()).unwrap();
+        assert_eq!(phase, LunarPhase::NewMoon);
+    }
+
+    #[test]
+    fn state_at_full_moon() {
+        let state = moon_state(at(19, 11, 2021, 9, 0));
+        assert!(state.illumination > 0.99);
+        assert!((state.age - 14.5).abs() < 0.1);
+    }
+
+    #[test]
+    fn state_at_new_moon() {
+        let state = moon_state(at(4, 11, 2021, 21, 0));
+        assert!(state.illumination < 0.01);
+        assert!(state.age > 29.0);
+        let state = moon_state(at(4, 11, 2021, 22, 0));
+        assert!(state.age < 0.1);
+    }
+
+    #[test]
+    fn state_trend() {
+        let waxing = moon_state(at(11, 11, 2021, 12, 0));
+        assert_eq!(waxing.trend, MoonTrend::Waxing);
+        assert!((waxing.illumination - 0.5).abs() < 0.05);
+        let waning = moon_state(at(27, 11, 2021, 12, 0));
+        assert_eq!(waning.trend, MoonTrend::Waning);
+        assert!((waning.illumination - 0.5).abs() < 0.05);
+    }
+}
@@ -0,0 +1,287 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Moonrise/Moonset calculation
+//!
+//! Moon position is calculated with the main terms of Meeus' "Astronomical Algorithms"
+//! chapter 47, which gives a few arcminutes precision. Horizon crossings are searched
+//! with one hour steps and refined with bisection.
+
+use crate::astro::{cos_deg, deg_to_rad, julian_centuries, normalize_degrees, sin_deg, J2000};
+use crate::date::CalendarDate;
+use libm::{asin, atan2, cos, sin, tan};
+
+/// Moonrise or moonset during a day
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoonEvent {
+    /// Event happens at the specified UTC minutes
+    At(u16),
+    /// Moon does not rise (or set) during this UTC day
+    NotToday,
+}
+
+// D, M, M', F multipliers, longitude (10^-6 degrees) and distance (10^-3 km) coefficients
+const LONGITUDE_DISTANCE_TERMS: [(i8, i8, i8, i8, f64, f64); 32] = [
+    (0, 0, 1, 0, 6288774.0, -20905355.0),
+    (2, 0, -1, 0, 1274027.0, -3699111.0),
+    (2, 0, 0, 0, 658314.0, -2955968.0),
+    (0, 0, 2, 0, 213618.0, -569925.0),
+    (0, 1, 0, 0, -185116.0, 48888.0),
+    (0, 0, 0, 2, -114332.0, -3149.0),
+    (2, 0, -2, 0, 58793.0, 246158.0),
+    (2, -1, -1, 0, 57066.0, -152138.0),
+    (2, 0, 1, 0, 53322.0, -170733.0),
+    (2, -1, 0, 0, 45758.0, -204586.0),
+    (0, 1, -1, 0, -40923.0, -129620.0),
+    (1, 0, 0, 0, -34720.0, 108743.0),
+    (0, 1, 1, 0, -30383.0, 104755.0),
+    (2, 0, 0, -2, 15327.0, 10321.0),
+    (0, 0, 1, 2, -12528.0, 0.0),
+    (0, 0, 1, -2, 10980.0, 79661.0),
+    (4, 0, -1, 0, 10675.0, -34782.0),
+    (0, 0, 3, 0, 10034.0, -23210.0),
+    (4, 0, -2, 0, 8548.0, -21636.0),
+    (2, 1, -1, 0, -7888.0, 24208.0),
+    (2, 1, 0, 0, -6766.0, 30824.0),
+    (1, 0, -1, 0, -5163.0, -8379.0),
+    (1, 1, 0, 0, 4987.0, -16675.0),
+    (2, -1, 1, 0, 4036.0, -12831.0),
+    (2, 0, 2, 0, 3994.0, -10445.0),
+    (4, 0, 0, 0, 3861.0, -11650.0),
+    (2, 0, -3, 0, 3665.0, 14403.0),
+    (0, 1, -2, 0, -2689.0, -7003.0),
+    (2, 0, -1, 2, -2602.0, 0.0),
+    (2, -1, -2, 0, 2390.0, 10056.0),
+    (1, 0, 1, 0, -2348.0, 6322.0),
+    (2, -2, 0, 0, 2236.0, -9884.0),
+];
+
+// D, M, M', F multipliers and latitude (10^-6 degrees) coefficients
+const LATITUDE_TERMS: [(i8, i8, i8, i8, f64); 20] = [
+    (0, 0, 0, 1, 5128122.0),
+    (0, 0, 1, 1, 280602.0),
+    (0, 0, 1, -1, 277693.0),
+    (2, 0, 0, -1, 173237.0),
+    (2, 0, -1, 1, 55413.0),
+    (2, 0, -1, -1, 46271.0),
+    (2, 0, 0, 1, 32573.0),
+    (0, 0, 2, 1, 17198.0),
+    (2, 0, 1, -1, 9266.0),
+    (0, 0, 2, -1, 8822.0),
+    (2, -1, 0, -1, 8216.0),
+    (2, 0, -2, -1, 4324.0),
+    (2, 0, 1, 1, 4200.0),
+    (2, 1, 0, -1, -3359.0),
+    (2, -1, -1, 1, 2463.0),
+    (2, -1, 0, 1, 2211.0),
+    (2, -1, -1, -1, 2065.0),
+    (0, 1, -1, -1, -1870.0),
+    (4, 0, -1, -1, 1828.0),
+    (0, 1, 0, 1, -1794.0),
+];
+
+/// Apparent equatorial position of the moon
+struct MoonPosition {
+    /// Right ascension in degrees
+    right_ascension: f64,
+    /// Declination in degrees
+    declination: f64,
+    /// Equatorial horizontal parallax in degrees
+    parallax: f64,
+}
+
+fn eccentricity_factor(m: i8, e: f64) -> f64 {
+    match m.abs() {
+        1 => e,
+        2 => e * e,
+        _ => 1.0,
+    }
+}
+
+fn moon_position(jd: f64) -> MoonPosition {
+    let t = julian_centuries(jd);
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let t4 = t3 * t;
+    let lp = 218.3164477 + 481267.88123421 * t - 0.0015786 * t2 + t3 / 538841.0 - t4 / 65194000.0;
+    let d = 297.8501921 + 445267.1114034 * t - 0.0018819 * t2 + t3 / 545868.0 - t4 / 113065000.0;
+    let m = 357.5291092 + 35999.0502909 * t - 0.0001536 * t2 + t3 / 24490000.0;
+    let mp = 134.9633964 + 477198.8675055 * t + 0.0087414 * t2 + t3 / 69699.0 - t4 / 14712000.0;
+    let f = 93.2720950 + 483202.0175233 * t - 0.0036539 * t2 - t3 / 3526000.0 + t4 / 863310000.0;
+    let a1 = 119.75 + 131.849 * t;
+    let a2 = 53.09 + 479264.290 * t;
+    let a3 = 313.45 + 481266.484 * t;
+    let e = 1.0 - 0.002516 * t - 0.0000074 * t2;
+
+    let argument = |cd: i8, cm: i8, cmp: i8, cf: i8| cd as f64 * d + cm as f64 * m + cmp as f64 * mp + cf as f64 * f;
+
+    let mut sum_l = 3958.0 * sin_deg(a1) + 1962.0 * sin_deg(lp - f) + 318.0 * sin_deg(a2);
+    let mut sum_r = 0.0;
+    for (cd, cm, cmp, cf, l, r) in LONGITUDE_DISTANCE_TERMS.iter() {
+        let angle = argument(*cd, *cm, *cmp, *cf);
+        let factor = eccentricity_factor(*cm, e);
+        sum_l += l * factor * sin_deg(angle);
+        sum_r += r * factor * cos_deg(angle);
+    }
+
+    let mut sum_b = -2235.0 * sin_deg(lp) + 382.0 * sin_deg(a3) + 175.0 * sin_deg(a1 - f) + 175.0 * sin_deg(a1 + f)
+        + 127.0 * sin_deg(lp - mp)
+        - 115.0 * sin_deg(lp + mp);
+    for (cd, cm, cmp, cf, b) in LATITUDE_TERMS.iter() {
+        sum_b += b * eccentricity_factor(*cm, e) * sin_deg(argument(*cd, *cm, *cmp, *cf));
+    }
+
+    let longitude = deg_to_rad(normalize_degrees(lp + sum_l / 1_000_000.0));
+    let latitude = deg_to_rad(sum_b / 1_000_000.0);
+    let distance = 385000.56 + sum_r / 1000.0;
+    let obliquity = deg_to_rad(23.439291 - 0.0130042 * t);
+
+    let right_ascension = atan2(sin(longitude) * cos(obliquity) - tan(latitude) * sin(obliquity), cos(longitude));
+    let declination = asin(sin(latitude) * cos(obliquity) + cos(latitude) * sin(obliquity) * sin(longitude));
+    MoonPosition {
+        right_ascension: normalize_degrees(right_ascension.to_degrees()),
+        declination: declination.to_degrees(),
+        parallax: asin(6378.14 / distance).to_degrees(),
+    }
+}
+
+/// Moon altitude above the rise/set altitude, in degrees
+fn altitude_above_horizon(jd: f64, lon: f64, lat: f64) -> f64 {
+    let position = moon_position(jd);
+    let t = julian_centuries(jd);
+    let sidereal_time = 280.46061837 + 360.98564736629 * (jd - J2000) + 0.000387933 * t * t - t * t * t / 38710000.0;
+    let hour_angle = sidereal_time + lon - position.right_ascension;
+    let sin_altitude = sin_deg(lat) * sin_deg(position.declination)
+        + cos_deg(lat) * cos_deg(position.declination) * cos_deg(hour_angle);
+    // Standard moon altitude accounts for parallax, refraction and semidiameter
+    let standard_altitude = 0.7275 * position.parallax - 0.5667;
+    asin(sin_altitude).to_degrees() - standard_altitude
+}
+
+fn moonpos(date: CalendarDate, lon: f32, lat: f32, rising: bool) -> MoonEvent {
+    let day_start = date.julian_day_number() as f64 - 0.5;
+    let (lon, lat) = (lon as f64, lat as f64);
+    let altitude = |hours: f64| altitude_above_horizon(day_start + hours / 24.0, lon, lat);
+    let crossed = |before: f64, after: f64| if rising { before < 0.0 && after >= 0.0 } else { before >= 0.0 && after < 0.0 };
+
+    let mut previous = altitude(0.0);
+    for hour in 1..=24 {
+        let current = altitude(hour as f64);
+        if crossed(previous, current) {
+            // Crossing is inside of that hour, narrow it down to less than a minute
+            let (mut start, mut end) = ((hour - 1) as f64, hour as f64);
+            let mut start_altitude = previous;
+            while end - start > 0.5 / 60.0 {
+                let middle = (start + end) / 2.0;
+                let middle_altitude = altitude(middle);
+                if crossed(start_altitude, middle_altitude) {
+                    end = middle;
+                } else {
+                    start = middle;
+                    start_altitude = middle_altitude;
+                }
+            }
+            let minutes = ((start + end) / 2.0 * 60.0) as u16;
+            return if minutes < 1440 { MoonEvent::At(minutes) } else { MoonEvent::NotToday };
+        }
+        previous = current;
+    }
+    MoonEvent::NotToday
+}
+
+/// Calculates moonrise for a location and date
+/// Moonrise in UTC minutes
+pub fn moonrise(date: CalendarDate, lon: f32, lat: f32) -> MoonEvent {
+    moonpos(date, lon, lat, true)
+}
+
+/// Calculates moonset for a location and date
+/// Moonset in UTC minutes
+pub fn moonset(date: CalendarDate, lon: f32, lat: f32) -> MoonEvent {
+    moonpos(date, lon, lat, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::date::CalendarDate;
+    use crate::moonrise::{altitude_above_horizon, moon_position, moonrise, moonset, MoonEvent};
+
+    const LON: f32 = 24.93545;
+    const LAT: f32 = 60.16952;
+
+    fn minutes(event: MoonEvent) -> u16 {
+        match event {
+            MoonEvent::At(m) => m,
+            MoonEvent::NotToday => panic!("Event expected"),
+        }
+    }
+
+    #[test]
+    fn meeus_position_example() {
+        // Example 47.a, 1992 April 12 0h TD
+        let position = moon_position(2448724.5);
+        assert!((position.right_ascension - 134.688470).abs() < 0.05);
+        assert!((position.declination - 13.768368).abs() < 0.05);
+        assert!((position.parallax - 0.991990).abs() < 0.001);
+    }
+
+    #[test]
+    fn rise_is_a_horizon_crossing() {
+        let date = CalendarDate::new(3, 11, 2021).unwrap();
+        let rise = minutes(moonrise(date, LON, LAT)) as f64;
+        let jd = date.julian_day_number() as f64 - 0.5 + rise / 1440.0;
+        assert!(altitude_above_horizon(jd - 2.0 / 1440.0, LON as f64, LAT as f64) < 0.0);
+        assert!(altitude_above_horizon(jd + 2.0 / 1440.0, LON as f64, LAT as f64) > 0.0);
+    }
+
+    #[test]
+    fn set_is_a_horizon_crossing() {
+        let date = CalendarDate::new(3, 11, 2021).unwrap();
+        let set = minutes(moonset(date, LON, LAT)) as f64;
+        let jd = date.julian_day_number() as f64 - 0.5 + set / 1440.0;
+        assert!(altitude_above_horizon(jd - 2.0 / 1440.0, LON as f64, LAT as f64) > 0.0);
+        assert!(altitude_above_horizon(jd + 2.0 / 1440.0, LON as f64, LAT as f64) < 0.0);
+    }
+
+    #[test]
+    fn full_moon_rises_around_sunset() {
+        // Full moon of 19th of November 2021, sun sets at about 13:30 UTC in Helsinki
+        let rise = minutes(moonrise(CalendarDate::new(19, 11, 2021).unwrap(), LON, LAT));
+        assert!(rise > 12 * 60 && rise < 15 * 60);
+    }
+
+    #[test]
+    fn new_moon_rises_around_sunrise() {
+        // New moon of 4th of November 2021, sun rises at about 6:00 UTC in Helsinki
+        let rise = minutes(moonrise(CalendarDate::new(4, 11, 2021).unwrap(), LON, LAT));
+        assert!(rise > 4 * 60 && rise < 8 * 60);
+    }
+
+    #[test]
+    fn days_without_rise_and_set() {
+        // Moon rises about 50 minutes later every day, so once a month a day without rise
+        // and a day without set is expected
+        let mut date = CalendarDate::new(1, 11, 2021).unwrap();
+        let mut no_rise = 0;
+        let mut no_set = 0;
+        for _ in 0..30 {
+            if moonrise(date, LON, LAT) == MoonEvent::NotToday {
+                no_rise += 1;
+            }
+            if moonset(date, LON, LAT) == MoonEvent::NotToday {
+                no_set += 1;
+            }
+            date = date.next_day().unwrap();
+        }
+        assert!(no_rise >= 1);
+        assert!(no_set >= 1);
+    }
+
+    #[test]
+    fn circumpolar_moon() {
+        // Moon declination is above +25 degrees at 1st of July 2024, so it never sets in Svalbard
+        let date = CalendarDate::new(1, 7, 2024).unwrap();
+        assert_eq!(moonset(date, 15.6, 78.2), MoonEvent::NotToday);
+        assert_eq!(moonrise(date, 15.6, 78.2), MoonEvent::NotToday);
+    }
+}
@@ -0,0 +1,160 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Equinoxes and solstices, Meeus chapter 27
+
+use crate::astro::{cos_deg, jde_to_jd, julian_centuries};
+use crate::date::{CalendarDate, UtcDateTime};
+
+/// First year, supported by the season calculation
+pub const MIN_SEASON_YEAR: u32 = 1900;
+/// Last year, supported by the season calculation
+pub const MAX_SEASON_YEAR: u32 = 2100;
+
+/// Turning point of the year
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeasonEvent {
+    /// March equinox, spring starts in the northern hemisphere
+    MarchEquinox,
+    /// June solstice, summer starts in the northern hemisphere
+    JuneSolstice,
+    /// September equinox, autumn starts in the northern hemisphere
+    SeptemberEquinox,
+    /// December solstice, winter starts in the northern hemisphere
+    DecemberSolstice,
+}
+
+/// Periodic terms A, B and C of the table 27.C
+const PERIODIC_TERMS: [(f64, f64, f64); 24] = [
+    (485.0, 324.96, 1934.136),
+    (203.0, 337.23, 32964.467),
+    (199.0, 342.08, 20.186),
+    (182.0, 27.85, 445267.112),
+    (156.0, 73.14, 45036.886),
+    (136.0, 171.52, 22518.443),
+    (77.0, 222.54, 65928.934),
+    (74.0, 296.72, 3034.906),
+    (70.0, 243.58, 9037.513),
+    (58.0, 119.81, 33718.147),
+    (52.0, 297.17, 150.678),
+    (50.0, 21.02, 2281.226),
+    (45.0, 247.54, 29929.562),
+    (44.0, 325.15, 31555.956),
+    (29.0, 60.93, 4443.417),
+    (18.0, 155.12, 67555.328),
+    (17.0, 288.79, 4562.452),
+    (16.0, 198.04, 62894.029),
+    (14.0, 199.76, 31436.921),
+    (12.0, 95.39, 14577.848),
+    (12.0, 287.11, 31931.756),
+    (12.0, 320.81, 34777.259),
+    (9.0, 227.73, 1222.114),
+    (8.0, 15.45, 16859.074),
+];
+
+/// Mean event time (table 27.B, years 1000-3000) as a Julian Ephemeris Day
+fn mean_event(event: SeasonEvent, year: u32) -> f64 {
+    let y = (year as f64 - 2000.0) / 1000.0;
+    let y2 = y * y;
+    let y3 = y2 * y;
+    let y4 = y3 * y;
+    match event {
+        SeasonEvent::MarchEquinox => 2451623.80984 + 365242.37404 * y + 0.05169 * y2 - 0.00411 * y3 - 0.00057 * y4,
+        SeasonEvent::JuneSolstice => 2451716.56767 + 365241.62603 * y + 0.00325 * y2 + 0.00888 * y3 - 0.00030 * y4,
+        SeasonEvent::SeptemberEquinox => 2451810.21715 + 365242.01767 * y - 0.11575 * y2 + 0.00337 * y3 + 0.00078 * y4,
+        SeasonEvent::DecemberSolstice => 2451900.05952 + 365242.74049 * y - 0.06223 * y2 - 0.00823 * y3 + 0.00032 * y4,
+    }
+}
+
+/// Event time as a Julian Ephemeris Day
+fn event_julian_ephemeris_day(event: SeasonEvent, year: u32) -> f64 {
+    let jde0 = mean_event(event, year);
+    let t = julian_centuries(jde0);
+    let w = 35999.373 * t - 2.47;
+    let delta_lambda = 1.0 + 0.0334 * cos_deg(w) + 0.0007 * cos_deg(2.0 * w);
+    let s: f64 = PERIODIC_TERMS.iter().map(|(a, b, c)| a * cos_deg(b + c * t)).sum();
+    jde0 + 0.00001 * s / delta_lambda
+}
+
+/// Calculates UTC moment of the equinox or solstice in the specified year
+///
+/// Returns None for years outside of 1900-2100
+pub fn season_event(event: SeasonEvent, year: u32) -> Option<UtcDateTime> {
+    if !(MIN_SEASON_YEAR..=MAX_SEASON_YEAR).contains(&year) {
+        return None
+    }
+    UtcDateTime::from_julian_day(jde_to_jd(event_julian_ephemeris_day(event, year)))
+}
+
+/// Finds an equinox or solstice, happening during the specified UTC day
+pub fn season_event_on(date: CalendarDate) -> Option<(SeasonEvent, UtcDateTime)> {
+    let candidate = match date.month() {
+        3 => SeasonEvent::MarchEquinox,
+        6 => SeasonEvent::JuneSolstice,
+        9 => SeasonEvent::SeptemberEquinox,
+        12 => SeasonEvent::DecemberSolstice,
+        _ => return None,
+    };
+    season_event(candidate, date.year())
+        .filter(|instant| instant.date() == date)
+        .map(|instant| (candidate, instant))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes_between(a: UtcDateTime, b: UtcDateTime) -> f64 {
+        (a.julian_day() - b.julian_day()).abs() * 1440.0
+    }
+
+    fn instant(day: u32, month: u32, year: u32, hour: u32, minute: u32) -> UtcDateTime {
+        UtcDateTime::new(CalendarDate::new(day, month, year).unwrap(), hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn meeus_example() {
+        // Example 27.a: June solstice 1962 at JDE 2437837.39245
+        assert!((event_julian_ephemeris_day(SeasonEvent::JuneSolstice, 1962) - 2437837.39245).abs() < 0.00001);
+    }
+
+    #[test]
+    fn events_of_2022() {
+        let expected = [
+            (SeasonEvent::MarchEquinox, instant(20, 3, 2022, 15, 33)),
+            (SeasonEvent::JuneSolstice, instant(21, 6, 2022, 9, 13)),
+            (SeasonEvent::SeptemberEquinox, instant(23, 9, 2022, 1, 3)),
+            (SeasonEvent::DecemberSolstice, instant(21, 12, 2022, 21, 48)),
+        ];
+        for (event, moment) in expected.iter() {
+            let actual = season_event(*event, 2022).unwrap();
+            assert!(minutes_between(actual, *moment) < 2.0, "{:?} {:?}", event, actual);
+        }
+    }
+
+    #[test]
+    fn supported_range() {
+        assert!(season_event(SeasonEvent::MarchEquinox, 1899).is_none());
+        assert!(season_event(SeasonEvent::MarchEquinox, 1900).is_some());
+        assert!(season_event(SeasonEvent::DecemberSolstice, 2100).is_some());
+        assert!(season_event(SeasonEvent::DecemberSolstice, 2101).is_none());
+    }
+
+    #[test]
+    fn events_stay_in_their_months() {
+        for year in MIN_SEASON_YEAR..=MAX_SEASON_YEAR {
+            assert_eq!(season_event(SeasonEvent::MarchEquinox, year).unwrap().date().month(), 3);
+            assert_eq!(season_event(SeasonEvent::JuneSolstice, year).unwrap().date().month(), 6);
+            assert_eq!(season_event(SeasonEvent::SeptemberEquinox, year).unwrap().date().month(), 9);
+            assert_eq!(season_event(SeasonEvent::DecemberSolstice, year).unwrap().date().month(), 12);
+        }
+    }
+
+    #[test]
+    fn event_on_date() {
+        let (event, _) = season_event_on(CalendarDate::new(21, 6, 2022).unwrap()).unwrap();
+        assert_eq!(event, SeasonEvent::JuneSolstice);
+        assert!(season_event_on(CalendarDate::new(22, 6, 2022).unwrap()).is_none());
+        assert!(season_event_on(CalendarDate::new(21, 5, 2022).unwrap()).is_none());
+    }
+}
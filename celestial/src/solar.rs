@@ -0,0 +1,242 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Precise solar position, based on the NOAA solar calculator
+//!
+//! Uses Meeus' low accuracy solar coordinates in double precision,
+//! that gives about 0.01° of accuracy for the years 1800-2100
+
+use crate::astro::{cos_deg, deg_to_rad, julian_centuries, normalize_degrees, sin_deg};
+use crate::date::{CalendarDate, UtcDateTime};
+use crate::sun::Horizon;
+use libm::{acos, asin, tan};
+
+/// Sun position as seen by an observer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolarPosition {
+    /// Azimuth in degrees, measured clockwise from the North
+    pub azimuth: f64,
+    /// Geometric elevation of the sun's center in degrees
+    pub elevation: f64,
+    /// Elevation corrected for the atmospheric refraction in degrees
+    pub apparent_elevation: f64,
+}
+
+/// Sun parameters, that do not depend on the observer location
+struct SolarCoordinates {
+    /// Apparent declination in degrees
+    declination: f64,
+    /// Equation of time in minutes
+    equation_of_time: f64,
+}
+
+fn rad_to_deg(rad: f64) -> f64 {
+    rad * (180.0 / core::f64::consts::PI)
+}
+
+fn solar_coordinates(jd: f64) -> SolarCoordinates {
+    let t = julian_centuries(jd);
+    let mean_longitude = normalize_degrees(280.46646 + t * (36000.76983 + t * 0.0003032));
+    let mean_anomaly = 357.52911 + t * (35999.05029 - 0.0001537 * t);
+    let eccentricity = 0.016708634 - t * (0.000042037 + 0.0000001267 * t);
+    let center = sin_deg(mean_anomaly) * (1.914602 - t * (0.004817 + 0.000014 * t))
+        + sin_deg(2.0 * mean_anomaly) * (0.019993 - 0.000101 * t)
+        + sin_deg(3.0 * mean_anomaly) * 0.000289;
+    let omega = 125.04 - 1934.136 * t;
+    let apparent_longitude = mean_longitude + center - 0.00569 - 0.00478 * sin_deg(omega);
+    let mean_obliquity = 23.0 + (26.0 + (21.448 - t * (46.815 + t * (0.00059 - t * 0.001813))) / 60.0) / 60.0;
+    let obliquity = mean_obliquity + 0.00256 * cos_deg(omega);
+
+    let declination = rad_to_deg(asin(sin_deg(obliquity) * sin_deg(apparent_longitude)));
+
+    let y = tan(deg_to_rad(obliquity) / 2.0) * tan(deg_to_rad(obliquity) / 2.0);
+    let equation_of_time = 4.0 * rad_to_deg(
+        y * sin_deg(2.0 * mean_longitude) - 2.0 * eccentricity * sin_deg(mean_anomaly)
+            + 4.0 * eccentricity * y * sin_deg(mean_anomaly) * cos_deg(2.0 * mean_longitude)
+            - 0.5 * y * y * sin_deg(4.0 * mean_longitude)
+            - 1.25 * eccentricity * eccentricity * sin_deg(2.0 * mean_anomaly),
+    );
+
+    SolarCoordinates { declination, equation_of_time }
+}
+
+/// Atmospheric refraction in degrees for the given geometric elevation
+fn refraction(elevation: f64) -> f64 {
+    let arcseconds = if elevation > 85.0 {
+        0.0
+    } else if elevation > 5.0 {
+        let te = tan(deg_to_rad(elevation));
+        58.1 / te - 0.07 / (te * te * te) + 0.000086 / (te * te * te * te * te)
+    } else if elevation > -0.575 {
+        1735.0 + elevation * (-518.2 + elevation * (103.4 + elevation * (-12.79 + elevation * 0.711)))
+    } else {
+        -20.772 / tan(deg_to_rad(elevation))
+    };
+    arcseconds / 3600.0
+}
+
+/// Calculates equation of time for an instant
+/// Difference between apparent and mean solar time in minutes
+pub fn equation_of_time(instant: UtcDateTime) -> f64 {
+    solar_coordinates(instant.julian_day()).equation_of_time
+}
+
+/// Calculates apparent declination of the sun for an instant
+/// Declination in degrees, positive values are north of the celestial equator
+pub fn solar_declination(instant: UtcDateTime) -> f64 {
+    solar_coordinates(instant.julian_day()).declination
+}
+
+/// Calculates sun position for an instant and a location
+pub fn solar_position(instant: UtcDateTime, lon: f64, lat: f64) -> SolarPosition {
+    let coordinates = solar_coordinates(instant.julian_day());
+    let minutes = instant.minute_of_day() as f64 + instant.second() as f64 / 60.0;
+    // True solar time in degrees is the hour angle, counted from the midnight
+    let true_solar_time = normalize_degrees((minutes + coordinates.equation_of_time) / 4.0 + lon);
+    let hour_angle = true_solar_time - 180.0;
+
+    let cos_zenith = sin_deg(lat) * sin_deg(coordinates.declination)
+        + cos_deg(lat) * cos_deg(coordinates.declination) * cos_deg(hour_angle);
+    let zenith = rad_to_deg(acos(cos_zenith.clamp(-1.0, 1.0)));
+    let elevation = 90.0 - zenith;
+
+    let cos_azimuth = (sin_deg(lat) * cos_deg(zenith) - sin_deg(coordinates.declination)) / (cos_deg(lat) * sin_deg(zenith));
+    let azimuth_angle = rad_to_deg(acos(cos_azimuth.clamp(-1.0, 1.0)));
+    let azimuth = if hour_angle > 0.0 {
+        normalize_degrees(azimuth_angle + 180.0)
+    } else {
+        normalize_degrees(540.0 - azimuth_angle)
+    };
+
+    SolarPosition { azimuth, elevation, apparent_elevation: elevation + refraction(elevation) }
+}
+
+/// Calculates sunrise (sign -1) or sunset (sign 1) as a Julian day
+///
+/// Sun coordinates are refined once at the approximate event time
+fn crossing(date: CalendarDate, lon: f64, lat: f64, horizon: Horizon, sign: f64) -> Option<f64> {
+    let midnight = date.julian_day_number() as f64 - 0.5;
+    let zenith = horizon.zenith() as f64;
+    let mut minutes = 720.0 - 4.0 * lon;
+    for _ in 0..2 {
+        let coordinates = solar_coordinates(midnight + minutes / 1440.0);
+        let cos_h = (libm::cos(zenith) - sin_deg(lat) * sin_deg(coordinates.declination))
+            / (cos_deg(lat) * cos_deg(coordinates.declination));
+        if !(-1.0..=1.0).contains(&cos_h) {
+            return None
+        }
+        let hour_angle = rad_to_deg(acos(cos_h));
+        minutes = 720.0 - 4.0 * (lon - sign * hour_angle) - coordinates.equation_of_time;
+    }
+    Some(midnight + minutes / 1440.0)
+}
+
+fn utc_minutes(jd: f64) -> Option<u16> {
+    UtcDateTime::from_julian_day(jd).map(|instant| instant.minute_of_day())
+}
+
+/// Calculates sunrise for a location and date using the precise model
+/// Sunrise in UTC minutes
+pub fn precise_sunrise(date: CalendarDate, lon: f64, lat: f64, horizon: Horizon) -> Option<u16> {
+    crossing(date, lon, lat, horizon, -1.0).and_then(utc_minutes)
+}
+
+/// Calculates sunset for a location and date using the precise model
+/// Sunset in UTC minutes
+pub fn precise_sunset(date: CalendarDate, lon: f64, lat: f64, horizon: Horizon) -> Option<u16> {
+    crossing(date, lon, lat, horizon, 1.0).and_then(utc_minutes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instant(day: u32, month: u32, year: u32, hour: u32, minute: u32, second: u32) -> UtcDateTime {
+        UtcDateTime::new(CalendarDate::new(day, month, year).unwrap(), hour, minute, second).unwrap()
+    }
+
+    #[test]
+    fn declination_meeus_example() {
+        // Meeus, Astronomical Algorithms, example 25.a: 1992 October 13.0
+        let declination = solar_declination(instant(13, 10, 1992, 0, 0, 0));
+        assert!((declination - -7.78507).abs() < 0.001, "{}", declination);
+    }
+
+    #[test]
+    fn equation_of_time_meeus_example() {
+        // Meeus, Astronomical Algorithms, example 28.a: 1992 October 13.0, 13m42.6s
+        let eot = equation_of_time(instant(13, 10, 1992, 0, 0, 0));
+        assert!((eot - 13.71).abs() < 0.05, "{}", eot);
+    }
+
+    #[test]
+    fn equation_of_time_extremes() {
+        // Sun dial is slow in February and fast in November
+        assert!((equation_of_time(instant(11, 2, 2022, 12, 0, 0)) - -14.2).abs() < 0.1);
+        assert!((equation_of_time(instant(3, 11, 2022, 12, 0, 0)) - 16.4).abs() < 0.1);
+    }
+
+    #[test]
+    fn declination_at_solstices() {
+        assert!((solar_declination(instant(21, 6, 2022, 9, 14, 0)) - 23.4366).abs() < 0.01);
+        assert!((solar_declination(instant(21, 12, 2022, 21, 48, 0)) - -23.4366).abs() < 0.01);
+    }
+
+    #[test]
+    fn position_spa_example() {
+        // Reda & Andreas, Solar Position Algorithm for Solar Radiation Applications,
+        // NREL/TP-560-34302, example: 2003-10-17 12:30:30 at UTC-7, Golden, Colorado.
+        // Topocentric zenith 50.11162°, azimuth 194.34024°
+        let position = solar_position(instant(17, 10, 2003, 19, 30, 30), -105.1786, 39.742476);
+        assert!((position.azimuth - 194.34024).abs() < 0.05, "{}", position.azimuth);
+        assert!((90.0 - position.apparent_elevation - 50.11162).abs() < 0.05, "{}", position.apparent_elevation);
+    }
+
+    #[test]
+    fn position_quadrants() {
+        // Helsinki: east before noon, south at noon, west after noon
+        let morning = solar_position(instant(21, 6, 2022, 4, 0, 0), 24.94, 60.17);
+        let noon = solar_position(instant(21, 6, 2022, 10, 22, 0), 24.94, 60.17);
+        let evening = solar_position(instant(21, 6, 2022, 16, 0, 0), 24.94, 60.17);
+        assert!(morning.azimuth > 45.0 && morning.azimuth < 135.0);
+        assert!((noon.azimuth - 180.0).abs() < 1.0);
+        assert!((noon.elevation - 53.27).abs() < 0.05);
+        assert!(evening.azimuth > 225.0 && evening.azimuth < 315.0);
+    }
+
+    #[test]
+    fn refraction_lifts_the_sun() {
+        // About 35' at the horizon, 1' at 45° and nothing at the zenith
+        assert!((refraction(0.0) - 0.48).abs() < 0.01);
+        assert!((refraction(45.0) - 58.0 / 3600.0).abs() < 0.001);
+        assert_eq!(refraction(90.0), 0.0);
+        let position = solar_position(instant(17, 10, 2003, 19, 30, 30), -105.1786, 39.742476);
+        assert!(position.apparent_elevation > position.elevation);
+    }
+
+    #[test]
+    fn sunrise_spa_example() {
+        // Same example: sunrise at 06:12:43 local time
+        let date = CalendarDate::new(17, 10, 2003).unwrap();
+        let rise = precise_sunrise(date, -105.1786, 39.742476, Horizon::Official).unwrap();
+        assert_eq!(rise, 13 * 60 + 12);
+    }
+
+    #[test]
+    fn sun_is_at_horizon_at_sunrise_and_sunset() {
+        let date = CalendarDate::new(11, 2, 2022).unwrap();
+        let rise = precise_sunrise(date, 24.14015, 60.09737, Horizon::Official).unwrap();
+        let set = precise_sunset(date, 24.14015, 60.09737, Horizon::Official).unwrap();
+        for event in [rise, set] {
+            let position = solar_position(UtcDateTime::new(date, (event / 60) as u32, (event % 60) as u32, 0).unwrap(), 24.14015, 60.09737);
+            assert!((position.elevation - -50.0 / 60.0).abs() < 0.15, "{}", position.elevation);
+        }
+    }
+
+    #[test]
+    fn no_sunrise_in_polar_night() {
+        let date = CalendarDate::new(21, 12, 2022).unwrap();
+        assert!(precise_sunrise(date, 27.02, 69.91, Horizon::Official).is_none());
+        assert!(precise_sunset(date, 27.02, 69.91, Horizon::Official).is_none());
+    }
+}
@@ -3,13 +3,76 @@
 
 //! Sunrise/Sunset calculation
 
+use crate::date::CalendarDate;
 use core::f32::consts::PI;
+#[allow(unused_imports)]
 use micromath::F32Ext;
 
-const ZENITH: f32 = (PI/180.0)*96.0; // Civil twilight
+/// Sun position, that is considered a sunrise or a sunset
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Horizon {
+    /// Official sunrise/sunset, sun's center is 50' below the horizon
+    Official,
+    /// Civil twilight, sun's center is 6° below the horizon
+    Civil,
+    /// Nautical twilight, sun's center is 12° below the horizon
+    Nautical,
+    /// Astronomical twilight, sun's center is 18° below the horizon
+    Astronomical,
+    /// Custom altitude of the sun's center in degrees, negative values are below the horizon
+    Custom(f32),
+}
 
-fn sunpos(day: u32, month: u32, year: u32, lon: f32, lat: f32, t_func: fn(u16, f32) -> f32, h_func: fn(f32) -> f32) -> Option<u16> {
-    let year_day = day_of_the_year(day, month, year);
+impl Horizon {
+    /// Sun zenith angle in radians
+    pub(crate) fn zenith(self) -> f32 {
+        let degrees = match self {
+            Horizon::Official => 90.0 + 50.0 / 60.0,
+            Horizon::Civil => 96.0,
+            Horizon::Nautical => 102.0,
+            Horizon::Astronomical => 108.0,
+            Horizon::Custom(altitude) => 90.0 - altitude,
+        };
+        deg_to_rad(degrees)
+    }
+}
+
+/// Sunrise and sunset of a day
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SunEvents {
+    /// Sun rises and sets, both values are in UTC minutes
+    Normal {
+        /// Sunrise in UTC minutes
+        rise: u16,
+        /// Sunset in UTC minutes
+        set: u16,
+    },
+    /// Sun stays above the horizon for the whole day
+    PolarDay,
+    /// Sun stays below the horizon for the whole day
+    PolarNight,
+}
+
+/// Sun crossing of the horizon
+enum SunCrossing {
+    /// Crossing in fractional UTC hours
+    At(f32),
+    AlwaysAbove,
+    AlwaysBelow,
+}
+
+impl SunCrossing {
+    fn hours(self) -> Option<f32> {
+        match self {
+            SunCrossing::At(hours) => Some(hours),
+            _ => None
+        }
+    }
+}
+
+/// Calculates sunrise (or sunset) in fractional UTC hours
+fn sunpos(date: CalendarDate, lon: f32, lat: f32, horizon: Horizon, t_func: fn(u16, f32) -> f32, h_func: fn(f32) -> f32) -> SunCrossing {
+    let year_day = date.day_of_year();
     let lng_hour = lon/15.0; //longitude hour
     let t = t_func(year_day, lng_hour);
     let m = (0.9856 * t) - 3.289; //sun's mean anomaly
@@ -21,35 +84,89 @@ fn sunpos(day: u32, month: u32, year: u32, lon: f32, lat: f32, t_func: fn(u16, f
     let ra_adjusted = (ra + (l_quadrant - ra_quadrant))/15.0;
     let sin_dec = 0.39782 * f32::sin(deg_to_rad(l_adjusted));
     let cos_dec = f32::cos(f32::asin(sin_dec));
-    let cos_h = (f32::cos(ZENITH) - (sin_dec * f32::sin(deg_to_rad(lat)))) / (cos_dec * f32::cos(deg_to_rad(lat)));
+    let cos_h = (f32::cos(horizon.zenith()) - (sin_dec * f32::sin(deg_to_rad(lat)))) / (cos_dec * f32::cos(deg_to_rad(lat)));
 
-    if cos_h > 1.0 || cos_h < -1.0 { None } else {
+    if cos_h > 1.0 { SunCrossing::AlwaysBelow }
+    else if cos_h < -1.0 { SunCrossing::AlwaysAbove }
+    else {
         let h = h_func(cos_h);
         let lmt = h + ra_adjusted - (0.06571 * t) - 6.622;
         let utc = lmt - lng_hour;
         let utc_adjusted = if utc <0.0 { utc + 24.0 } else if utc > 24.0 { utc - 24.0 } else { utc };
-        Some((utc_adjusted * 60.0) as u16)
+        SunCrossing::At(utc_adjusted)
+    }
+}
+
+fn sunrise_hours(date: CalendarDate, lon: f32, lat: f32, horizon: Horizon) -> SunCrossing {
+    sunpos(date, lon, lat, horizon, |year_day,lng_hour| -> f32 {year_day as f32 + ((6.0 - lng_hour) / 24.0)}, |cos_h| -> f32 {(360.0-rad_to_deg(f32::acos(cos_h)))/15.0})
+}
+
+fn sunset_hours(date: CalendarDate, lon: f32, lat: f32, horizon: Horizon) -> SunCrossing {
+    sunpos(date, lon, lat, horizon, |year_day,lng_hour| -> f32 {year_day as f32 + ((18.0 - lng_hour) / 24.0)}, |cos_h| -> f32 {rad_to_deg(f32::acos(cos_h))/15.0})
+}
+
+fn day_length_hours(date: CalendarDate, lon: f32, lat: f32, horizon: Horizon) -> f32 {
+    match (sunrise_hours(date, lon, lat, horizon), sunset_hours(date, lon, lat, horizon)) {
+        // Sunset could happen on the next UTC day
+        (SunCrossing::At(rise), SunCrossing::At(set)) => if set < rise { set + 24.0 - rise } else { set - rise },
+        (SunCrossing::AlwaysBelow, _) | (_, SunCrossing::AlwaysBelow) => 0.0,
+        _ => 24.0
     }
 }
 
 /// Calculates sunrise for a location and date
 /// Sunrise in UTC minutes
-pub fn sunrise(day: u32, month: u32, year: u32, lon: f32, lat: f32) -> Option<u16> {
-    sunpos(day, month, year, lon, lat, |year_day,lng_hour| -> f32 {year_day as f32 + ((6.0 - lng_hour) / 24.0)}, |cos_h| -> f32 {(360.0-rad_to_deg(f32::acos(cos_h)))/15.0})
+pub fn sunrise(date: CalendarDate, lon: f32, lat: f32, horizon: Horizon) -> Option<u16> {
+    sunrise_hours(date, lon, lat, horizon).hours().map(|h| (h * 60.0) as u16)
 }
 
 /// Calculates sunset for a location and date
 /// Snset in UTC minutes
-pub fn sunset(day: u32, month: u32, year: u32, lon: f32, lat: f32) -> Option<u16> {
-    sunpos(day, month, year, lon, lat, |year_day,lng_hour| -> f32 {year_day as f32 + ((18.0 - lng_hour) / 24.0)}, |cos_h| -> f32 {rad_to_deg(f32::acos(cos_h))/15.0})
+pub fn sunset(date: CalendarDate, lon: f32, lat: f32, horizon: Horizon) -> Option<u16> {
+    sunset_hours(date, lon, lat, horizon).hours().map(|h| (h * 60.0) as u16)
+}
+
+/// Calculates sunrise and sunset for a location and date, telling polar day from polar night
+///
+/// Days, when only one of the events is missing, are reported as polar night if the sun
+/// does not rise high enough or as polar day otherwise
+pub fn sun_events(date: CalendarDate, lon: f32, lat: f32, horizon: Horizon) -> SunEvents {
+    match (sunrise_hours(date, lon, lat, horizon), sunset_hours(date, lon, lat, horizon)) {
+        (SunCrossing::At(rise), SunCrossing::At(set)) => SunEvents::Normal { rise: (rise * 60.0) as u16, set: (set * 60.0) as u16 },
+        (SunCrossing::AlwaysBelow, _) | (_, SunCrossing::AlwaysBelow) => SunEvents::PolarNight,
+        _ => SunEvents::PolarDay
+    }
+}
+
+/// Calculates solar noon for a location and date
+/// Solar noon in UTC minutes
+pub fn solar_noon(date: CalendarDate, lon: f32) -> u16 {
+    // Equation of time approximation from the NOAA
+    let gamma = 2.0 * PI / 365.0 * (date.day_of_year() as f32 - 1.0 - lon / 360.0);
+    let equation_of_time = 229.18 * (0.000075 + 0.001868 * f32::cos(gamma) - 0.032077 * f32::sin(gamma)
+        - 0.014615 * f32::cos(2.0 * gamma) - 0.040849 * f32::sin(2.0 * gamma));
+    let noon = 720.0 - 4.0 * lon - equation_of_time;
+    let noon_adjusted = if noon < 0.0 { noon + 1440.0 } else if noon >= 1440.0 { noon - 1440.0 } else { noon };
+    f32::round(noon_adjusted) as u16
+}
+
+/// Calculates time between sunrise and sunset for a location and date
+/// Day length in minutes, 0 for the polar night and 1440 for the polar day
+pub fn day_length(date: CalendarDate, lon: f32, lat: f32, horizon: Horizon) -> u16 {
+    f32::round(day_length_hours(date, lon, lat, horizon) * 60.0) as u16
+}
+
+/// Calculates how day length changed since the previous day
+/// Difference in minutes, positive values mean longer days
+pub fn day_length_change(date: CalendarDate, lon: f32, lat: f32, horizon: Horizon) -> Option<i16> {
+    let today = day_length_hours(date, lon, lat, horizon);
+    let yesterday = day_length_hours(date.prev_day()?, lon, lat, horizon);
+    Some(f32::round((today - yesterday) * 60.0) as i16)
 }
 
 /// Calculates number of a day in the year for the specified day
-pub fn day_of_the_year(day: u32, month: u32, year: u32) -> u16 {
-    let n1 = 275 * month / 9;
-    let n2 = (month + 9) / 12;
-    let n3 = 1 + ((year - 4 * (year / 4) + 2) / 3);
-    (n1 - (n2 * n3) + day - 30) as u16
+pub fn day_of_the_year(date: CalendarDate) -> u16 {
+    date.day_of_year()
 }
 
 fn deg_to_rad(deg: f32) -> f32 {
@@ -62,114 +179,206 @@ fn rad_to_deg(rad: f32) -> f32 {
 
 #[cfg(test)]
 mod tests {
-    use crate::sun::{day_of_the_year, sunrise, sunset};
+    use crate::date::CalendarDate;
+    use crate::sun::{day_length, day_length_change, day_of_the_year, solar_noon, sun_events, sunrise, sunset, Horizon, SunEvents};
 
     #[test]
     fn first_day() {
-        assert_eq!(day_of_the_year(01, 01, 2021), 1);
+        assert_eq!(day_of_the_year(CalendarDate::new(1, 1, 2021).unwrap()), 1);
     }
 
     #[test]
     fn last_day() {
-        assert_eq!(day_of_the_year(31, 12, 2021), 365);
+        assert_eq!(day_of_the_year(CalendarDate::new(31, 12, 2021).unwrap()), 365);
     }
 
     #[test]
     fn random_day() {
-        assert_eq!(day_of_the_year(03, 11, 2021), 307);
+        assert_eq!(day_of_the_year(CalendarDate::new(3, 11, 2021).unwrap()), 307);
     }
 
     #[test]
     fn leap_day() {
-        assert_eq!(day_of_the_year(29, 02, 2020), 60);
+        assert_eq!(day_of_the_year(CalendarDate::new(29, 2, 2020).unwrap()), 60);
     }
 
     #[test]
     fn day_after_leap_day() {
-        assert_eq!(day_of_the_year(01, 03, 2020), 61);
+        assert_eq!(day_of_the_year(CalendarDate::new(1, 3, 2020).unwrap()), 61);
     }
 
     #[test]
     fn random_day_sunrise() {
-        let sr = sunrise(03, 11, 2021, 24.14015, 60.05842);
+        let sr = sunrise(CalendarDate::new(3, 11, 2021).unwrap(), 24.14015, 60.05842, Horizon::Civil);
         assert!(sr.is_some());
         assert_eq!(sr.unwrap(), 304);
     }
 
     #[test]
     fn first_day_sunrise() {
-        let sr = sunrise(01, 01, 2021, 24.14015, 60.05842);
+        let sr = sunrise(CalendarDate::new(1, 1, 2021).unwrap(), 24.14015, 60.05842, Horizon::Civil);
         assert!(sr.is_some());
         assert_eq!(sr.unwrap(), 389);
     }
 
     #[test]
     fn spring_equinox_sunrise() {
-        let sr = sunrise(20, 03, 2021, 24.14015, 60.05842);
+        let sr = sunrise(CalendarDate::new(20, 3, 2021).unwrap(), 24.14015, 60.05842, Horizon::Civil);
         assert!(sr.is_some());
         assert_eq!(sr.unwrap(), 224);
     }
 
     #[test]
     fn summer_solstice_sunrise() {
-        let sr = sunrise(21, 06, 2021, 24.14015, 60.05842);
+        let sr = sunrise(CalendarDate::new(21, 6, 2021).unwrap(), 24.14015, 60.05842, Horizon::Civil);
         assert!(sr.is_some());
         assert_eq!(sr.unwrap(), 1389);
     }
 
     #[test]
     fn autumn_equinox_sunrise() {
-        let sr = sunrise(23, 09, 2021, 24.14015, 60.05842);
+        let sr = sunrise(CalendarDate::new(23, 9, 2021).unwrap(), 24.14015, 60.05842, Horizon::Civil);
         assert!(sr.is_some());
         assert_eq!(sr.unwrap(), 207);
     }
 
     #[test]
     fn winter_solstice_sunrise() {
-        let sr = sunrise(21, 12, 2021, 24.14015, 60.05842);
+        let sr = sunrise(CalendarDate::new(21, 12, 2021).unwrap(), 24.14015, 60.05842, Horizon::Civil);
         assert!(sr.is_some());
         assert_eq!(sr.unwrap(), 387);
     }
 
     #[test]
     fn random_day_sunset() {
-        let sr = sunset(03, 11, 2021, 24.14015, 60.05842);
+        let sr = sunset(CalendarDate::new(3, 11, 2021).unwrap(), 24.14015, 60.05842, Horizon::Civil);
         assert!(sr.is_some());
         assert_eq!(sr.unwrap(), 908);
     }
 
     #[test]
     fn first_day_sunset() {
-        let sr = sunset(01, 01, 2021, 24.14015, 60.05842);
+        let sr = sunset(CalendarDate::new(1, 1, 2021).unwrap(), 24.14015, 60.05842, Horizon::Civil);
         assert!(sr.is_some());
         assert_eq!(sr.unwrap(), 864);
     }
 
     #[test]
     fn spring_equinox_sunset() {
-        let sr = sunset(20, 03, 2021, 24.14015, 60.05842);
+        let sr = sunset(CalendarDate::new(20, 3, 2021).unwrap(), 24.14015, 60.05842, Horizon::Civil);
         assert!(sr.is_some());
         assert_eq!(sr.unwrap(), 1038);
     }
 
     #[test]
     fn summer_solstice_sunset() {
-        let sr = sunset(21, 06, 2021, 24.14015, 60.05842);
+        let sr = sunset(CalendarDate::new(21, 6, 2021).unwrap(), 24.14015, 60.05842, Horizon::Civil);
         assert!(sr.is_some());
         assert_eq!(sr.unwrap(), 1300);
     }
 
     #[test]
     fn autumn_equinox_sunset() {
-        let sr = sunset(23, 09, 2021, 24.14015, 60.05842);
+        let sr = sunset(CalendarDate::new(23, 9, 2021).unwrap(), 24.14015, 60.05842, Horizon::Civil);
         assert!(sr.is_some());
         assert_eq!(sr.unwrap(), 1023);
     }
 
     #[test]
     fn winter_solstice_sunset() {
-        let sr = sunset(21, 12, 2021, 24.14015, 60.05842);
+        let sr = sunset(CalendarDate::new(21, 12, 2021).unwrap(), 24.14015, 60.05842, Horizon::Civil);
         assert!(sr.is_some());
         assert_eq!(sr.unwrap(), 854);
     }
+
+    #[test]
+    fn official_sunrise() {
+        let sr = sunrise(CalendarDate::new(21, 6, 2021).unwrap(), 24.14015, 60.05842, Horizon::Official);
+        assert_eq!(sr, Some(58));
+    }
+
+    #[test]
+    fn official_sunset() {
+        let ss = sunset(CalendarDate::new(21, 12, 2021).unwrap(), 24.14015, 60.05842, Horizon::Official);
+        assert_eq!(ss, Some(796));
+    }
+
+    #[test]
+    fn twilight_is_earlier_than_sunrise() {
+        let date = CalendarDate::new(3, 11, 2021).unwrap();
+        let official = sunrise(date, 24.14015, 60.05842, Horizon::Official).unwrap();
+        let civil = sunrise(date, 24.14015, 60.05842, Horizon::Civil).unwrap();
+        let nautical = sunrise(date, 24.14015, 60.05842, Horizon::Nautical).unwrap();
+        let astronomical = sunrise(date, 24.14015, 60.05842, Horizon::Astronomical).unwrap();
+        assert!(astronomical < nautical && nautical < civil && civil < official);
+        assert_eq!(sunrise(date, 24.14015, 60.05842, Horizon::Custom(-6.0)), Some(civil));
+    }
+
+    #[test]
+    fn no_nautical_twilight_on_white_nights() {
+        assert!(sunrise(CalendarDate::new(21, 6, 2021).unwrap(), 24.14015, 60.05842, Horizon::Nautical).is_none());
+    }
+
+    #[test]
+    fn greenwich_solar_noon() {
+        // Equation of time is about -14 minutes in the middle of February
+        assert_eq!(solar_noon(CalendarDate::new(11, 2, 2021).unwrap(), 0.0), 734);
+    }
+
+    #[test]
+    fn random_day_solar_noon() {
+        // Equation of time is about +16 minutes in the beginning of November
+        assert_eq!(solar_noon(CalendarDate::new(3, 11, 2021).unwrap(), 24.14015), 607);
+    }
+
+    #[test]
+    fn summer_solstice_day_length() {
+        assert_eq!(day_length(CalendarDate::new(21, 6, 2021).unwrap(), 24.14015, 60.05842, Horizon::Official), 1134);
+    }
+
+    #[test]
+    fn winter_solstice_day_length() {
+        assert_eq!(day_length(CalendarDate::new(21, 12, 2021).unwrap(), 24.14015, 60.05842, Horizon::Official), 351);
+    }
+
+    #[test]
+    fn day_length_changes() {
+        let spring = day_length_change(CalendarDate::new(20, 3, 2021).unwrap(), 24.14015, 60.05842, Horizon::Official);
+        assert_eq!(spring, Some(5));
+        let autumn = day_length_change(CalendarDate::new(23, 9, 2021).unwrap(), 24.14015, 60.05842, Horizon::Official);
+        assert_eq!(autumn, Some(-5));
+        let solstice = day_length_change(CalendarDate::new(21, 12, 2021).unwrap(), 24.14015, 60.05842, Horizon::Official);
+        assert_eq!(solstice, Some(0));
+    }
+
+    #[test]
+    fn normal_sun_events() {
+        let events = sun_events(CalendarDate::new(21, 12, 2021).unwrap(), 24.14015, 60.05842, Horizon::Official);
+        assert_eq!(events, SunEvents::Normal { rise: 445, set: 796 });
+    }
+
+    #[test]
+    fn polar_day() {
+        // Utsjoki, Lapland
+        let date = CalendarDate::new(21, 6, 2021).unwrap();
+        assert_eq!(sun_events(date, 27.02, 69.91, Horizon::Official), SunEvents::PolarDay);
+        assert!(sunrise(date, 27.02, 69.91, Horizon::Official).is_none());
+        assert_eq!(day_length(date, 27.02, 69.91, Horizon::Official), 1440);
+    }
+
+    #[test]
+    fn polar_night() {
+        // Utsjoki, Lapland
+        let date = CalendarDate::new(21, 12, 2021).unwrap();
+        assert_eq!(sun_events(date, 27.02, 69.91, Horizon::Official), SunEvents::PolarNight);
+        assert!(sunset(date, 27.02, 69.91, Horizon::Official).is_none());
+        assert_eq!(day_length(date, 27.02, 69.91, Horizon::Official), 0);
+    }
+
+    #[test]
+    fn civil_polar_night_is_shorter() {
+        // Sun does not rise, but there is still a civil twilight
+        let date = CalendarDate::new(21, 12, 2021).unwrap();
+        assert!(matches!(sun_events(date, 27.02, 69.91, Horizon::Civil), SunEvents::Normal { .. }));
+    }
 }
@@ -0,0 +1,352 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Time zones with daylight saving time rules
+//!
+//! Time zones are described with POSIX TZ strings, like `EET-2EEST,M3.5.0/3,M10.5.0/4`,
+//! which are compact enough to be stored in a flash and cover all the current
+//! rules of the IANA time zones database. Only `Mm.w.d` transition rules are supported.
+
+use crate::date::{days_in_month, CalendarDate, UtcDateTime};
+use crate::weekday::weekday;
+
+/// Daylight saving time transition, happens on a weekday of a month
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    /// Month, starting with 1
+    pub month: u8,
+    /// Week of the month, 1 to 4, 5 means the last week
+    pub week: u8,
+    /// 1 - Monday, 7 - Sunday
+    pub weekday: u8,
+    /// Local time of the transition in seconds since midnight, may be negative or exceed a day
+    pub time: i32,
+}
+
+/// Daylight saving time rules
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DaylightSaving {
+    /// UTC offset during daylight saving time in seconds, east of Greenwich is positive
+    pub offset: i32,
+    /// Switch to daylight saving time, in standard local time
+    pub start: Transition,
+    /// Switch back to standard time, in daylight saving local time
+    pub end: Transition,
+}
+
+/// Time zone, that is a standard UTC offset and optional daylight saving time rules
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeZone {
+    /// Standard UTC offset in seconds, east of Greenwich is positive
+    pub offset: i32,
+    /// Daylight saving time rules, None if time zone does not observe it
+    pub dst: Option<DaylightSaving>,
+}
+
+/// Wall clock date and time in some time zone
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LocalDateTime {
+    date: CalendarDate,
+    seconds: u32,
+    offset: i32,
+}
+
+impl LocalDateTime {
+    /// Calendar date
+    pub fn date(&self) -> CalendarDate {
+        self.date
+    }
+
+    /// Hour starting with 0
+    pub fn hour(&self) -> u32 {
+        self.seconds / 3600
+    }
+
+    /// Minute starting with 0
+    pub fn minute(&self) -> u32 {
+        self.seconds / 60 % 60
+    }
+
+    /// Second starting with 0
+    pub fn second(&self) -> u32 {
+        self.seconds % 60
+    }
+
+    /// Minutes since midnight
+    pub fn minute_of_day(&self) -> u16 {
+        (self.seconds / 60) as u16
+    }
+
+    /// UTC offset in seconds, that was applied to get that local time
+    pub fn utc_offset(&self) -> i32 {
+        self.offset
+    }
+}
+
+/// Seconds since the beginning of the Julian Day Number 0, used to compare instants
+fn timestamp(date: CalendarDate, seconds: i32) -> i64 {
+    date.julian_day_number() as i64 * 86400 + seconds as i64
+}
+
+impl Transition {
+    /// Date of the transition in a year
+    pub fn date(&self, year: u32) -> Option<CalendarDate> {
+        let first = CalendarDate::new(1, self.month as u32, year)?;
+        let first_weekday = (self.weekday as u32 + 7 - weekday(first) as u32) % 7;
+        let mut day = 1 + first_weekday + (self.week as u32 - 1) * 7;
+        let month_length = days_in_month(self.month as u32, year)?;
+        while day > month_length {
+            day -= 7;
+        }
+        CalendarDate::new(day, self.month as u32, year)
+    }
+
+    /// UTC timestamp of the transition, given UTC offset of the local time
+    fn timestamp(&self, year: u32, offset: i32) -> Option<i64> {
+        Some(timestamp(self.date(year)?, self.time - offset))
+    }
+}
+
+impl TimeZone {
+    /// Coordinated universal time
+    pub const UTC: TimeZone = TimeZone { offset: 0, dst: None };
+
+    /// Parses POSIX TZ string, like `EET-2EEST,M3.5.0/3,M10.5.0/4` or `<+03>-3`
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parser = Parser { text: text.trim().as_bytes() };
+        parser.name()?;
+        let offset = -parser.offset()?;
+        if parser.is_empty() {
+            return Some(TimeZone { offset, dst: None });
+        }
+        parser.name()?;
+        let dst_offset = if parser.peek()? == b',' { offset + 3600 } else { -parser.offset()? };
+        parser.expect(b',')?;
+        let start = parser.transition()?;
+        parser.expect(b',')?;
+        let end = parser.transition()?;
+        if !parser.is_empty() {
+            return None;
+        }
+        Some(TimeZone { offset, dst: Some(DaylightSaving { offset: dst_offset, start, end }) })
+    }
+
+    /// UTC offset in seconds at the specified instant
+    pub fn utc_offset(&self, instant: UtcDateTime) -> i32 {
+        let dst = match self.dst {
+            Some(dst) => dst,
+            None => return self.offset,
+        };
+        let year = instant.date().year();
+        let now = timestamp(instant.date(), (instant.minute_of_day() as u32 * 60 + instant.second()) as i32);
+        let start = dst.start.timestamp(year, self.offset);
+        let end = dst.end.timestamp(year, dst.offset);
+        let is_dst = match (start, end) {
+            // Northern hemisphere, daylight saving time is in the middle of the year
+            (Some(start), Some(end)) if start < end => now >= start && now < end,
+            // Southern hemisphere, daylight saving time spans over the new year
+            (Some(start), Some(end)) => now >= start || now < end,
+            _ => false,
+        };
+        if is_dst {
+            dst.offset
+        } else {
+            self.offset
+        }
+    }
+
+    /// Converts UTC instant to the local time
+    pub fn to_local(&self, instant: UtcDateTime) -> Option<LocalDateTime> {
+        let offset = self.utc_offset(instant);
+        let seconds = (instant.minute_of_day() as u32 * 60 + instant.second()) as i32 + offset;
+        let days = seconds.div_euclid(86400);
+        let date = instant.date().add_days(days)?;
+        Some(LocalDateTime { date, seconds: seconds.rem_euclid(86400) as u32, offset })
+    }
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+}
+
+impl<'a> Parser<'a> {
+    fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.first().copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        if self.peek()? == byte {
+            self.text = &self.text[1..];
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    /// Zone abbreviation, either alphabetic or quoted with angle brackets
+    fn name(&mut self) -> Option<()> {
+        let length = if self.peek()? == b'<' {
+            self.text.iter().position(|c| *c == b'>')? + 1
+        } else {
+            self.text.iter().position(|c| !c.is_ascii_alphabetic()).unwrap_or(self.text.len())
+        };
+        if length < 3 {
+            return None;
+        }
+        self.text = &self.text[length..];
+        Some(())
+    }
+
+    fn number(&mut self, max: i32) -> Option<i32> {
+        let length = self.text.iter().position(|c| !c.is_ascii_digit()).unwrap_or(self.text.len());
+        if length == 0 || length > 3 {
+            return None;
+        }
+        let mut value = 0;
+        for digit in &self.text[..length] {
+            value = value * 10 + (digit - b'0') as i32;
+        }
+        self.text = &self.text[length..];
+        if value > max {
+            None
+        } else {
+            Some(value)
+        }
+    }
+
+    /// `[+-]hh[:mm[:ss]]` in seconds
+    fn time(&mut self, max_hours: i32) -> Option<i32> {
+        let sign = match self.peek()? {
+            b'-' => {
+                self.text = &self.text[1..];
+                -1
+            }
+            b'+' => {
+                self.text = &self.text[1..];
+                1
+            }
+            _ => 1,
+        };
+        let mut seconds = self.number(max_hours)? * 3600;
+        if self.peek() == Some(b':') {
+            self.text = &self.text[1..];
+            seconds += self.number(59)? * 60;
+            if self.peek() == Some(b':') {
+                self.text = &self.text[1..];
+                seconds += self.number(59)?;
+            }
+        }
+        Some(sign * seconds)
+    }
+
+    /// POSIX offset, i.e. positive west of Greenwich
+    fn offset(&mut self) -> Option<i32> {
+        self.time(24)
+    }
+
+    /// `Mm.w.d[/time]` rule
+    fn transition(&mut self) -> Option<Transition> {
+        self.expect(b'M')?;
+        let month = self.number(12)? as u8;
+        self.expect(b'.')?;
+        let week = self.number(5)? as u8;
+        self.expect(b'.')?;
+        let day = self.number(6)? as u8;
+        let time = if self.peek() == Some(b'/') {
+            self.text = &self.text[1..];
+            self.time(167)?
+        } else {
+            2 * 3600
+        };
+        if month == 0 || week == 0 {
+            return None;
+        }
+        // POSIX counts weekdays from Sunday
+        let weekday = if day == 0 { 7 } else { day };
+        Some(Transition { month, week, weekday, time })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(day: u32, month: u32, year: u32, hour: u32, minute: u32) -> UtcDateTime {
+        UtcDateTime::new(CalendarDate::new(day, month, year).unwrap(), hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn parse_zones() {
+        let helsinki = TimeZone::parse("EET-2EEST,M3.5.0/3,M10.5.0/4").unwrap();
+        assert_eq!(helsinki.offset, 7200);
+        let dst = helsinki.dst.unwrap();
+        assert_eq!(dst.offset, 10800);
+        assert_eq!(dst.start, Transition { month: 3, week: 5, weekday: 7, time: 3 * 3600 });
+        assert_eq!(dst.end, Transition { month: 10, week: 5, weekday: 7, time: 4 * 3600 });
+
+        assert_eq!(TimeZone::parse("UTC0"), Some(TimeZone::UTC));
+        assert_eq!(TimeZone::parse("<+03>-3"), Some(TimeZone { offset: 10800, dst: None }));
+        assert_eq!(TimeZone::parse("<+0530>-5:30").unwrap().offset, 19800);
+        let new_york = TimeZone::parse("EST5EDT,M3.2.0,M11.1.0").unwrap();
+        assert_eq!(new_york.offset, -18000);
+        assert_eq!(new_york.dst.unwrap().offset, -14400);
+        assert_eq!(new_york.dst.unwrap().start.time, 7200);
+    }
+
+    #[test]
+    fn invalid_zones() {
+        assert_eq!(TimeZone::parse(""), None);
+        assert_eq!(TimeZone::parse("EET"), None);
+        assert_eq!(TimeZone::parse("EET-2EEST"), None);
+        assert_eq!(TimeZone::parse("EET-2EEST,J60,J300"), None);
+        assert_eq!(TimeZone::parse("EET-2EEST,M13.5.0,M10.5.0"), None);
+        assert_eq!(TimeZone::parse("EET-2EEST,M3.5.0/3,M10.5.0/4 trailing"), None);
+    }
+
+    #[test]
+    fn transition_dates() {
+        let last_sunday_of_march = Transition { month: 3, week: 5, weekday: 7, time: 0 };
+        assert_eq!(last_sunday_of_march.date(2021), CalendarDate::new(28, 3, 2021));
+        assert_eq!(last_sunday_of_march.date(2024), CalendarDate::new(31, 3, 2024));
+        let second_sunday_of_march = Transition { month: 3, week: 2, weekday: 7, time: 0 };
+        assert_eq!(second_sunday_of_march.date(2021), CalendarDate::new(14, 3, 2021));
+    }
+
+    #[test]
+    fn helsinki_dst() {
+        let helsinki = TimeZone::parse("EET-2EEST,M3.5.0/3,M10.5.0/4").unwrap();
+        // Switch happens at 01:00 UTC on 28th of March 2021 and 31st of October 2021
+        assert_eq!(helsinki.utc_offset(utc(28, 3, 2021, 0, 59)), 7200);
+        assert_eq!(helsinki.utc_offset(utc(28, 3, 2021, 1, 0)), 10800);
+        assert_eq!(helsinki.utc_offset(utc(31, 10, 2021, 0, 59)), 10800);
+        assert_eq!(helsinki.utc_offset(utc(31, 10, 2021, 1, 0)), 7200);
+        assert_eq!(helsinki.utc_offset(utc(1, 1, 2021, 12, 0)), 7200);
+
+        let local = helsinki.to_local(utc(30, 6, 2021, 22, 30)).unwrap();
+        assert_eq!(local.date(), CalendarDate::new(1, 7, 2021).unwrap());
+        assert_eq!((local.hour(), local.minute()), (1, 30));
+        assert_eq!(local.utc_offset(), 10800);
+    }
+
+    #[test]
+    fn southern_hemisphere_dst() {
+        let sydney = TimeZone::parse("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+        assert_eq!(sydney.utc_offset(utc(1, 1, 2022, 0, 0)), 39600);
+        assert_eq!(sydney.utc_offset(utc(1, 7, 2022, 0, 0)), 36000);
+        // Back to standard time at 03:00 local daylight time on 3rd of April 2022
+        assert_eq!(sydney.utc_offset(utc(2, 4, 2022, 15, 59)), 39600);
+        assert_eq!(sydney.utc_offset(utc(2, 4, 2022, 16, 0)), 36000);
+    }
+
+    #[test]
+    fn western_local_time() {
+        let new_york = TimeZone::parse("EST5EDT,M3.2.0,M11.1.0").unwrap();
+        let local = new_york.to_local(utc(1, 1, 2022, 3, 0)).unwrap();
+        assert_eq!(local.date(), CalendarDate::new(31, 12, 2021).unwrap());
+        assert_eq!(local.hour(), 22);
+    }
+}
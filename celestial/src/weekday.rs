@@ -2,48 +2,154 @@
 #![deny(unsafe_code)]
 
 //! Weekday calculation using Zeller approach
+//! and ISO 8601 week numbering
+
+use crate::date::{is_leap_year, CalendarDate};
 
 /// Calculates weekday number for a date.
 /// 1 - Monday, 7 - Sunday
-pub fn weekday(day: u32, month: u32, year: u32) -> u8 {
-    let year_adjusted = year + ((month + 9) / 12) - 1;
-    let month_adjusted = (month + 9) % 12;
+pub fn weekday(date: CalendarDate) -> u8 {
+    let year_adjusted = date.year() + ((date.month() + 9) / 12) - 1;
+    let month_adjusted = (date.month() + 9) % 12;
     let leap_year_correction = year_adjusted * 365 + (year_adjusted / 4) - (year_adjusted / 100) + (year_adjusted / 400);
-    let zeller = leap_year_correction + month_adjusted * 30 + ((6 * month_adjusted + 5) / 10) + day + 1;
+    let zeller = leap_year_correction + month_adjusted * 30 + ((6 * month_adjusted + 5) / 10) + date.day() + 1;
     ((zeller % 7) + 1) as u8
 }
 
+/// Calculates ISO 8601 week for a date.
+/// Returns week-based year and week number starting with 1
+///
+/// Days around the new year may belong to the last week of the previous year
+/// or to the first week of the next year
+pub fn iso_week(date: CalendarDate) -> (u32, u8) {
+    let week = (date.day_of_year() as i32 - weekday(date) as i32 + 10) / 7;
+    if week < 1 {
+        let previous_year = date.year() - 1;
+        (previous_year, weeks_in_year(previous_year).unwrap_or(52))
+    } else if week > weeks_in_year(date.year()).unwrap_or(52) as i32 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), week as u8)
+    }
+}
+
+/// Calculates number of ISO 8601 weeks in a week-based year, either 52 or 53
+pub fn weeks_in_year(year: u32) -> Option<u8> {
+    let first_day = weekday(CalendarDate::new(1, 1, year)?);
+    if first_day == 4 || (first_day == 3 && is_leap_year(year)) {
+        Some(53)
+    } else {
+        Some(52)
+    }
+}
+
+/// Finds Monday of the ISO 8601 week in a week-based year
+pub fn first_day_of_week(week_year: u32, week: u8) -> Option<CalendarDate> {
+    if week == 0 || week > weeks_in_year(week_year)? {
+        return None
+    }
+    // 4th of January always belongs to the first week
+    let january_4 = CalendarDate::new(4, 1, week_year)?;
+    january_4.add_days(7 * (week as i32 - 1) - (weekday(january_4) as i32 - 1))
+}
+
+/// Calculates number of a day in the quarter, starting with 1
+pub fn day_of_quarter(date: CalendarDate) -> u8 {
+    let first_month = (date.month() - 1) / 3 * 3 + 1;
+    let quarter_start = CalendarDate::new(1, first_month, date.year()).map(|d| d.day_of_year()).unwrap_or(1);
+    (date.day_of_year() - quarter_start + 1) as u8
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::weekday::weekday;
+    use crate::date::CalendarDate;
+    use crate::weekday::{day_of_quarter, first_day_of_week, iso_week, weeks_in_year, weekday};
 
     #[test]
     fn random_wednesday() {
-        assert_eq!(weekday(03, 11, 2021), 3);
+        assert_eq!(weekday(CalendarDate::new(3, 11, 2021).unwrap()), 3);
     }
 
     #[test]
     fn monday_is_one() {
-        assert_eq!(weekday(04, 10, 2021), 1);
+        assert_eq!(weekday(CalendarDate::new(4, 10, 2021).unwrap()), 1);
     }
 
     #[test]
     fn sunday_is_seven() {
-        assert_eq!(weekday(05, 12, 2021), 7);
+        assert_eq!(weekday(CalendarDate::new(5, 12, 2021).unwrap()), 7);
     }
 
     #[test]
     fn last_day() {
-        assert_eq!(weekday(31, 12, 2021), 5);
+        assert_eq!(weekday(CalendarDate::new(31, 12, 2021).unwrap()), 5);
     }
 
     #[test]
     fn first_day() {
-        assert_eq!(weekday(01, 01, 2021), 5);
+        assert_eq!(weekday(CalendarDate::new(1, 1, 2021).unwrap()), 5);
     }
 
     #[test]
     fn leap_day() {
-        assert_eq!(weekday(29, 02, 2020), 6);
+        assert_eq!(weekday(CalendarDate::new(29, 2, 2020).unwrap()), 6);
+    }
+
+    #[test]
+    fn iso_week_in_the_middle_of_year() {
+        assert_eq!(iso_week(CalendarDate::new(3, 11, 2021).unwrap()), (2021, 44));
+        assert_eq!(iso_week(CalendarDate::new(17, 10, 2022).unwrap()), (2022, 42));
+    }
+
+    #[test]
+    fn iso_week_belongs_to_previous_year() {
+        assert_eq!(iso_week(CalendarDate::new(1, 1, 2021).unwrap()), (2020, 53));
+        assert_eq!(iso_week(CalendarDate::new(2, 1, 2022).unwrap()), (2021, 52));
+        assert_eq!(iso_week(CalendarDate::new(3, 1, 2022).unwrap()), (2022, 1));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn iso_week_belongs_to_next_year() {
+        assert_eq!(iso_week(CalendarDate::new(29, 12, 2014).unwrap()), (2015, 1));
+        assert_eq!(iso_week(CalendarDate::new(31, 12, 2024).unwrap()), (2025, 1));
+        assert_eq!(iso_week(CalendarDate::new(28, 12, 2024).unwrap()), (2024, 52));
+    }
+
+    #[test]
+    fn long_years() {
+        assert_eq!(weeks_in_year(2015), Some(53));
+        assert_eq!(weeks_in_year(2020), Some(53));
+        assert_eq!(weeks_in_year(2021), Some(52));
+        assert_eq!(weeks_in_year(2026), Some(53));
+        assert_eq!(weeks_in_year(0), None);
+    }
+
+    #[test]
+    fn iso_week_round_trip() {
+        for year in 2000..2030 {
+            for week in 1..=weeks_in_year(year).unwrap() {
+                let monday = first_day_of_week(year, week).unwrap();
+                assert_eq!(weekday(monday), 1);
+                assert_eq!(iso_week(monday), (year, week));
+            }
+        }
+    }
+
+    #[test]
+    fn first_day_of_week_out_of_range() {
+        assert_eq!(first_day_of_week(2021, 0), None);
+        assert_eq!(first_day_of_week(2021, 53), None);
+        assert_eq!(first_day_of_week(2020, 53), CalendarDate::new(28, 12, 2020));
+        assert_eq!(first_day_of_week(2021, 1), CalendarDate::new(4, 1, 2021));
+    }
+
+    #[test]
+    fn quarter_days() {
+        assert_eq!(day_of_quarter(CalendarDate::new(1, 1, 2022).unwrap()), 1);
+        assert_eq!(day_of_quarter(CalendarDate::new(31, 3, 2022).unwrap()), 90);
+        assert_eq!(day_of_quarter(CalendarDate::new(31, 3, 2020).unwrap()), 91);
+        assert_eq!(day_of_quarter(CalendarDate::new(1, 4, 2022).unwrap()), 1);
+        assert_eq!(day_of_quarter(CalendarDate::new(30, 9, 2022).unwrap()), 92);
+        assert_eq!(day_of_quarter(CalendarDate::new(31, 12, 2022).unwrap()), 92);
+    }
+}
@@ -0,0 +1,165 @@
+use bit_field::BitField;
+use core::cmp::Ordering;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::text::renderer::{TextMetrics, TextRenderer};
+use embedded_graphics::text::Baseline;
+use epd_waveshare::prelude::TriColor;
+
+// Height, baseline and u16 glyphs count
+const HEADER_SIZE: usize = 4;
+// u32 code point, u8 width and u16 bitmap offset
+const GLYPH_SIZE: usize = 7;
+
+/// Proportional bitmap font, produced by png2bin from a glyph sheet
+#[derive(Clone, Copy)]
+pub struct Font {
+    height: u8,
+    baseline: u8,
+    glyphs: &'static [u8],
+    bitmaps: &'static [u8],
+}
+
+struct Glyph {
+    width: u32,
+    bitmap: &'static [u8],
+}
+
+impl Font {
+    pub fn from_slice(data: &'static [u8]) -> Option<Font> {
+        let header = data.get(0..HEADER_SIZE)?;
+        let count = u16::from_le_bytes([header[2], header[3]]) as usize;
+        let bitmaps_start = HEADER_SIZE + count * GLYPH_SIZE;
+        Some(Font {
+            height: header[0],
+            baseline: header[1],
+            glyphs: data.get(HEADER_SIZE..bitmaps_start)?,
+            bitmaps: data.get(bitmaps_start..)?,
+        })
+    }
+
+    /// Width of the text in pixels, when rendered in a single line
+    pub fn text_width(&self, text: &str) -> u32 {
+        text.chars()
+            .filter_map(|c| self.glyph(c))
+            .map(|glyph| glyph.width)
+            .sum()
+    }
+
+    fn glyph(&self, c: char) -> Option<Glyph> {
+        // Glyphs table is sorted by the code point
+        let mut low = 0;
+        let mut high = self.glyphs.len() / GLYPH_SIZE;
+        while low < high {
+            let middle = (low + high) / 2;
+            let entry = &self.glyphs[middle * GLYPH_SIZE..(middle + 1) * GLYPH_SIZE];
+            let code_point = u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]);
+            match code_point.cmp(&(c as u32)) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => {
+                    let width = entry[4] as u32;
+                    let offset = u16::from_le_bytes([entry[5], entry[6]]) as usize;
+                    let size = (width * self.height as u32).div_ceil(8) as usize;
+                    let bitmap = self.bitmaps.get(offset..offset + size)?;
+                    return Some(Glyph { width, bitmap });
+                }
+            }
+        }
+        // Unknown characters are replaced with question mark, if font has it
+        if c != '?' {
+            self.glyph('?')
+        } else {
+            None
+        }
+    }
+}
+
+/// Text style, that renders text with the font in a single color
+///
+/// Alignment is provided by the embedded-graphics `Text`, for example
+/// `Text::with_alignment(text, position, FontStyle::new(&font, color), Alignment::Center)`
+#[derive(Clone, Copy)]
+pub struct FontStyle<'a> {
+    font: &'a Font,
+    color: TriColor,
+}
+
+impl<'a> FontStyle<'a> {
+    pub fn new(font: &'a Font, color: TriColor) -> Self {
+        FontStyle { font, color }
+    }
+
+    fn top(&self, position: Point, baseline: Baseline) -> i32 {
+        let height = self.font.height as i32;
+        let offset = match baseline {
+            Baseline::Top => 0,
+            Baseline::Bottom => height - 1,
+            Baseline::Middle => (height - 1) / 2,
+            Baseline::Alphabetic => self.font.baseline as i32,
+        };
+        position.y - offset
+    }
+}
+
+impl TextRenderer for FontStyle<'_> {
+    type Color = TriColor;
+
+    fn draw_string<D>(
+        &self,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let top = self.top(position, baseline);
+        let mut x = position.x;
+        for glyph in text.chars().filter_map(|c| self.font.glyph(c)) {
+            let width = glyph.width;
+            let pixels = width * self.font.height as u32;
+            // Only black pixels of the glyph are drawn, background is kept intact
+            target.draw_iter(
+                (0..pixels)
+                    .filter(|pixel| {
+                        !glyph.bitmap[(pixel / 8) as usize].get_bit((pixel % 8) as usize)
+                    })
+                    .map(|pixel| {
+                        let point =
+                            Point::new(x + (pixel % width) as i32, top + (pixel / width) as i32);
+                        Pixel(point, self.color)
+                    }),
+            )?;
+            x += width as i32;
+        }
+        Ok(Point::new(x, position.y))
+    }
+
+    fn draw_whitespace<D>(
+        &self,
+        width: u32,
+        position: Point,
+        _baseline: Baseline,
+        _target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        Ok(position + Point::new(width as i32, 0))
+    }
+
+    fn measure_string(&self, text: &str, position: Point, baseline: Baseline) -> TextMetrics {
+        let width = self.font.text_width(text);
+        let top_left = Point::new(position.x, self.top(position, baseline));
+        TextMetrics {
+            bounding_box: Rectangle::new(top_left, Size::new(width, self.font.height as u32)),
+            next_position: position + Point::new(width as i32, 0),
+        }
+    }
+
+    fn line_height(&self) -> u32 {
+        self.font.height as u32
+    }
+}
@@ -3,17 +3,21 @@ use board::hal::interrupt;
 use board::hal::pac::{NVIC, USART2};
 use board::hal::serial::{Event, Rx};
 use board::{GpsEnPin, GpsUsart};
-use core::borrow::{Borrow, BorrowMut};
 use core::cell::{Cell, RefCell};
 use core::sync::atomic::{AtomicU16, Ordering};
 use cortex_m::interrupt as ci;
 use cortex_m::interrupt::Mutex;
-use nmea::{GpsDate, GpsPosition, GpsTime};
+use nmea::{GpsDate, GpsPosition, GpsSync, GpsTime, NmeaFramer, Sentence, MAX_SENTENCE_LENGTH};
 
-type NmeaBuffer = heapless::String<84>;
+type NmeaBuffer = heapless::String<MAX_SENTENCE_LENGTH>;
+
+/// Delay between the fix and the end of the time sentence. Receiver sends a burst of sentences
+/// at 9600 baud after each fix, time sentence is one of the first in the burst
+pub const SENTENCE_LATENCY_MS: u32 = 100;
 
 static MESSAGES_SEEN: AtomicU16 = AtomicU16::new(0);
 static GPS_RX: Mutex<RefCell<Option<Rx<USART2>>>> = Mutex::new(RefCell::new(None));
+static FRAMER: Mutex<RefCell<NmeaFramer>> = Mutex::new(RefCell::new(NmeaFramer::new()));
 static RECEIVE_BUFFER: Mutex<RefCell<NmeaBuffer>> =
     Mutex::new(RefCell::new(heapless::String::new()));
 static EOL_FLAG: Mutex<Cell<bool>> = Mutex::new(Cell::new(false));
@@ -29,27 +33,21 @@ fn USART2() {
         if let Some(rx) = GPS_RX.borrow(cs).borrow_mut().as_mut() {
             if let Ok(byte) = rx.read() {
                 // Read unconditionally to ACK the interrupt
-                if byte == 0x0A {
-                    return; //We ignore LF
-                }
-                if !EOL_FLAG.borrow(cs).borrow().get() {
-                    //We only modify line if EOL flas is not set, which means processing is etiher not started or already finished
-                    if byte == 0x0D {
-                        //On CR set end of line flag
-                        EOL_FLAG.borrow(cs).borrow_mut().set(true);
-                        return; //But do not store the CR
-                    }
-                    let mut buffer = RECEIVE_BUFFER.borrow(cs).borrow_mut();
-                    if buffer.len() > 83 {
+                match FRAMER.borrow(cs).borrow_mut().push(byte) {
+                    Some(Ok(sentence)) if !EOL_FLAG.borrow(cs).get() => {
+                        //We only modify line if EOL flag is not set, which means processing is either not started or already finished
+                        let mut buffer = RECEIVE_BUFFER.borrow(cs).borrow_mut();
                         buffer.clear();
-                        // Mark too big string as a message, so we don't spend
+                        if buffer.push_str(sentence).is_ok() {
+                            EOL_FLAG.borrow(cs).set(true);
+                        }
+                    }
+                    Some(Err(_)) => {
+                        // Mark broken sentence as a message, so we don't spend
                         // too much time reading garbage
                         MESSAGES_SEEN.fetch_add(1, Ordering::Relaxed);
                     }
-                    if buffer.push(byte as char).is_err() {
-                        //Unable to proceed, let's clear it
-                        buffer.clear()
-                    }
+                    _ => {}
                 }
             }
         }
@@ -65,9 +63,15 @@ impl Gps {
         Gps { en }
     }
 
-    pub fn sync_date_time(&mut self) -> (Option<(GpsDate, GpsTime)>, Option<GpsPosition>) {
-        let mut date = None;
-        let mut pos = None;
+    /// Receives date, time and position
+    ///
+    /// Date and time are returned along with the `clock` value at the moment of their reception
+    pub fn sync_date_time(
+        &mut self,
+        mut clock: impl FnMut() -> u32,
+    ) -> (Option<(GpsDate, GpsTime, u32)>, Option<GpsPosition>) {
+        let mut sync = GpsSync::new();
+        let mut received_at = 0;
         unsafe {
             NVIC::unmask(interrupt::USART2);
         }
@@ -78,23 +82,40 @@ impl Gps {
                 break;
             }
             ci::free(|cs| {
-                if EOL_FLAG.borrow(cs).borrow().get() {
-                    // Full line is received, parse it
-                    let (dt, p) = nmea::parse_nmea_string(&RECEIVE_BUFFER.borrow(cs).borrow());
-                    date = dt;
-                    pos = p;
-                    RECEIVE_BUFFER.borrow(cs).borrow_mut().clear();
-                    EOL_FLAG.borrow(cs).borrow_mut().set(false);
+                if EOL_FLAG.borrow(cs).get() {
+                    // Full sentence is received, parse it
+                    let sentence = RECEIVE_BUFFER.borrow(cs).borrow();
+                    let date_time = sync.date_time();
+                    match nmea::parse_sentence(&sentence) {
+                        Ok(parsed @ Sentence::Rmc(_)) => {
+                            MESSAGES_SEEN.fetch_add(1, Ordering::Relaxed);
+                            sync.push(&parsed);
+                        }
+                        Ok(parsed) => {
+                            sync.push(&parsed);
+                        }
+                        // Checksums are already verified by the framer, so errors are either
+                        // unsupported or proprietary sentences or malformed fields, nothing to do with them
+                        Err(_) => {}
+                    }
+                    if sync.date_time() != date_time {
+                        received_at = clock();
+                    }
+                    EOL_FLAG.borrow(cs).set(false);
                 }
             });
-            if date.is_some() && pos.is_some() {
-                //We got the fix
+            if sync.is_complete() {
+                //We got the fix, good enough to trust the position
                 break;
             }
             cortex_m::asm::wfi(); //Sleep till next char arrives
         }
         self.en.set_high(); // Disable GPS receiver
         NVIC::mask(interrupt::USART2);
-        (date, pos)
+        (
+            sync.date_time()
+                .map(|(date, time)| (date, time, received_at)),
+            sync.position().filter(|_| sync.has_good_fix()),
+        )
     }
 }
@@ -1,18 +1,35 @@
-use board::hal::datetime::Date;
+use crate::image_manager::ImageManager;
+use celestial::CalendarDate;
+use heapless::Vec;
+use holidays::{Country, Holiday, HolidayCalendar};
 
-// Finish holidays 2022.
-//TODO Make other countries holidays
-//TODO Calculate unstable holidays dates
-pub fn is_holiday(date: Date) -> bool {
-    (date.month == 1 && date.date == 1)
-        || (date.month == 1 && date.date == 6)
-        || (date.month == 4 && date.date == 15)
-        || (date.month == 4 && date.date == 18)
-        || (date.month == 5 && date.date == 1)
-        || (date.month == 5 && date.date == 26)
-        || (date.month == 6 && date.date == 24)
-        || (date.month == 12 && date.date == 6)
-        || (date.month == 12 && date.date == 24)
-        || (date.month == 12 && date.date == 25)
-        || (date.month == 12 && date.date == 26)
+const MAX_HOLIDAYS: usize = 64;
+
+// Used when flash image does not have holidays rules
+const DEFAULT_COUNTRY: Country = Country::Finland;
+
+pub struct Holidays {
+    rules: Vec<Holiday<'static>, MAX_HOLIDAYS>,
+}
+
+impl Holidays {
+    pub fn new(image_manager: &ImageManager) -> Self {
+        let mut rules = Vec::new();
+        // Rules above the capacity are ignored
+        match image_manager.holidays() {
+            Some(records) => rules.extend(records.take(MAX_HOLIDAYS)),
+            None => rules.extend(
+                DEFAULT_COUNTRY
+                    .holidays()
+                    .iter()
+                    .copied()
+                    .take(MAX_HOLIDAYS),
+            ),
+        }
+        Holidays { rules }
+    }
+
+    pub fn is_holiday(&self, date: CalendarDate) -> bool {
+        HolidayCalendar::new(&self.rules).is_public_holiday(date)
+    }
 }
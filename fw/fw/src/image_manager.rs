@@ -1,5 +1,9 @@
 use crate::bin_image::BinImage;
+use crate::font::Font;
+use celestial::{CalendarDate, TimeZone};
 use embedded_graphics::prelude::Size;
+use holidays::HolidayRecords;
+use tzmap::TimeZoneMap;
 
 pub struct ImageManager {
     images: &'static [u8],
@@ -68,8 +72,59 @@ impl ImageManager {
         let rw_data = self.fetch_image_data((48 + day_offset + 1) as usize);
         BinImage::from_slice(size, bw_data, rw_data)
     }
+
+    pub fn holidays(&self) -> Option<HolidayRecords<'static>> {
+        // Holidays rules are optional and stored after the last day triplet, at position 1146
+        self.fetch_image_data(1146).map(HolidayRecords::new)
+    }
+
+    pub fn name_days(&self, date: CalendarDate) -> Option<&'static str> {
+        // Name days are optional and stored at position 1147, as 366 length prefixed records
+        let data = self.fetch_image_data(1147)?;
+        // Leap year index keeps a place for the 29th of February
+        let index = CalendarDate::new(date.day(), date.month(), 2000)?.day_of_year() - 1;
+        let mut position = 0;
+        for _ in 0..index {
+            position += 1 + *data.get(position)? as usize;
+        }
+        let length = *data.get(position)? as usize;
+        let names = core::str::from_utf8(data.get(position + 1..position + 1 + length)?).ok()?;
+        if names.is_empty() {
+            None
+        } else {
+            Some(names)
+        }
+    }
+
+    pub fn font(&self) -> Option<Font> {
+        // Text font is optional and stored at position 1148
+        self.fetch_image_data(1148).and_then(Font::from_slice)
+    }
+
+    pub fn timezone(&self) -> Option<TimeZone> {
+        // Timezone is stored at position 1149 as a POSIX TZ string, older images do not have it
+        let data = self.fetch_image_data(1149)?;
+        TimeZone::parse(core::str::from_utf8(data).ok()?)
+    }
+
+    pub fn timezone_map(&self) -> Option<TimeZoneMap<'static>> {
+        // Timezone map is optional and stored at position 1150
+        self.fetch_image_data(1150).and_then(TimeZoneMap::new)
+    }
+
+    fn directory_size(&self) -> usize {
+        // Layout is always the first entry, that directly follows the directory
+        let mut offset_bytes: [u8; 4] = [0, 0, 0, 0];
+        offset_bytes.copy_from_slice(&self.images[0..4]);
+        u32::from_le_bytes(offset_bytes) as usize
+    }
+
     fn fetch_image_data(&self, index: usize) -> Option<&'static [u8]> {
         let index_position = index * 4;
+        if index_position + 4 > self.directory_size() {
+            // Older flash images have shorter directory
+            return None;
+        }
         let mut offset_bytes: [u8; 4] = [0, 0, 0, 0];
         offset_bytes.copy_from_slice(&self.images[index_position..index_position + 4]);
         let offset = u32::from_le_bytes(offset_bytes) as usize;
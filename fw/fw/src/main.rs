@@ -23,6 +23,7 @@ use epd_waveshare::prelude::WaveshareThreeColorDisplay;
 use epd_waveshare::prelude::*;
 
 mod bin_image;
+mod font;
 mod gps;
 mod holiday;
 mod image_manager;
@@ -65,19 +66,6 @@ fn main() -> ! {
             let systick = cp.SYST;
             let delay = SharedDelay::new(Delay::new(systick, clocks));
 
-            //Get date/time information from the RTC
-            let watch = Watch::new(
-                p.RTC,
-                &mut rcc.apb1r1,
-                &mut rcc.bdcr,
-                &mut pwr.cr1,
-                &mut exti,
-                p.GPIOD,
-                p.USART2,
-                &mut rcc.ahb2,
-                clocks,
-            );
-
             //Configure board
             let (mut bme280, mut epd_spi, mut epd) = board::init(
                 p.GPIOA,
@@ -100,7 +88,7 @@ fn main() -> ! {
             let mut display = Display5in83::default();
             display.set_rotation(DisplayRotation::Rotate90);
 
-            //Get renderer
+            //Get images, external flash is only available after the board configuration
             #[cfg(feature = "debug-images")]
             let image_manager = ImageManager::new(&IMAGES);
             #[cfg(feature = "external-images")]
@@ -108,6 +96,21 @@ fn main() -> ! {
             #[cfg(feature = "external-images")]
             let image_manager = ImageManager::new(images);
 
+            //Get date/time information from the RTC
+            let watch = Watch::new(
+                p.RTC,
+                &mut rcc.apb1r1,
+                &mut rcc.bdcr,
+                &mut pwr.cr1,
+                &mut exti,
+                p.GPIOD,
+                p.USART2,
+                &mut rcc.ahb2,
+                clocks,
+                &image_manager,
+            );
+
+            //Get renderer
             let renderer = Renderer::new(image_manager);
 
             //Check if we woke up due to the button press and draw B side in that case
@@ -1,22 +1,36 @@
 use crate::bin_image::BinImage;
-use crate::holiday::is_holiday;
+use crate::font::{Font, FontStyle};
+use crate::holiday::Holidays;
 use crate::image_manager::ImageManager;
 use crate::Watch;
-use board::hal::datetime::Date;
-use celestial::{day_of_the_year, moon_phase, sunrise, sunset};
-use chrono::{TimeZone, Timelike, Utc};
-use chrono_tz::Europe::Helsinki;
+use celestial::{
+    day_of_the_year, iso_week, moon_phase, sun_events, weekday, CalendarDate, Horizon, SunEvents,
+    UtcDateTime,
+};
 use embedded_graphics::image::Image;
+use embedded_graphics::mono_font::iso_8859_1::FONT_7X13;
+use embedded_graphics::mono_font::MonoTextStyle;
 use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{Circle, PrimitiveStyle};
+use embedded_graphics::text::{Alignment, Baseline, Text, TextStyleBuilder};
 use epd_waveshare::epd5in83b_v2::Display5in83;
+use epd_waveshare::prelude::TriColor;
 
 pub struct Renderer {
     image_manager: ImageManager,
+    holidays: Holidays,
+    font: Option<Font>,
 }
 
 impl Renderer {
     pub fn new(image_manager: ImageManager) -> Self {
-        Renderer { image_manager }
+        let holidays = Holidays::new(&image_manager);
+        let font = image_manager.font();
+        Renderer {
+            image_manager,
+            holidays,
+            font,
+        }
     }
     pub fn render_side_a(
         &self,
@@ -27,7 +41,7 @@ impl Renderer {
         humidity: f32,
     ) {
         //Draw daily info
-        let day_of_year = day_of_the_year(watch.date().date, watch.date().month, watch.date().year);
+        let day_of_year = day_of_the_year(watch.date());
         let a_side_image = self.image_manager.a_side(day_of_year - 1); //Image indices start with 0, but days start with 1
         Image::new(&a_side_image, Point::zero())
             .draw(display)
@@ -43,7 +57,7 @@ impl Renderer {
         self.render_date(display, watch);
 
         //Render moon phase
-        let moon_phase = moon_phase(watch.date().date, watch.date().month, watch.date().year);
+        let moon_phase = moon_phase(watch.date());
         let moon_phase_image = self.image_manager.moon(moon_phase - 1);
         Image::new(&moon_phase_image, Point::new(112, 486))
             .draw(display)
@@ -55,7 +69,7 @@ impl Renderer {
 
     pub fn render_side_b(&self, display: &mut Display5in83, watch: &Watch) {
         //Draw daily info
-        let day_of_year = day_of_the_year(watch.date().date, watch.date().month, watch.date().year);
+        let day_of_year = day_of_the_year(watch.date());
         let b_side_image = self.image_manager.b_side(day_of_year - 1); //Image indices start with 0, but days start with 1
         Image::new(&b_side_image, Point::zero())
             .draw(display)
@@ -76,8 +90,8 @@ impl Renderer {
 
     fn render_date(&self, display: &mut Display5in83, watch: &Watch) {
         //Draw day of week
-        let dow_image = Self::mark_holiday(
-            self.image_manager.weekday((watch.date().day - 1) as u8),
+        let dow_image = self.mark_holiday(
+            self.image_manager.weekday(weekday(watch.date()) - 1),
             watch.date(),
         ); //Same index shift as for day of year
         Image::new(&dow_image, Point::new(274, 448))
@@ -85,8 +99,8 @@ impl Renderer {
             .unwrap();
 
         //Draw month
-        let month_image = Self::mark_holiday(
-            self.image_manager.month((watch.date().month - 1) as u8),
+        let month_image = self.mark_holiday(
+            self.image_manager.month((watch.date().month() - 1) as u8),
             watch.date(),
         ); //Same index shift as for day of year
         Image::new(&month_image, Point::new(20, 448))
@@ -94,10 +108,10 @@ impl Renderer {
             .unwrap();
 
         //Draw day
-        if watch.date().date < 10 {
+        if watch.date().day() < 10 {
             //Simple single digit case
-            let day_image = Self::mark_holiday(
-                self.image_manager.big_digit((watch.date().date) as u8),
+            let day_image = self.mark_holiday(
+                self.image_manager.big_digit(watch.date().day() as u8),
                 watch.date(),
             );
             Image::new(&day_image, Point::new(195, 478))
@@ -105,13 +119,13 @@ impl Renderer {
                 .unwrap();
         } else {
             //Two digits are slightly more complex
-            let left_digit = watch.date().date / 10;
-            let right_digit = watch.date().date % 10;
-            let left_day_image = Self::mark_holiday(
+            let left_digit = watch.date().day() / 10;
+            let right_digit = watch.date().day() % 10;
+            let left_day_image = self.mark_holiday(
                 self.image_manager.big_digit((left_digit) as u8),
                 watch.date(),
             );
-            let right_day_image = Self::mark_holiday(
+            let right_day_image = self.mark_holiday(
                 self.image_manager.big_digit((right_digit) as u8),
                 watch.date(),
             );
@@ -124,48 +138,100 @@ impl Renderer {
         }
 
         //Draw year
-        self.render_small_digits(display, watch.date().year as u16, Point::new(6, 624), 4);
+        self.render_small_digits(display, watch.date().year() as u16, Point::new(6, 624), 4);
+
+        //Draw week number
+        let (_, week) = iso_week(watch.date());
+        self.render_small_digits(display, week as u16, Point::new(336, 624), 2);
+
+        //Draw name days
+        if let Some(names) = self.image_manager.name_days(watch.date()) {
+            self.render_text(display, names, Point::new(6, 584), Alignment::Left);
+        }
 
         //Draw sunrise/sunset
-        //TODO use timezone polygons and current location to determine actual timezone
-        if let Some(sunrise) = sunrise(
-            watch.date().date,
-            watch.date().month,
-            watch.date().year,
-            watch.lon(),
-            watch.lat(),
-        ) {
-            let local_time = Utc
-                .ymd(
-                    watch.date().year as i32,
-                    watch.date().month,
-                    watch.date().date,
-                )
-                .and_hms((sunrise / 60) as u32, (sunrise % 60) as u32, 0)
-                .with_timezone(&Helsinki);
-            self.render_small_digits(display, local_time.hour() as u16, Point::new(66, 524), 2);
-            self.render_small_digits(display, local_time.minute() as u16, Point::new(110, 524), 2);
+        match sun_events(watch.date(), watch.lon(), watch.lat(), Horizon::Official) {
+            SunEvents::Normal { rise, set } => {
+                self.render_sun_time(display, watch, rise, Point::new(66, 524));
+                self.render_sun_time(display, watch, set, Point::new(66, 550));
+            }
+            SunEvents::PolarDay => {
+                //Sun never sets - draw an empty sun instead of both times
+                let style = PrimitiveStyle::with_stroke(TriColor::Black, 2);
+                Self::render_polar_icon(display, style, Point::new(66, 524));
+                Self::render_polar_icon(display, style, Point::new(66, 550));
+            }
+            SunEvents::PolarNight => {
+                //Sun never rises - draw a filled sun instead of both times
+                let style = PrimitiveStyle::with_fill(TriColor::Black);
+                Self::render_polar_icon(display, style, Point::new(66, 524));
+                Self::render_polar_icon(display, style, Point::new(66, 550));
+            }
         }
-        if let Some(sunset) = sunset(
-            watch.date().date,
-            watch.date().month,
-            watch.date().year,
-            watch.lon(),
-            watch.lat(),
-        ) {
-            let local_time = Utc
-                .ymd(
-                    watch.date().year as i32,
-                    watch.date().month,
-                    watch.date().date,
-                )
-                .and_hms((sunset / 60) as u32, (sunset % 60) as u32, 0)
-                .with_timezone(&Helsinki);
-            self.render_small_digits(display, local_time.hour() as u16, Point::new(66, 550), 2);
-            self.render_small_digits(display, local_time.minute() as u16, Point::new(110, 550), 2);
+    }
+
+    fn render_text(
+        &self,
+        display: &mut Display5in83,
+        text: &str,
+        position: Point,
+        alignment: Alignment,
+    ) {
+        // Position is the top point of the text, horizontally it is aligned to it
+        let text_style = TextStyleBuilder::new()
+            .alignment(alignment)
+            .baseline(Baseline::Top)
+            .build();
+        match &self.font {
+            Some(font) => {
+                let style = FontStyle::new(font, TriColor::Black);
+                Text::with_text_style(text, position, style, text_style)
+                    .draw(display)
+                    .unwrap();
+            }
+            None => {
+                //Built-in font covers Latin-1 only, but it is better than nothing
+                let style = MonoTextStyle::new(&FONT_7X13, TriColor::Black);
+                Text::with_text_style(text, position, style, text_style)
+                    .draw(display)
+                    .unwrap();
+            }
         }
     }
 
+    fn render_sun_time(
+        &self,
+        display: &mut Display5in83,
+        watch: &Watch,
+        minutes: u16,
+        position: Point,
+    ) {
+        //Sun events are in UTC minutes, DST is applied for the moment of the event
+        let local_minutes = UtcDateTime::new(
+            watch.date(),
+            (minutes / 60) as u32,
+            (minutes % 60) as u32,
+            0,
+        )
+        .and_then(|instant| watch.timezone().to_local(instant))
+        .map(|local| local.minute_of_day())
+        .unwrap_or(minutes);
+        self.render_small_digits(display, local_minutes / 60, position, 2);
+        self.render_small_digits(display, local_minutes % 60, position + Point::new(44, 0), 2);
+    }
+
+    fn render_polar_icon(
+        display: &mut Display5in83,
+        style: PrimitiveStyle<TriColor>,
+        position: Point,
+    ) {
+        // Icon takes the same space as a two digits number
+        Circle::new(position + Point::new(8, 0), 16)
+            .into_styled(style)
+            .draw(display)
+            .unwrap();
+    }
+
     fn render_small_digits(
         &self,
         display: &mut Display5in83,
@@ -190,8 +256,8 @@ impl Renderer {
         }
     }
 
-    fn mark_holiday(source: BinImage, date: Date) -> BinImage {
-        if date.day == 6 || date.day == 7 || is_holiday(date) {
+    fn mark_holiday(&self, source: BinImage, date: CalendarDate) -> BinImage {
+        if weekday(date) >= 6 || self.holidays.is_holiday(date) {
             source.force_chromatic()
         } else {
             source
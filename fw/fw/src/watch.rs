@@ -4,20 +4,35 @@
 //! * Syncs RTC with GPS data
 //! * Provides wakeup event from the RTC
 //! * Provides position information from the GPS
+//! * Converts RTC time, which is always UTC, to the local time
+//! * Looks up the timezone by the position
 
-use crate::gps::Gps;
+use crate::gps::{Gps, SENTENCE_LATENCY_MS};
+use crate::image_manager::ImageManager;
 use board::hal::datetime::{Date, Time, U32Ext};
 use board::hal::hal::timer::CountDown;
 use board::hal::pac::{GPIOD, RTC, USART2};
 use board::hal::pwr::CR1;
 use board::hal::rcc::{Clocks, AHB2, APB1R1, BDCR};
 use board::hal::rtc::{Event, Rtc, RtcConfig};
+use celestial::{weekday, CalendarDate, TimeZone, UtcDateTime};
+use tzmap::Point;
+
+// Zone index in BKP3 is marked with upper half, lower half is the index of the zone in the map
+const ZONE_MARKER: u32 = 0x7A0E_0000;
+// Zone index, stored when position is outside of the map
+const NO_ZONE: u16 = 0xFFFF;
+// BKP4 marks that BKP1/BKP2 keep decimal degrees. Older firmware stored raw ddmm.mmmm
+// values, multiplied by 10^4, and didn't write this marker
+const DECIMAL_POSITION: u32 = 0xDEC1_0001;
+const MILLIS_PER_DAY: u32 = 86_400_000;
 
 pub struct Watch {
-    date: Date,
+    date: CalendarDate,
     time: Time,
     lon: f32,
     lat: f32,
+    timezone: TimeZone,
 }
 
 impl Watch {
@@ -31,6 +46,7 @@ impl Watch {
         usart2: USART2,
         ahb2: &mut AHB2,
         clocks: Clocks,
+        image_manager: &ImageManager,
     ) -> Self {
         // Get RTC
         let rtc_config = RtcConfig::default();
@@ -39,7 +55,7 @@ impl Watch {
         // Manage sync flags
         // Sync flags are stored in BKP register 0. We use following 3 flags:
         // * 0xBEEF - No sync needed
-        // * 0xC0FE - Sync requested. This is set when we are between 05:00 and 06:00 on Sunday and
+        // * 0xC0FE - Sync requested. This is set when we are between 05:00 and 06:00 local time on Sunday and
         //             current code is 0xBEEF
         // * 0xC0CA - Sync is done. This is set by sync procedure and reset if we are outside of
         //            05:00-06:00 sync window, but code is still 0xC0CA
@@ -50,13 +66,6 @@ impl Watch {
             //before sync
             //Default timestamp is 2022 Jan 01 00:00:00
             //Default location is Helsinki
-            let rtc_date = Date {
-                day: 6,
-                date: 1,
-                month: 1,
-                year: 2022,
-            };
-
             let rtc_time = Time {
                 hours: 00,
                 minutes: 00,
@@ -64,46 +73,90 @@ impl Watch {
                 micros: 0,
                 daylight_savings: false,
             };
-            rtc.set_date_time(rtc_date, rtc_time);
-            rtc.write_backup_register(1, 24140159);
-            rtc.write_backup_register(2, 60058425);
+            rtc.set_date_time(Self::rtc_date(Self::default_date()), rtc_time);
+            rtc.write_backup_register(1, 24_233_598);
+            rtc.write_backup_register(2, 60_097_375);
+            rtc.write_backup_register(4, DECIMAL_POSITION);
+        }
+        if rtc.read_backup_register(4).unwrap_or(0) != DECIMAL_POSITION {
+            //Position is stored by the older firmware, convert it and look up the timezone again
+            for register in 1..=2 {
+                let legacy = rtc.read_backup_register(register).unwrap_or(0) as i32;
+                rtc.write_backup_register(register, nmea::legacy_coords_to_decimal(legacy) as u32);
+            }
+            rtc.write_backup_register(3, 0);
+            rtc.write_backup_register(4, DECIMAL_POSITION);
         }
         if flag_value != 0xBEEF && flag_value != 0xC0CA {
             //Any other value means that sync is needed
             let (gps_usart, gps_en) = board::init_uart(gpiod, usart2, ahb2, apb1r1, clocks);
             let mut gps = Gps::new(gps_usart, gps_en);
-            let (gps_date, gps_pos) = gps.sync_date_time();
-            if let Some((gps_d, gps_t)) = gps_date {
-                //We've seen at least time, that's enough
-                rtc.write_backup_register(0, 0xC0CA_u32); // Mark as synced
-
-                let weekday = celestial::weekday(gps_d.date, gps_d.month, gps_d.year);
-                let rtc_date = Date {
-                    day: weekday as u32,
-                    date: gps_d.date,
-                    month: gps_d.month,
-                    year: gps_d.year,
-                };
-
-                let rtc_time = Time {
-                    hours: gps_t.hour,
-                    minutes: gps_t.minute,
-                    seconds: gps_t.second,
-                    micros: 0,
-                    daylight_savings: false,
-                };
-                rtc.set_date_time(rtc_date, rtc_time);
+            //RTC is not synced yet, but it is still good enough to measure the time since reception
+            let (gps_date, gps_pos) = gps.sync_date_time(|| Self::rtc_millis(&rtc));
+            if let Some((gps_d, gps_t, received_at)) = gps_date {
+                let elapsed =
+                    (Self::rtc_millis(&rtc) + MILLIS_PER_DAY - received_at) % MILLIS_PER_DAY;
+                let setting = nmea::rtc_setting(gps_d, gps_t, SENTENCE_LATENCY_MS, elapsed);
+                if let Some((date, setting)) = setting.and_then(|s| {
+                    CalendarDate::new(s.date.date, s.date.month, s.date.year).map(|date| (date, s))
+                }) {
+                    //We've seen at least time, that's enough
+                    rtc.write_backup_register(0, 0xC0CA_u32); // Mark as synced
+
+                    //RTC is set to the whole second only, so wait for it
+                    cortex_m::asm::delay(setting.delay_ms * (clocks.sysclk().0 / 1000));
+                    let rtc_time = Time {
+                        hours: setting.time.hour,
+                        minutes: setting.time.minute,
+                        seconds: setting.time.second,
+                        micros: 0,
+                        daylight_savings: false,
+                    };
+                    rtc.set_date_time(Self::rtc_date(date), rtc_time);
+                }
             }
             if let Some(g_p) = gps_pos {
-                //Store the position
+                //Store the position and forget the timezone of the previous one
                 rtc.write_backup_register(1, g_p.lon as u32);
                 rtc.write_backup_register(2, g_p.lat as u32);
+                rtc.write_backup_register(3, 0);
             }
         }
-        let (date, time) = rtc.get_date_time();
+
+        //Get position, stored in BPK1 (lon) and bkp2 (lon)
+        //Coordinates are stored as integers, multiplied by 10^6, which gives good enough resolution
+        //for sun/moon calculations
+        let lon_i = rtc.read_backup_register(1).unwrap_or(0) as i32;
+        let lat_i = rtc.read_backup_register(2).unwrap_or(0) as i32;
+
+        let lon = lon_i as f32 / 1_000_000.0;
+        let lat = lat_i as f32 / 1_000_000.0;
+
+        let timezone = Self::timezone(&mut rtc, image_manager, Point::new(lon_i, lat_i));
+
+        let (rtc_date, rtc_time) = rtc.get_date_time();
+        let utc_date = CalendarDate::new(rtc_date.date, rtc_date.month, rtc_date.year)
+            .unwrap_or_else(Self::default_date);
+
+        //RTC keeps UTC, as it is synced from GPS, everything else uses local time
+        let local = UtcDateTime::new(utc_date, rtc_time.hours, rtc_time.minutes, rtc_time.seconds)
+            .and_then(|instant| timezone.to_local(instant));
+        let (date, time) = match local {
+            Some(local) => (
+                local.date(),
+                Time {
+                    hours: local.hour(),
+                    minutes: local.minute(),
+                    seconds: local.second(),
+                    micros: rtc_time.micros,
+                    daylight_savings: local.utc_offset() != timezone.offset,
+                },
+            ),
+            None => (utc_date, rtc_time),
+        };
 
         //Schedule sync for the next run if needed
-        if date.day == 7 && time.hours > 5 && time.hours < 6 {
+        if weekday(date) == 7 && time.hours == 5 {
             if flag_value == 0xBEEF {
                 rtc.write_backup_register(0, 0xC0FE_u32); // Request sync for the next run
             }
@@ -120,24 +173,16 @@ impl Watch {
             rtc.wakeup_timer().start(600.seconds());
         }
 
-        //Get position, stored in BPK1 (lon) and bkp2 (lon)
-        //Coordinates are stored as integers, multiplied by 10^6, which gives good enough resolution
-        //for sun/moon calculations
-        let lon_i = rtc.read_backup_register(1).unwrap_or(0) as i32;
-        let lat_i = rtc.read_backup_register(2).unwrap_or(0) as i32;
-
-        let lon = lon_i as f32 / 1_000_000.0;
-        let lat = lat_i as f32 / 1_000_000.0;
-
         Watch {
             date,
             time,
             lon,
             lat,
+            timezone,
         }
     }
 
-    pub fn date(&self) -> Date {
+    pub fn date(&self) -> CalendarDate {
         self.date
     }
     pub fn time(&self) -> Time {
@@ -149,4 +194,54 @@ impl Watch {
     pub fn lat(&self) -> f32 {
         self.lat
     }
+    pub fn timezone(&self) -> TimeZone {
+        self.timezone
+    }
+
+    /// Milliseconds since the midnight by the RTC
+    fn rtc_millis(rtc: &Rtc) -> u32 {
+        let (_, time) = rtc.get_date_time();
+        ((time.hours * 60 + time.minutes) * 60 + time.seconds) * 1000 + time.micros / 1000
+    }
+
+    fn timezone(rtc: &mut Rtc, image_manager: &ImageManager, position: Point) -> TimeZone {
+        //Timezone map lookup is too slow to repeat it on every wakeup, so found zone index
+        //is stored in BKP3 and is looked up again only after position change
+        let timezone_map = image_manager.timezone_map();
+        let stored = rtc.read_backup_register(3).unwrap_or(0);
+        let zone_index = if stored & 0xFFFF_0000 == ZONE_MARKER {
+            stored as u16
+        } else {
+            let zone_index = timezone_map
+                .and_then(|map| map.lookup(position))
+                .unwrap_or(NO_ZONE);
+            rtc.write_backup_register(3, ZONE_MARKER | zone_index as u32);
+            zone_index
+        };
+
+        //Configured timezone is used outside of the map
+        timezone_map
+            .and_then(|map| map.zone(zone_index))
+            .and_then(|zone| TimeZone::parse(zone.posix))
+            .or_else(|| image_manager.timezone())
+            .unwrap_or_else(Self::default_timezone)
+    }
+
+    fn default_timezone() -> TimeZone {
+        //Finnish time, used with flash images without timezone configuration
+        TimeZone::parse("EET-2EEST,M3.5.0/3,M10.5.0/4").unwrap_or(TimeZone::UTC)
+    }
+
+    fn default_date() -> CalendarDate {
+        CalendarDate::new(1, 1, 2022).unwrap() //Always valid
+    }
+
+    fn rtc_date(date: CalendarDate) -> Date {
+        Date {
+            day: celestial::weekday(date) as u32,
+            date: date.day(),
+            month: date.month(),
+            year: date.year(),
+        }
+    }
 }
@@ -0,0 +1,184 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Compact binary representation of holidays definitions, used in flash images
+//!
+//! Each record has 9 bytes header, followed by UTF-8 name:
+//! * kind: 0 - public holiday, 1 - flag day, 2 - observance
+//! * rule type: 0 - fixed, 1 - nth weekday, 2 - last weekday, 3 - western Easter,
+//!   4 - orthodox Easter, 5 - weekday from
+//! * 3 bytes of rule parameters, in the order of rule fields, Easter offset is i16 LE
+//! * substitution: 0 - none, 1 - next free weekday, 2 - nearest weekday
+//! * u16 LE first year, 0 if always
+//! * name length
+
+use crate::{Holiday, HolidayKind, Rule, Substitution};
+use celestial::Church;
+
+/// Size of the record without name
+pub const RECORD_HEADER_SIZE: usize = 9;
+
+impl<'a> Holiday<'a> {
+    /// Size of the binary record of the holiday
+    pub fn encoded_size(&self) -> usize {
+        RECORD_HEADER_SIZE + self.name.len()
+    }
+
+    /// Writes binary record of the holiday to the buffer
+    ///
+    /// Returns number of bytes written or None if buffer is too small, the name
+    /// is longer than 255 bytes or the first year does not fit into u16
+    pub fn encode(&self, buffer: &mut [u8]) -> Option<usize> {
+        let size = self.encoded_size();
+        if buffer.len() < size || self.name.len() > u8::MAX as usize {
+            return None
+        }
+        let since = match self.since {
+            Some(year) if year == 0 || year > u16::MAX as u32 => return None,
+            Some(year) => year as u16,
+            None => 0,
+        };
+        buffer[0] = match self.kind {
+            HolidayKind::Public => 0,
+            HolidayKind::FlagDay => 1,
+            HolidayKind::Observance => 2,
+        };
+        let (rule_type, parameters) = match self.rule {
+            Rule::Fixed { day, month } => (0, [day, month, 0]),
+            Rule::NthWeekday { n, weekday, month } => (1, [n, weekday, month]),
+            Rule::LastWeekday { weekday, month } => (2, [weekday, month, 0]),
+            Rule::Easter { offset, church } => {
+                let offset = offset.to_le_bytes();
+                let rule_type = match church {
+                    Church::Western => 3,
+                    Church::Orthodox => 4,
+                };
+                (rule_type, [offset[0], offset[1], 0])
+            }
+            Rule::WeekdayFrom { weekday, day, month } => (5, [weekday, day, month]),
+        };
+        buffer[1] = rule_type;
+        buffer[2..5].copy_from_slice(&parameters);
+        buffer[5] = match self.substitution {
+            Substitution::None => 0,
+            Substitution::NextFreeWeekday => 1,
+            Substitution::NearestWeekday => 2,
+        };
+        buffer[6..8].copy_from_slice(&since.to_le_bytes());
+        buffer[8] = self.name.len() as u8;
+        buffer[RECORD_HEADER_SIZE..size].copy_from_slice(self.name.as_bytes());
+        Some(size)
+    }
+
+    /// Reads a holiday from the beginning of the binary record
+    ///
+    /// Returns the holiday and the record size
+    pub fn decode(bytes: &'a [u8]) -> Option<(Self, usize)> {
+        let header = bytes.get(0..RECORD_HEADER_SIZE)?;
+        let kind = match header[0] {
+            0 => HolidayKind::Public,
+            1 => HolidayKind::FlagDay,
+            2 => HolidayKind::Observance,
+            _ => return None,
+        };
+        let (a, b, c) = (header[2], header[3], header[4]);
+        let rule = match header[1] {
+            0 => Rule::Fixed { day: a, month: b },
+            1 => Rule::NthWeekday { n: a, weekday: b, month: c },
+            2 => Rule::LastWeekday { weekday: a, month: b },
+            3 => Rule::Easter { offset: i16::from_le_bytes([a, b]), church: Church::Western },
+            4 => Rule::Easter { offset: i16::from_le_bytes([a, b]), church: Church::Orthodox },
+            5 => Rule::WeekdayFrom { weekday: a, day: b, month: c },
+            _ => return None,
+        };
+        let substitution = match header[5] {
+            0 => Substitution::None,
+            1 => Substitution::NextFreeWeekday,
+            2 => Substitution::NearestWeekday,
+            _ => return None,
+        };
+        let since = match u16::from_le_bytes([header[6], header[7]]) {
+            0 => None,
+            year => Some(year as u32),
+        };
+        let size = RECORD_HEADER_SIZE + header[8] as usize;
+        let name = core::str::from_utf8(bytes.get(RECORD_HEADER_SIZE..size)?).ok()?;
+        Some((Holiday { name, kind, rule, substitution, since }, size))
+    }
+}
+
+/// Iterator over the holidays, stored as a sequence of binary records
+///
+/// Iteration stops at the first malformed record
+pub struct HolidayRecords<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> HolidayRecords<'a> {
+    /// Creates iterator over the binary records
+    pub fn new(bytes: &'a [u8]) -> Self {
+        HolidayRecords { bytes }
+    }
+}
+
+impl<'a> Iterator for HolidayRecords<'a> {
+    type Item = Holiday<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (holiday, size) = Holiday::decode(self.bytes)?;
+        self.bytes = &self.bytes[size..];
+        Some(holiday)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Country;
+
+    #[test]
+    fn built_in_holidays_round_trip() {
+        for country in [Country::Finland, Country::Sweden, Country::Germany, Country::UnitedKingdom, Country::UnitedStates] {
+            let mut buffer = vec![0u8; 4096];
+            let mut size = 0;
+            for holiday in country.holidays() {
+                size += holiday.encode(&mut buffer[size..]).unwrap();
+            }
+            let decoded: Vec<Holiday> = HolidayRecords::new(&buffer[..size]).collect();
+            assert_eq!(decoded.as_slice(), country.holidays());
+        }
+    }
+
+    #[test]
+    fn negative_easter_offset() {
+        let holiday = Holiday {
+            name: "Good Friday",
+            kind: HolidayKind::Public,
+            rule: Rule::Easter { offset: -2, church: Church::Orthodox },
+            substitution: Substitution::None,
+            since: Some(2000),
+        };
+        let mut buffer = [0u8; 32];
+        assert_eq!(holiday.encode(&mut buffer), Some(20));
+        assert_eq!(Holiday::decode(&buffer), Some((holiday, 20)));
+    }
+
+    #[test]
+    fn small_buffer() {
+        let holiday = Country::Finland.holidays()[0];
+        let mut buffer = [0u8; 10];
+        assert_eq!(holiday.encode(&mut buffer), None);
+    }
+
+    #[test]
+    fn malformed_records_stop_iteration() {
+        let mut buffer = [0u8; 64];
+        let size = Country::Finland.holidays()[0].encode(&mut buffer).unwrap();
+        // Unknown rule type
+        buffer[size + 1] = 42;
+        assert_eq!(HolidayRecords::new(&buffer).count(), 1);
+        // Truncated name
+        assert_eq!(HolidayRecords::new(&buffer[..size - 1]).count(), 0);
+        assert_eq!(HolidayRecords::new(&[]).count(), 0);
+    }
+}
@@ -0,0 +1,337 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Built-in holidays of several countries
+
+use crate::{Holiday, HolidayCalendar, HolidayKind, Rule, Substitution};
+use celestial::Church;
+
+/// Country with built-in holidays
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Country {
+    /// Finland, FI
+    Finland,
+    /// Sweden, SE
+    Sweden,
+    /// Germany, DE, nationwide holidays only
+    Germany,
+    /// United Kingdom, GB (or UK), England and Wales bank holidays
+    UnitedKingdom,
+    /// United States, US, federal holidays
+    UnitedStates,
+}
+
+impl Country {
+    /// Finds a country by ISO 3166-1 alpha-2 code, case insensitive
+    pub fn from_code(code: &str) -> Option<Self> {
+        let upper = |c: u8| c.to_ascii_uppercase();
+        match code.as_bytes() {
+            [a, b] => match (upper(*a), upper(*b)) {
+                (b'F', b'I') => Some(Country::Finland),
+                (b'S', b'E') => Some(Country::Sweden),
+                (b'D', b'E') => Some(Country::Germany),
+                (b'G', b'B') | (b'U', b'K') => Some(Country::UnitedKingdom),
+                (b'U', b'S') => Some(Country::UnitedStates),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// ISO 3166-1 alpha-2 code of the country
+    pub fn code(self) -> &'static str {
+        match self {
+            Country::Finland => "FI",
+            Country::Sweden => "SE",
+            Country::Germany => "DE",
+            Country::UnitedKingdom => "GB",
+            Country::UnitedStates => "US",
+        }
+    }
+
+    /// Holidays definitions of the country
+    pub fn holidays(self) -> &'static [Holiday<'static>] {
+        match self {
+            Country::Finland => &FINLAND,
+            Country::Sweden => &SWEDEN,
+            Country::Germany => &GERMANY,
+            Country::UnitedKingdom => &UNITED_KINGDOM,
+            Country::UnitedStates => &UNITED_STATES,
+        }
+    }
+
+    /// Holiday calendar of the country
+    pub fn calendar(self) -> HolidayCalendar<'static> {
+        HolidayCalendar::new(self.holidays())
+    }
+}
+
+const fn fixed(name: &'static str, kind: HolidayKind, day: u8, month: u8) -> Holiday<'static> {
+    Holiday { name, kind, rule: Rule::Fixed { day, month }, substitution: Substitution::None, since: None }
+}
+
+const fn easter(name: &'static str, offset: i16) -> Holiday<'static> {
+    Holiday {
+        name,
+        kind: HolidayKind::Public,
+        rule: Rule::Easter { offset, church: Church::Western },
+        substitution: Substitution::None,
+        since: None,
+    }
+}
+
+const fn nth(name: &'static str, kind: HolidayKind, n: u8, weekday: u8, month: u8) -> Holiday<'static> {
+    Holiday { name, kind, rule: Rule::NthWeekday { n, weekday, month }, substitution: Substitution::None, since: None }
+}
+
+const fn last(name: &'static str, kind: HolidayKind, weekday: u8, month: u8) -> Holiday<'static> {
+    Holiday { name, kind, rule: Rule::LastWeekday { weekday, month }, substitution: Substitution::None, since: None }
+}
+
+const fn week_from(name: &'static str, weekday: u8, day: u8, month: u8) -> Holiday<'static> {
+    Holiday {
+        name,
+        kind: HolidayKind::Public,
+        rule: Rule::WeekdayFrom { weekday, day, month },
+        substitution: Substitution::None,
+        since: None,
+    }
+}
+
+const fn substituted(holiday: Holiday<'static>, substitution: Substitution) -> Holiday<'static> {
+    Holiday { substitution, ..holiday }
+}
+
+const fn since(holiday: Holiday<'static>, year: u32) -> Holiday<'static> {
+    Holiday { since: Some(year), ..holiday }
+}
+
+// Midsummer Eve and Christmas Eve are not official holidays,
+// but are days off in practice, so they are treated as public holidays
+const FINLAND: [Holiday<'static>; 19] = [
+    fixed("New Year's Day", HolidayKind::Public, 1, 1),
+    fixed("Epiphany", HolidayKind::Public, 6, 1),
+    fixed("Runeberg Day", HolidayKind::FlagDay, 5, 2),
+    fixed("Kalevala Day", HolidayKind::FlagDay, 28, 2),
+    easter("Good Friday", -2),
+    easter("Easter Sunday", 0),
+    easter("Easter Monday", 1),
+    fixed("May Day", HolidayKind::Public, 1, 5),
+    nth("Mother's Day", HolidayKind::FlagDay, 2, 7, 5),
+    easter("Ascension Day", 39),
+    easter("Whitsunday", 49),
+    week_from("Midsummer Eve", 5, 19, 6),
+    week_from("Midsummer Day", 6, 20, 6),
+    week_from("All Saints' Day", 6, 31, 10),
+    nth("Father's Day", HolidayKind::FlagDay, 2, 7, 11),
+    fixed("Independence Day", HolidayKind::Public, 6, 12),
+    fixed("Christmas Eve", HolidayKind::Public, 24, 12),
+    fixed("Christmas Day", HolidayKind::Public, 25, 12),
+    fixed("St. Stephen's Day", HolidayKind::Public, 26, 12),
+];
+
+// Midsummer Eve, Christmas Eve and New Year's Eve are days off in practice
+const SWEDEN: [Holiday<'static>; 17] = [
+    fixed("New Year's Day", HolidayKind::Public, 1, 1),
+    fixed("Epiphany", HolidayKind::Public, 6, 1),
+    easter("Good Friday", -2),
+    easter("Easter Sunday", 0),
+    easter("Easter Monday", 1),
+    fixed("May Day", HolidayKind::Public, 1, 5),
+    last("Mother's Day", HolidayKind::Observance, 7, 5),
+    easter("Ascension Day", 39),
+    easter("Whitsunday", 49),
+    since(fixed("National Day", HolidayKind::Public, 6, 6), 2005),
+    week_from("Midsummer Eve", 5, 19, 6),
+    week_from("Midsummer Day", 6, 20, 6),
+    week_from("All Saints' Day", 6, 31, 10),
+    fixed("Christmas Eve", HolidayKind::Public, 24, 12),
+    fixed("Christmas Day", HolidayKind::Public, 25, 12),
+    fixed("Boxing Day", HolidayKind::Public, 26, 12),
+    fixed("New Year's Eve", HolidayKind::Public, 31, 12),
+];
+
+const GERMANY: [Holiday<'static>; 10] = [
+    fixed("New Year's Day", HolidayKind::Public, 1, 1),
+    easter("Good Friday", -2),
+    easter("Easter Monday", 1),
+    fixed("Labour Day", HolidayKind::Public, 1, 5),
+    nth("Mother's Day", HolidayKind::Observance, 2, 7, 5),
+    easter("Ascension Day", 39),
+    easter("Whit Monday", 50),
+    since(fixed("German Unity Day", HolidayKind::Public, 3, 10), 1990),
+    fixed("Christmas Day", HolidayKind::Public, 25, 12),
+    fixed("Boxing Day", HolidayKind::Public, 26, 12),
+];
+
+const UNITED_KINGDOM: [Holiday<'static>; 8] = [
+    substituted(fixed("New Year's Day", HolidayKind::Public, 1, 1), Substitution::NextFreeWeekday),
+    easter("Good Friday", -2),
+    easter("Easter Monday", 1),
+    nth("Early May Bank Holiday", HolidayKind::Public, 1, 1, 5),
+    last("Spring Bank Holiday", HolidayKind::Public, 1, 5),
+    last("Summer Bank Holiday", HolidayKind::Public, 1, 8),
+    substituted(fixed("Christmas Day", HolidayKind::Public, 25, 12), Substitution::NextFreeWeekday),
+    substituted(fixed("Boxing Day", HolidayKind::Public, 26, 12), Substitution::NextFreeWeekday),
+];
+
+const UNITED_STATES: [Holiday<'static>; 14] = [
+    substituted(fixed("New Year's Day", HolidayKind::Public, 1, 1), Substitution::NearestWeekday),
+    since(nth("Martin Luther King Jr. Day", HolidayKind::Public, 3, 1, 1), 1986),
+    nth("Washington's Birthday", HolidayKind::Public, 3, 1, 2),
+    nth("Mother's Day", HolidayKind::Observance, 2, 7, 5),
+    last("Memorial Day", HolidayKind::Public, 1, 5),
+    fixed("Flag Day", HolidayKind::FlagDay, 14, 6),
+    nth("Father's Day", HolidayKind::Observance, 3, 7, 6),
+    since(substituted(fixed("Juneteenth", HolidayKind::Public, 19, 6), Substitution::NearestWeekday), 2021),
+    substituted(fixed("Independence Day", HolidayKind::Public, 4, 7), Substitution::NearestWeekday),
+    nth("Labor Day", HolidayKind::Public, 1, 1, 9),
+    nth("Columbus Day", HolidayKind::Public, 2, 1, 10),
+    substituted(fixed("Veterans Day", HolidayKind::Public, 11, 11), Substitution::NearestWeekday),
+    nth("Thanksgiving Day", HolidayKind::Public, 4, 4, 11),
+    substituted(fixed("Christmas Day", HolidayKind::Public, 25, 12), Substitution::NearestWeekday),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestial::CalendarDate;
+
+    fn date(day: u32, month: u32, year: u32) -> CalendarDate {
+        CalendarDate::new(day, month, year).unwrap()
+    }
+
+    fn names(country: Country, day: u32, month: u32, year: u32) -> Vec<&'static str> {
+        country.calendar().holidays_on(date(day, month, year)).map(|o| o.holiday.name).collect()
+    }
+
+    fn is_day_off(country: Country, day: u32, month: u32, year: u32) -> bool {
+        country.calendar().is_public_holiday(date(day, month, year))
+    }
+
+    #[test]
+    fn country_codes() {
+        assert_eq!(Country::from_code("FI"), Some(Country::Finland));
+        assert_eq!(Country::from_code("se"), Some(Country::Sweden));
+        assert_eq!(Country::from_code("De"), Some(Country::Germany));
+        assert_eq!(Country::from_code("UK"), Some(Country::UnitedKingdom));
+        assert_eq!(Country::from_code("GB"), Some(Country::UnitedKingdom));
+        assert_eq!(Country::from_code("US"), Some(Country::UnitedStates));
+        assert_eq!(Country::from_code("RU"), None);
+        assert_eq!(Country::from_code("FIN"), None);
+        for country in [Country::Finland, Country::Sweden, Country::Germany, Country::UnitedKingdom, Country::UnitedStates] {
+            assert_eq!(Country::from_code(country.code()), Some(country));
+        }
+    }
+
+    #[test]
+    fn finland_2022() {
+        // Days, that were hard-coded in the firmware before
+        let days_off = [(1, 1), (6, 1), (15, 4), (18, 4), (1, 5), (26, 5), (24, 6), (6, 12), (24, 12), (25, 12), (26, 12)];
+        for (day, month) in days_off.iter() {
+            assert!(is_day_off(Country::Finland, *day, *month, 2022), "{}.{}", day, month);
+        }
+        assert!(is_day_off(Country::Finland, 25, 6, 2022));
+        assert!(is_day_off(Country::Finland, 5, 11, 2022));
+        assert!(!is_day_off(Country::Finland, 23, 6, 2022));
+    }
+
+    #[test]
+    fn finland_movable_holidays() {
+        assert_eq!(names(Country::Finland, 7, 4, 2023), vec!["Good Friday"]);
+        assert_eq!(names(Country::Finland, 10, 4, 2023), vec!["Easter Monday"]);
+        assert_eq!(names(Country::Finland, 18, 5, 2023), vec!["Ascension Day"]);
+        assert_eq!(names(Country::Finland, 23, 6, 2023), vec!["Midsummer Eve"]);
+        assert_eq!(names(Country::Finland, 4, 11, 2023), vec!["All Saints' Day"]);
+        assert_eq!(names(Country::Finland, 21, 6, 2024), vec!["Midsummer Eve"]);
+        assert_eq!(names(Country::Finland, 2, 11, 2024), vec!["All Saints' Day"]);
+    }
+
+    #[test]
+    fn finland_flag_days() {
+        let calendar = Country::Finland.calendar();
+        let mothers_day = calendar.holidays_on(date(14, 5, 2023)).next().unwrap();
+        assert_eq!(mothers_day.holiday.name, "Mother's Day");
+        assert_eq!(mothers_day.holiday.kind, HolidayKind::FlagDay);
+        assert_eq!(names(Country::Finland, 12, 11, 2023), vec!["Father's Day"]);
+        assert!(!is_day_off(Country::Finland, 5, 2, 2024));
+    }
+
+    #[test]
+    fn sweden() {
+        assert!(is_day_off(Country::Sweden, 6, 6, 2022));
+        assert!(!is_day_off(Country::Sweden, 6, 6, 2004));
+        assert_eq!(names(Country::Sweden, 24, 6, 2022), vec!["Midsummer Eve"]);
+        assert_eq!(names(Country::Sweden, 5, 11, 2022), vec!["All Saints' Day"]);
+        assert_eq!(names(Country::Sweden, 28, 5, 2023), vec!["Mother's Day", "Whitsunday"]);
+        assert!(is_day_off(Country::Sweden, 31, 12, 2023));
+        assert!(is_day_off(Country::Sweden, 29, 3, 2024));
+    }
+
+    #[test]
+    fn germany() {
+        assert!(is_day_off(Country::Germany, 3, 10, 2022));
+        assert!(!is_day_off(Country::Germany, 3, 10, 1989));
+        assert_eq!(names(Country::Germany, 6, 6, 2022), vec!["Whit Monday"]);
+        assert_eq!(names(Country::Germany, 29, 5, 2023), vec!["Whit Monday"]);
+        assert_eq!(names(Country::Germany, 9, 5, 2024), vec!["Ascension Day"]);
+        assert!(!is_day_off(Country::Germany, 6, 1, 2022));
+        assert!(!is_day_off(Country::Germany, 24, 12, 2022));
+    }
+
+    #[test]
+    fn united_kingdom() {
+        assert_eq!(names(Country::UnitedKingdom, 3, 5, 2021), vec!["Early May Bank Holiday"]);
+        assert_eq!(names(Country::UnitedKingdom, 31, 5, 2021), vec!["Spring Bank Holiday"]);
+        assert_eq!(names(Country::UnitedKingdom, 30, 8, 2021), vec!["Summer Bank Holiday"]);
+        assert_eq!(names(Country::UnitedKingdom, 28, 8, 2023), vec!["Summer Bank Holiday"]);
+        assert_eq!(names(Country::UnitedKingdom, 27, 5, 2024), vec!["Spring Bank Holiday"]);
+    }
+
+    #[test]
+    fn united_kingdom_substitutions() {
+        // Christmas on Saturday and Boxing Day on Sunday
+        assert!(is_day_off(Country::UnitedKingdom, 27, 12, 2021));
+        assert!(is_day_off(Country::UnitedKingdom, 28, 12, 2021));
+        // New Year's Day on Saturday
+        assert!(is_day_off(Country::UnitedKingdom, 3, 1, 2022));
+        // Christmas on Sunday, Boxing Day stays on Monday
+        let calendar = Country::UnitedKingdom.calendar();
+        let occurrence = calendar.holidays_on(date(27, 12, 2022)).next().unwrap();
+        assert_eq!(occurrence.holiday.name, "Christmas Day");
+        assert!(occurrence.observed);
+        assert_eq!(names(Country::UnitedKingdom, 26, 12, 2022), vec!["Boxing Day"]);
+        // New Year's Day on Sunday
+        assert!(is_day_off(Country::UnitedKingdom, 2, 1, 2023));
+        assert!(!is_day_off(Country::UnitedKingdom, 3, 1, 2023));
+    }
+
+    #[test]
+    fn united_states() {
+        assert_eq!(names(Country::UnitedStates, 17, 1, 2022), vec!["Martin Luther King Jr. Day"]);
+        assert_eq!(names(Country::UnitedStates, 20, 2, 2023), vec!["Washington's Birthday"]);
+        assert_eq!(names(Country::UnitedStates, 27, 5, 2024), vec!["Memorial Day"]);
+        assert_eq!(names(Country::UnitedStates, 4, 9, 2023), vec!["Labor Day"]);
+        assert_eq!(names(Country::UnitedStates, 9, 10, 2023), vec!["Columbus Day"]);
+        assert_eq!(names(Country::UnitedStates, 24, 11, 2022), vec!["Thanksgiving Day"]);
+        assert_eq!(names(Country::UnitedStates, 23, 11, 2023), vec!["Thanksgiving Day"]);
+        assert_eq!(names(Country::UnitedStates, 14, 6, 2023), vec!["Flag Day"]);
+        assert!(!is_day_off(Country::UnitedStates, 19, 6, 2020));
+        assert!(is_day_off(Country::UnitedStates, 19, 6, 2023));
+    }
+
+    #[test]
+    fn united_states_substitutions() {
+        // New Year's Day 2022 on Saturday is observed in 2021
+        assert!(is_day_off(Country::UnitedStates, 31, 12, 2021));
+        // Independence Day 2021 on Sunday
+        assert!(is_day_off(Country::UnitedStates, 5, 7, 2021));
+        // Juneteenth 2022 on Sunday
+        assert!(is_day_off(Country::UnitedStates, 20, 6, 2022));
+        // Christmas 2022 on Sunday
+        assert!(is_day_off(Country::UnitedStates, 26, 12, 2022));
+        // Veterans Day 2023 on Saturday
+        assert!(is_day_off(Country::UnitedStates, 10, 11, 2023));
+        assert!(!is_day_off(Country::UnitedStates, 13, 11, 2023));
+    }
+}
@@ -0,0 +1,352 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+#![cfg_attr(not(test), no_std)]
+
+//! Rule based public holidays calendar
+//!
+//! * fixed dates, nth and last weekdays of a month, Easter offsets
+//! * weekday in a week, starting with a fixed date (Nordic Midsummer and All Saints' Day)
+//! * weekend substitutions
+//! * built-in rules for several countries
+//! * textual and compact binary rules representation
+
+mod binary;
+mod countries;
+mod text;
+
+use celestial::{easter_sunday, weekday, CalendarDate, Church};
+
+pub use binary::HolidayRecords as HolidayRecords;
+pub use binary::RECORD_HEADER_SIZE as RECORD_HEADER_SIZE;
+pub use countries::Country as Country;
+
+/// Significance of a holiday
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HolidayKind {
+    /// Public holiday, a day off
+    Public,
+    /// Official flag day, not a day off
+    FlagDay,
+    /// Commonly observed day, not a day off
+    Observance,
+}
+
+/// Way to find the holiday date in a year
+///
+/// Weekdays are numbered as in [celestial::weekday]: 1 - Monday, 7 - Sunday
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    /// Same day every year
+    Fixed {
+        /// Day of month starting with 1
+        day: u8,
+        /// Month of year starting with 1
+        month: u8,
+    },
+    /// N-th weekday of a month, like the second Sunday of May
+    NthWeekday {
+        /// Number of the weekday in the month, starting with 1
+        n: u8,
+        /// Weekday
+        weekday: u8,
+        /// Month of year starting with 1
+        month: u8,
+    },
+    /// Last weekday of a month, like the last Monday of May
+    LastWeekday {
+        /// Weekday
+        weekday: u8,
+        /// Month of year starting with 1
+        month: u8,
+    },
+    /// Days after (or before, if negative) the Easter Sunday
+    Easter {
+        /// Offset in days
+        offset: i16,
+        /// Church, which Easter is used
+        church: Church,
+    },
+    /// Weekday during a week, that starts with a fixed date,
+    /// like Saturday between 20th and 26th of June
+    WeekdayFrom {
+        /// Weekday
+        weekday: u8,
+        /// First day of the week
+        day: u8,
+        /// Month of the first day of the week
+        month: u8,
+    },
+}
+
+impl Rule {
+    /// Calculates date of the rule in the year
+    pub fn date(&self, year: u32) -> Option<CalendarDate> {
+        match *self {
+            Rule::Fixed { day, month } => CalendarDate::new(day as u32, month as u32, year),
+            Rule::NthWeekday { n, weekday: wd, month } => {
+                let first = CalendarDate::new(1, month as u32, year)?;
+                let offset = days_until(first, wd) + 7 * (n as i32 - 1);
+                first.add_days(offset).filter(|date| n > 0 && date.month() == month as u32)
+            }
+            Rule::LastWeekday { weekday: wd, month } => {
+                let first = CalendarDate::new(1, month as u32, year)?;
+                let last = first.add_days(first.days_in_month() as i32 - 1)?;
+                last.add_days(-((weekday(last) as i32 - wd as i32 + 7) % 7))
+            }
+            Rule::Easter { offset, church } => easter_sunday(year, church)?.add_days(offset as i32),
+            Rule::WeekdayFrom { weekday: wd, day, month } => {
+                let first = CalendarDate::new(day as u32, month as u32, year)?;
+                first.add_days(days_until(first, wd))
+            }
+        }
+    }
+}
+
+/// Days from the date to the nearest weekday on or after it
+fn days_until(date: CalendarDate, wd: u8) -> i32 {
+    (wd as i32 - weekday(date) as i32 + 7) % 7
+}
+
+/// Moving a holiday, that falls on a weekend
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Substitution {
+    /// Holiday is not moved
+    None,
+    /// Holiday is observed on the next weekday, that is not a holiday itself (UK)
+    NextFreeWeekday,
+    /// Saturday holiday is observed on Friday, Sunday holiday on Monday (US)
+    NearestWeekday,
+}
+
+/// Holiday definition
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Holiday<'a> {
+    /// Holiday name
+    pub name: &'a str,
+    /// Holiday significance
+    pub kind: HolidayKind,
+    /// Holiday date rule
+    pub rule: Rule,
+    /// Weekend substitution rule
+    pub substitution: Substitution,
+    /// First year, when the holiday is celebrated, None if always
+    pub since: Option<u32>,
+}
+
+impl<'a> Holiday<'a> {
+    /// Calculates actual date of the holiday in the year
+    pub fn date(&self, year: u32) -> Option<CalendarDate> {
+        if self.since.map(|since| year < since).unwrap_or(false) {
+            None
+        } else {
+            self.rule.date(year)
+        }
+    }
+}
+
+/// Holiday, happening on some date
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Occurrence<'a> {
+    /// Holiday definition
+    pub holiday: &'a Holiday<'a>,
+    /// True if the date is a weekday substitution for the holiday
+    pub observed: bool,
+}
+
+/// Set of holidays, usually of some country
+#[derive(Clone, Copy, Debug)]
+pub struct HolidayCalendar<'a> {
+    holidays: &'a [Holiday<'a>],
+}
+
+impl<'a> HolidayCalendar<'a> {
+    /// Creates calendar from the holidays definitions
+    pub fn new(holidays: &'a [Holiday<'a>]) -> Self {
+        HolidayCalendar { holidays }
+    }
+
+    /// Lists all holidays, happening or observed on the date
+    pub fn holidays_on(&self, date: CalendarDate) -> impl Iterator<Item = Occurrence<'a>> + 'a {
+        let calendar = *self;
+        self.holidays.iter().enumerate().filter_map(move |(index, holiday)| {
+            // Substituted days may cross the year boundary
+            let years = [date.year() - 1, date.year(), date.year() + 1];
+            if holiday.date(date.year()) == Some(date) {
+                Some(Occurrence { holiday, observed: false })
+            } else if years.iter().any(|year| calendar.observed_date(index, *year) == Some(date)) {
+                Some(Occurrence { holiday, observed: true })
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Checks if the date is a day off due to a public holiday
+    pub fn is_public_holiday(&self, date: CalendarDate) -> bool {
+        self.holidays_on(date).any(|occurrence| occurrence.holiday.kind == HolidayKind::Public)
+    }
+
+    /// Calculates date, when the holiday is observed in the year
+    pub fn observed_date(&self, index: usize, year: u32) -> Option<CalendarDate> {
+        let holiday = self.holidays.get(index)?;
+        let date = holiday.date(year)?;
+        match holiday.substitution {
+            Substitution::None => Some(date),
+            Substitution::NearestWeekday => match weekday(date) {
+                6 => date.prev_day(),
+                7 => date.next_day(),
+                _ => Some(date),
+            },
+            Substitution::NextFreeWeekday => {
+                if weekday(date) < 6 {
+                    return Some(date)
+                }
+                let mut candidate = date.next_day()?;
+                // There is always a free day in a week
+                for _ in 0..7 {
+                    if weekday(candidate) < 6 && !self.is_taken(candidate, index, year) {
+                        break
+                    }
+                    candidate = candidate.next_day()?;
+                }
+                Some(candidate)
+            }
+        }
+    }
+
+    /// Checks if the day is already a public holiday or taken by a previous substitution
+    fn is_taken(&self, date: CalendarDate, before: usize, year: u32) -> bool {
+        self.holidays.iter().enumerate().any(|(index, holiday)| {
+            holiday.kind == HolidayKind::Public
+                && (holiday.date(year) == Some(date)
+                    || (index < before && holiday.substitution != Substitution::None && self.observed_date(index, year) == Some(date)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32, month: u32, year: u32) -> CalendarDate {
+        CalendarDate::new(day, month, year).unwrap()
+    }
+
+    #[test]
+    fn fixed_rule() {
+        let rule = Rule::Fixed { day: 6, month: 12 };
+        assert_eq!(rule.date(2022), Some(date(6, 12, 2022)));
+        assert_eq!(Rule::Fixed { day: 30, month: 2 }.date(2022), None);
+    }
+
+    #[test]
+    fn nth_weekday_rule() {
+        // Second Sunday of May
+        let rule = Rule::NthWeekday { n: 2, weekday: 7, month: 5 };
+        assert_eq!(rule.date(2021), Some(date(9, 5, 2021)));
+        assert_eq!(rule.date(2022), Some(date(8, 5, 2022)));
+        // Month starts with the weekday
+        assert_eq!(Rule::NthWeekday { n: 1, weekday: 3, month: 6 }.date(2022), Some(date(1, 6, 2022)));
+        // There are no fifth Monday in February 2022
+        assert_eq!(Rule::NthWeekday { n: 5, weekday: 1, month: 2 }.date(2022), None);
+        assert_eq!(Rule::NthWeekday { n: 0, weekday: 1, month: 2 }.date(2022), None);
+    }
+
+    #[test]
+    fn last_weekday_rule() {
+        // Last Monday of May
+        let rule = Rule::LastWeekday { weekday: 1, month: 5 };
+        assert_eq!(rule.date(2021), Some(date(31, 5, 2021)));
+        assert_eq!(rule.date(2022), Some(date(30, 5, 2022)));
+        assert_eq!(rule.date(2023), Some(date(29, 5, 2023)));
+    }
+
+    #[test]
+    fn easter_rule() {
+        let rule = Rule::Easter { offset: -2, church: Church::Western };
+        assert_eq!(rule.date(2022), Some(date(15, 4, 2022)));
+        let rule = Rule::Easter { offset: 1, church: Church::Orthodox };
+        assert_eq!(rule.date(2022), Some(date(25, 4, 2022)));
+    }
+
+    #[test]
+    fn weekday_from_rule() {
+        let rule = Rule::WeekdayFrom { weekday: 6, day: 31, month: 10 };
+        assert_eq!(rule.date(2020), Some(date(31, 10, 2020)));
+        assert_eq!(rule.date(2021), Some(date(6, 11, 2021)));
+    }
+
+    #[test]
+    fn holiday_since() {
+        let holiday = Holiday {
+            name: "Test",
+            kind: HolidayKind::Public,
+            rule: Rule::Fixed { day: 1, month: 1 },
+            substitution: Substitution::None,
+            since: Some(2000),
+        };
+        assert_eq!(holiday.date(1999), None);
+        assert_eq!(holiday.date(2000), Some(date(1, 1, 2000)));
+    }
+
+    #[test]
+    fn nearest_weekday_substitution() {
+        let holidays = [Holiday {
+            name: "Test",
+            kind: HolidayKind::Public,
+            rule: Rule::Fixed { day: 1, month: 1 },
+            substitution: Substitution::NearestWeekday,
+            since: None,
+        }];
+        let calendar = HolidayCalendar::new(&holidays);
+        // Saturday, observed on Friday of the previous year
+        let occurrence = calendar.holidays_on(date(31, 12, 2021)).next().unwrap();
+        assert!(occurrence.observed);
+        assert_eq!(occurrence.holiday.name, "Test");
+        // Sunday, observed on Monday
+        assert!(calendar.is_public_holiday(date(2, 1, 2023)));
+        assert!(!calendar.is_public_holiday(date(3, 1, 2022)));
+    }
+
+    #[test]
+    fn next_free_weekday_substitution() {
+        let holidays = [
+            Holiday {
+                name: "First",
+                kind: HolidayKind::Public,
+                rule: Rule::Fixed { day: 25, month: 12 },
+                substitution: Substitution::NextFreeWeekday,
+                since: None,
+            },
+            Holiday {
+                name: "Second",
+                kind: HolidayKind::Public,
+                rule: Rule::Fixed { day: 26, month: 12 },
+                substitution: Substitution::NextFreeWeekday,
+                since: None,
+            },
+        ];
+        let calendar = HolidayCalendar::new(&holidays);
+        // Saturday and Sunday: Monday and Tuesday
+        assert_eq!(calendar.observed_date(0, 2021), Some(date(27, 12, 2021)));
+        assert_eq!(calendar.observed_date(1, 2021), Some(date(28, 12, 2021)));
+        // Sunday and Monday: Tuesday and Monday
+        assert_eq!(calendar.observed_date(0, 2022), Some(date(27, 12, 2022)));
+        assert_eq!(calendar.observed_date(1, 2022), Some(date(26, 12, 2022)));
+        assert!(calendar.observed_date(2, 2022).is_none());
+    }
+
+    #[test]
+    fn non_public_holidays_are_not_days_off() {
+        let holidays = [Holiday {
+            name: "Flag",
+            kind: HolidayKind::FlagDay,
+            rule: Rule::Fixed { day: 5, month: 2 },
+            substitution: Substitution::None,
+            since: None,
+        }];
+        let calendar = HolidayCalendar::new(&holidays);
+        assert_eq!(calendar.holidays_on(date(5, 2, 2022)).count(), 1);
+        assert!(!calendar.is_public_holiday(date(5, 2, 2022)));
+    }
+}
@@ -0,0 +1,138 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Textual representation of holidays definitions, used in data files
+//!
+//! Rules are written as space separated words, weekdays are `mon`..`sun`,
+//! dates are `MM-DD`:
+//! * `fixed 12-06` - 6th of December
+//! * `nth 2 sun 05` - second Sunday of May
+//! * `last mon 05` - last Monday of May
+//! * `easter -2` - two days before Easter, `orthodox-easter` uses orthodox Easter
+//! * `weekday-from sat 06-20` - Saturday between 20th and 26th of June
+
+use crate::{HolidayKind, Rule, Substitution};
+use celestial::Church;
+
+fn parse_weekday(text: &str) -> Option<u8> {
+    let index = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"]
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(text))?;
+    Some(index as u8 + 1)
+}
+
+/// Parses `MM-DD` into day and month
+fn parse_date(text: &str) -> Option<(u8, u8)> {
+    let mut parts = text.split('-');
+    let month = parts.next()?.parse::<u8>().ok()?;
+    let day = parts.next()?.parse::<u8>().ok()?;
+    if parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        None
+    } else {
+        Some((day, month))
+    }
+}
+
+fn parse_month(text: &str) -> Option<u8> {
+    text.parse::<u8>().ok().filter(|month| (1..=12).contains(month))
+}
+
+impl Rule {
+    /// Parses textual rule definition
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut words = text.split_whitespace();
+        let rule = match words.next()? {
+            "fixed" => {
+                let (day, month) = parse_date(words.next()?)?;
+                Rule::Fixed { day, month }
+            }
+            "nth" => {
+                let n = words.next()?.parse::<u8>().ok().filter(|n| (1..=5).contains(n))?;
+                let weekday = parse_weekday(words.next()?)?;
+                let month = parse_month(words.next()?)?;
+                Rule::NthWeekday { n, weekday, month }
+            }
+            "last" => {
+                let weekday = parse_weekday(words.next()?)?;
+                let month = parse_month(words.next()?)?;
+                Rule::LastWeekday { weekday, month }
+            }
+            "easter" => Rule::Easter { offset: words.next()?.parse().ok()?, church: Church::Western },
+            "orthodox-easter" => Rule::Easter { offset: words.next()?.parse().ok()?, church: Church::Orthodox },
+            "weekday-from" => {
+                let weekday = parse_weekday(words.next()?)?;
+                let (day, month) = parse_date(words.next()?)?;
+                Rule::WeekdayFrom { weekday, day, month }
+            }
+            _ => return None,
+        };
+        if words.next().is_some() {
+            None
+        } else {
+            Some(rule)
+        }
+    }
+}
+
+impl HolidayKind {
+    /// Parses `public`, `flag` or `observance`
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim() {
+            "public" => Some(HolidayKind::Public),
+            "flag" => Some(HolidayKind::FlagDay),
+            "observance" => Some(HolidayKind::Observance),
+            _ => None,
+        }
+    }
+}
+
+impl Substitution {
+    /// Parses `none` (or empty string), `next-free-weekday` or `nearest-weekday`
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim() {
+            "" | "none" => Some(Substitution::None),
+            "next-free-weekday" => Some(Substitution::NextFreeWeekday),
+            "nearest-weekday" => Some(Substitution::NearestWeekday),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules() {
+        assert_eq!(Rule::parse("fixed 12-06"), Some(Rule::Fixed { day: 6, month: 12 }));
+        assert_eq!(Rule::parse("nth 2 sun 05"), Some(Rule::NthWeekday { n: 2, weekday: 7, month: 5 }));
+        assert_eq!(Rule::parse("last Mon 5"), Some(Rule::LastWeekday { weekday: 1, month: 5 }));
+        assert_eq!(Rule::parse("easter -2"), Some(Rule::Easter { offset: -2, church: Church::Western }));
+        assert_eq!(Rule::parse("orthodox-easter 1"), Some(Rule::Easter { offset: 1, church: Church::Orthodox }));
+        assert_eq!(Rule::parse("  weekday-from sat 06-20 "), Some(Rule::WeekdayFrom { weekday: 6, day: 20, month: 6 }));
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert_eq!(Rule::parse(""), None);
+        assert_eq!(Rule::parse("fixed"), None);
+        assert_eq!(Rule::parse("fixed 13-01"), None);
+        assert_eq!(Rule::parse("fixed 12-06 extra"), None);
+        assert_eq!(Rule::parse("nth 6 sun 05"), None);
+        assert_eq!(Rule::parse("last sunday 05"), None);
+        assert_eq!(Rule::parse("easter two"), None);
+        assert_eq!(Rule::parse("lunar 1"), None);
+    }
+
+    #[test]
+    fn kinds_and_substitutions() {
+        assert_eq!(HolidayKind::parse("public"), Some(HolidayKind::Public));
+        assert_eq!(HolidayKind::parse(" flag"), Some(HolidayKind::FlagDay));
+        assert_eq!(HolidayKind::parse("observance"), Some(HolidayKind::Observance));
+        assert_eq!(HolidayKind::parse("holiday"), None);
+        assert_eq!(Substitution::parse(""), Some(Substitution::None));
+        assert_eq!(Substitution::parse("next-free-weekday"), Some(Substitution::NextFreeWeekday));
+        assert_eq!(Substitution::parse("nearest-weekday"), Some(Substitution::NearestWeekday));
+        assert_eq!(Substitution::parse("monday"), None);
+    }
+}
@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nmea::{GpsSync, NmeaFramer};
+
+// Same path as in the firmware: bytes from the UART go through the framer, the parser and the sync
+fuzz_target!(|data: &[u8]| {
+    let mut framer = NmeaFramer::new();
+    let mut sync = GpsSync::new();
+    for byte in data {
+        if let Some(Ok(sentence)) = framer.push(*byte) {
+            if let Ok(parsed) = nmea::parse_sentence(sentence) {
+                sync.push(&parsed);
+            }
+        }
+    }
+    let _ = sync.is_complete();
+});
@@ -0,0 +1,124 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! RTC setting from the GPS time
+//!
+//! Sentence timestamp is the moment of the fix, but the sentence itself
+//! arrives later, after the receiver computed and transmitted it, and RTC is
+//! set even later. Both delays are added to the timestamp and the result
+//! is aligned to the next whole second, as RTC can only be set to a whole second.
+
+use crate::{GpsDate, GpsTime};
+
+const MILLIS_PER_SECOND: u64 = 1000;
+const SECONDS_PER_DAY: u64 = 86_400;
+// ZDA sentence has four digits year
+const MAX_YEAR: u32 = 9999;
+
+/// RTC value, corrected for the sentence latency
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RtcSetting {
+    /// Milliseconds to wait before setting the RTC
+    pub delay_ms: u32,
+    /// UTC date to set after the delay
+    pub date: GpsDate,
+    /// UTC time to set after the delay, always a whole second
+    pub time: GpsTime,
+}
+
+fn days_in_month(month: u32, year: u32) -> u32 {
+    match month {
+        2 if (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn next_day(date: GpsDate) -> GpsDate {
+    if date.date < days_in_month(date.month, date.year) {
+        GpsDate { date: date.date + 1, ..date }
+    } else if date.month < 12 {
+        GpsDate { date: 1, month: date.month + 1, year: date.year }
+    } else {
+        GpsDate { date: 1, month: 1, year: date.year.saturating_add(1) }
+    }
+}
+
+/// Moves date and time forward by the amount of seconds, milliseconds are kept
+pub(crate) fn advance(date: GpsDate, time: GpsTime, seconds: u64) -> (GpsDate, GpsTime) {
+    let second_of_day = time.hour as u64 * 3600 + time.minute as u64 * 60 + time.second as u64 + seconds;
+    let mut date = date;
+    for _ in 0..second_of_day / SECONDS_PER_DAY {
+        date = next_day(date);
+    }
+    let second_of_day = second_of_day % SECONDS_PER_DAY;
+    let time = GpsTime {
+        hour: (second_of_day / 3600) as u32,
+        minute: (second_of_day % 3600 / 60) as u32,
+        second: (second_of_day % 60) as u32,
+        millisecond: time.millisecond,
+    };
+    (date, time)
+}
+
+/// Calculates RTC value from the sentence timestamp
+///
+/// * `latency_ms` - delay between the fix and the reception of the sentence, receiver specific
+/// * `elapsed_ms` - time passed since the sentence was received
+///
+/// Returns None if date or time are out of range
+pub fn rtc_setting(date: GpsDate, time: GpsTime, latency_ms: u32, elapsed_ms: u32) -> Option<RtcSetting> {
+    if date.year > MAX_YEAR || date.month == 0 || date.month > 12 || date.date == 0 || date.date > days_in_month(date.month, date.year) {
+        return None;
+    }
+    if time.hour > 23 || time.minute > 59 || time.second > 60 || time.millisecond > 999 {
+        return None;
+    }
+    let since_second = time.millisecond as u64 + latency_ms as u64 + elapsed_ms as u64;
+    let seconds = since_second.div_ceil(MILLIS_PER_SECOND);
+    let delay_ms = (seconds * MILLIS_PER_SECOND - since_second) as u32;
+
+    // RTC knows nothing about leap seconds, so the leap second is folded into the previous one
+    let (date, time) = advance(date, GpsTime { second: time.second.min(59), millisecond: 0, ..time }, seconds);
+    Some(RtcSetting { delay_ms, date, time })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32, minute: u32, second: u32, millisecond: u32) -> GpsTime {
+        GpsTime { hour, minute, second, millisecond }
+    }
+
+    #[test]
+    fn aligned_to_next_second() {
+        let date = GpsDate { date: 3, month: 11, year: 2021 };
+        let setting = rtc_setting(date, time(9, 26, 18, 510), 120, 30).unwrap();
+        assert_eq!(setting, RtcSetting { delay_ms: 340, date, time: time(9, 26, 19, 0) });
+        let setting = rtc_setting(date, time(9, 26, 18, 0), 0, 0).unwrap();
+        assert_eq!(setting, RtcSetting { delay_ms: 0, date, time: time(9, 26, 18, 0) });
+        let setting = rtc_setting(date, time(9, 26, 18, 0), 1500, 1000).unwrap();
+        assert_eq!(setting, RtcSetting { delay_ms: 500, date, time: time(9, 26, 21, 0) });
+    }
+
+    #[test]
+    fn date_rollover() {
+        let setting = rtc_setting(GpsDate { date: 31, month: 12, year: 2021 }, time(23, 59, 59, 900), 200, 0).unwrap();
+        assert_eq!(setting.date, GpsDate { date: 1, month: 1, year: 2022 });
+        assert_eq!(setting.time, time(0, 0, 1, 0));
+        assert_eq!(setting.delay_ms, 900);
+        let setting = rtc_setting(GpsDate { date: 28, month: 2, year: 2024 }, time(23, 59, 60, 500), 0, 0).unwrap();
+        assert_eq!(setting.date, GpsDate { date: 29, month: 2, year: 2024 });
+        assert_eq!(setting.time, time(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn invalid_values() {
+        assert_eq!(rtc_setting(GpsDate { date: 29, month: 2, year: 2023 }, time(0, 0, 0, 0), 0, 0), None);
+        assert_eq!(rtc_setting(GpsDate { date: 1, month: 13, year: 2023 }, time(0, 0, 0, 0), 0, 0), None);
+        assert_eq!(rtc_setting(GpsDate { date: 1, month: 1, year: 2023 }, time(24, 0, 0, 0), 0, 0), None);
+        assert_eq!(rtc_setting(GpsDate { date: 31, month: 12, year: u32::MAX }, time(23, 59, 59, 999), u32::MAX, u32::MAX), None);
+    }
+}
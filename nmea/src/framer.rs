@@ -0,0 +1,214 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Streaming NMEA sentence framer
+//!
+//! Takes bytes one by one, as they arrive from the receiver, and
+//! returns complete checksum validated sentences. Works without
+//! allocations, so could be fed directly from the interrupt handler.
+
+use core::ops::BitXor;
+
+/// Maximal sentence length, including start delimiter and CR/LF
+pub const MAX_SENTENCE_LENGTH: usize = 82;
+
+// CR/LF are not stored
+const BUFFER_SIZE: usize = MAX_SENTENCE_LENGTH - 2;
+
+/// Reason, why received sentence was dropped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameError {
+    /// Sentence is longer than 82 characters
+    Overflow,
+    /// Sentence contains non printable or non ASCII character
+    InvalidCharacter,
+    /// Sentence has no checksum field
+    MissingChecksum,
+    /// Sentence checksum does not match the data
+    BadChecksum,
+}
+
+/// Assembles NMEA sentences from the byte stream
+///
+/// Sentence starts with `$` or `!`, every start delimiter begins a new sentence,
+/// so framer resyncs after garbage or partially received sentences. Sentence
+/// ends with CR or LF.
+pub struct NmeaFramer {
+    buffer: [u8; BUFFER_SIZE],
+    length: usize,
+    receiving: bool,
+}
+
+impl Default for NmeaFramer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NmeaFramer {
+    /// Creates framer, waiting for the start of a sentence
+    pub const fn new() -> Self {
+        NmeaFramer { buffer: [0; BUFFER_SIZE], length: 0, receiving: false }
+    }
+
+    /// Drops partially received sentence
+    pub fn reset(&mut self) {
+        self.length = 0;
+        self.receiving = false;
+    }
+
+    /// Feeds next byte to the framer
+    ///
+    /// Returns None while sentence is incomplete or no sentence is being received,
+    /// otherwise returns the sentence without CR/LF or the reason why it was dropped
+    pub fn push(&mut self, byte: u8) -> Option<Result<&str, FrameError>> {
+        match byte {
+            b'$' | b'!' => {
+                self.buffer[0] = byte;
+                self.length = 1;
+                self.receiving = true;
+                None
+            }
+            _ if !self.receiving => None,
+            b'\r' | b'\n' => {
+                self.receiving = false;
+                Some(self.validate())
+            }
+            0x20..=0x7E => {
+                if self.length == BUFFER_SIZE {
+                    self.reset();
+                    return Some(Err(FrameError::Overflow));
+                }
+                self.buffer[self.length] = byte;
+                self.length += 1;
+                None
+            }
+            _ => {
+                self.reset();
+                Some(Err(FrameError::InvalidCharacter))
+            }
+        }
+    }
+
+    fn validate(&self) -> Result<&str, FrameError> {
+        let sentence = &self.buffer[..self.length];
+        let separator = sentence.iter().rposition(|c| *c == b'*').ok_or(FrameError::MissingChecksum)?;
+        let checksum = match &sentence[separator + 1..] {
+            [high, low] => hex_value(*high).zip(hex_value(*low)).map(|(h, l)| h << 4 | l),
+            _ => None,
+        }
+        .ok_or(FrameError::MissingChecksum)?;
+        let actual = sentence[1..separator].iter().fold(0u8, |checksum, byte| checksum.bitxor(byte));
+        if actual != checksum {
+            return Err(FrameError::BadChecksum);
+        }
+        // Only printable ASCII is stored, so it is always valid UTF-8
+        core::str::from_utf8(sentence).map_err(|_| FrameError::InvalidCharacter)
+    }
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(framer: &mut NmeaFramer, bytes: &[u8]) -> Vec<Result<String, FrameError>> {
+        bytes
+            .iter()
+            .filter_map(|byte| framer.push(*byte).map(|result| result.map(|s| s.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn recorded_stream() {
+        let stream = b"\x00\xFF2.00,A*1F\r\n$GPRMC,092623.00,V,,,,,,,031121,,,N*71\r\n$GPGSV,3,1,12,01,15,170,20,02,08,326,18,03,63,126,22,04,66,205,*7D\r\n";
+        let mut framer = NmeaFramer::new();
+        assert_eq!(
+            feed(&mut framer, stream),
+            vec![
+                Ok("$GPRMC,092623.00,V,,,,,,,031121,,,N*71".to_string()),
+                // LF after CR is ignored, as no sentence is being received
+                Ok("$GPGSV,3,1,12,01,15,170,20,02,08,326,18,03,63,126,22,04,66,205,*7D".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn resync_on_start_delimiter() {
+        let mut framer = NmeaFramer::new();
+        let stream = b"$GPRMC,0926$GPRMC,,V,,,,,,,,,,N*53\n!AIVDM,1,1,,B,177KQJ5000G?tO`K>RA1wUbN0TKH,0*5C\n";
+        assert_eq!(
+            feed(&mut framer, stream),
+            vec![
+                Ok("$GPRMC,,V,,,,,,,,,,N*53".to_string()),
+                Ok("!AIVDM,1,1,,B,177KQJ5000G?tO`K>RA1wUbN0TKH,0*5C".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn checksum_errors() {
+        let mut framer = NmeaFramer::new();
+        let stream = b"$GPRMC,,V,,,,,,,,,,N*35\r\n$GPRMC,,V,,,,,,,,,,N\r\n$GPRMC,,V,,,,,,,,,,N*5\r\n$GPRMC,,V,,,,,,,,,,N*53\r\n";
+        assert_eq!(
+            feed(&mut framer, stream),
+            vec![
+                Err(FrameError::BadChecksum),
+                Err(FrameError::MissingChecksum),
+                Err(FrameError::MissingChecksum),
+                Ok("$GPRMC,,V,,,,,,,,,,N*53".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn lowercase_checksum() {
+        let mut framer = NmeaFramer::new();
+        assert_eq!(
+            feed(&mut framer, b"$GPGSV,3,1,12,01,15,170,20,02,08,326,18,03,63,126,22,04,66,205,*7d\r"),
+            vec![Ok("$GPGSV,3,1,12,01,15,170,20,02,08,326,18,03,63,126,22,04,66,205,*7d".to_string())]
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let mut framer = NmeaFramer::new();
+        let mut stream = vec![b'$'];
+        stream.extend_from_slice(&[b'A'; 80]);
+        stream.extend_from_slice(b"\r\n$GPRMC,,V,,,,,,,,,,N*53\r\n");
+        assert_eq!(
+            feed(&mut framer, &stream),
+            vec![Err(FrameError::Overflow), Ok("$GPRMC,,V,,,,,,,,,,N*53".to_string())]
+        );
+    }
+
+    #[test]
+    fn longest_sentence() {
+        // 79 characters after the delimiter, plus CR/LF makes exactly 82
+        let mut sentence = String::from("$");
+        sentence.push_str(&"A".repeat(76));
+        let checksum = sentence[1..].bytes().fold(0u8, |c, b| c ^ b);
+        sentence.push_str(&format!("*{:02X}", checksum));
+        assert_eq!(sentence.len() + 2, MAX_SENTENCE_LENGTH);
+        let mut framer = NmeaFramer::new();
+        assert_eq!(feed(&mut framer, format!("{}\r\n", sentence).as_bytes()), vec![Ok(sentence)]);
+    }
+
+    #[test]
+    fn invalid_characters() {
+        let mut framer = NmeaFramer::new();
+        let stream = "$GPRMC,ä,V*00\r\n$GPRMC,,V,,,,,,,,,,N*53\r\n".as_bytes();
+        assert_eq!(
+            feed(&mut framer, stream),
+            vec![Err(FrameError::InvalidCharacter), Ok("$GPRMC,,V,,,,,,,,,,N*53".to_string())]
+        );
+    }
+}
@@ -3,11 +3,53 @@
 #![cfg_attr(not(test), no_std)]
 
 //! Helper module to parse NMEA messages
-//! At the moment onl RMC message is supported
-
-use core::ops::{BitXor, Neg};
+//!
+//! * streaming sentence framer
+//! * typed RMC, GGA, ZDA, GSA and GSV sentences from any talker
+//! * talker preference policy for multi-constellation receivers
+//! * latency corrected RTC setting from the sentence timestamp
+//! * time and position accumulation over several sentences
+//! * sentence serialization and a deterministic fake receiver
+//!
+//! Parser never panics, whatever it is fed with, as a panic halts the MCU.
+//! It is checked with property tests and with `cargo fuzz run parse_sentence`
+//! or `cargo fuzz run framer` in the `nmea` directory.
+
+mod clock;
+mod framer;
+mod sentence;
+mod simulator;
+mod sync;
+mod talker;
+mod writer;
+
+use core::ops::BitXor;
+
+pub use clock::rtc_setting as rtc_setting;
+pub use clock::RtcSetting as RtcSetting;
+pub use framer::NmeaFramer as NmeaFramer;
+pub use framer::FrameError as FrameError;
+pub use framer::MAX_SENTENCE_LENGTH as MAX_SENTENCE_LENGTH;
+pub use sentence::parse_sentence as parse_sentence;
+pub use sentence::Sentence as Sentence;
+pub use sentence::ParseError as ParseError;
+pub use sentence::RmcStatus as RmcStatus;
+pub use sentence::FaaMode as FaaMode;
+pub use sentence::Rmc as Rmc;
+pub use sentence::Gga as Gga;
+pub use sentence::Zda as Zda;
+pub use sentence::Gsa as Gsa;
+pub use sentence::Gsv as Gsv;
+pub use sentence::FixQuality as FixQuality;
+pub use sentence::FixType as FixType;
+pub use sentence::SatelliteInfo as SatelliteInfo;
+pub use simulator::Simulator as Simulator;
+pub use sync::GpsSync as GpsSync;
+pub use talker::Talker as Talker;
+pub use talker::TalkerPolicy as TalkerPolicy;
 
 /// GPS date representation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct GpsDate {
     /// Day of month starting with 1
     pub date: u32,
@@ -18,81 +60,88 @@ pub struct GpsDate {
 }
 
 /// GPS time  representation (UTC)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct GpsTime {
     /// Hour starting with 00
     pub hour: u32,
     /// Minute starting with 0
     pub minute: u32,
-    /// Second starting with 0
-    pub second: u32
+    /// Second starting with 0, 60 is a leap second
+    pub second: u32,
+    /// Millisecond of the second
+    pub millisecond: u32,
 }
 
 /// GPS position representation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct GpsPosition {
-    /// Longitude multiplied to 10^6, negative means Western
+    /// Longitude in decimal degrees multiplied to 10^6, negative means Western
     pub lon: i32,
-    /// Latitude multiplies to 10^6, negative means Southern
+    /// Latitude in decimal degrees multiplied to 10^6, negative means Southern
     pub lat: i32
 }
 
-enum Parts {
-    UTC,
-    Status,
-    Lat,
-    LatDir,
-    Lon,
-    LonDir,
-    Speed,
-    Track,
-    Date,
-    MagVar,
-    VarDir,
-    Mode,
-    None
-}
-
-impl Parts {
-    fn next_part(self) -> Self {
-        match self {
-            Parts::UTC => {Parts::Status}
-            Parts::Status => {Parts::Lat}
-            Parts::Lat => {Parts::LatDir}
-            Parts::LatDir => {Parts::Lon}
-            Parts::Lon => {Parts::LonDir}
-            Parts::LonDir => {Parts::Speed}
-            Parts::Speed => {Parts::Track}
-            Parts::Track => {Parts::Date}
-            Parts::Date => {Parts::MagVar}
-            Parts::MagVar => {Parts::VarDir}
-            Parts::VarDir => {Parts::Mode}
-            Parts::Mode => {Parts::None}
-            Parts::None => {Parts::None}
-        }
-    }
-}
-
 fn parse_nmea_date(nmea: &str) -> Option<GpsDate> {
-    let day = nmea.get(0..2).and_then(|s| u32::from_str_radix(s, 10).ok());
-    let month = nmea.get(2..4).and_then(|s| u32::from_str_radix(s, 10).ok());
-    let year = nmea.get(4..6).and_then(|s| u32::from_str_radix(s, 10).ok()).map(|y| y + 2000);
-    match (day, month, year) {
-        (Some(d), Some(m), Some(y)) => Some(GpsDate { date: d, month: m, year: y }),
-        _ => None
+    if nmea.len() != 6 || !nmea.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
     }
+    let day = nmea[0..2].parse::<u32>().ok()?;
+    let month = nmea[2..4].parse::<u32>().ok()?;
+    let year = nmea[4..6].parse::<u32>().ok()? + 2000;
+    Some(GpsDate { date: day, month, year })
 }
 
 fn parse_nmea_time(nmea: &str) -> Option<GpsTime> {
-    let hour = nmea.get(0..2).and_then(|s| u32::from_str_radix(s, 10).ok());
-    let minute = nmea.get(2..4).and_then(|s| u32::from_str_radix(s, 10).ok());
-    let second = nmea.get(4..6).and_then(|s| u32::from_str_radix(s, 10).ok());
-    match (hour, minute, second) {
-        (Some(h), Some(m), Some(s)) => Some(GpsTime { hour: h, minute: m, second: s }),
-        _ => None
-    }
+    let (integer, fraction) = nmea.split_once('.').unwrap_or((nmea, ""));
+    if integer.len() != 6 || !integer.bytes().chain(fraction.bytes()).all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let hour = integer[0..2].parse::<u32>().ok()?;
+    let minute = integer[2..4].parse::<u32>().ok()?;
+    let second = integer[4..6].parse::<u32>().ok()?;
+    // Fraction is usually two digits, anything beyond milliseconds is truncated
+    let mut digits = fraction.bytes();
+    let millisecond = (0..3).fold(0, |ms, _| ms * 10 + digits.next().map(|c| (c - b'0') as u32).unwrap_or(0));
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    Some(GpsTime { hour, minute, second, millisecond })
 }
 
+/// Converts NMEA ddmm.mmmm (or dddmm.mmmm) value to the decimal degrees, multiplied to 10^6
+///
+/// Only integer math is used, so no precision is lost on the way
 fn parse_nmea_coords(nmea: &str) -> Option<i32> {
-    nmea.parse::<f32>().ok().map(|v| v*10000.0).map(|v| v as i32)
+    let (integer, fraction) = nmea.split_once('.').unwrap_or((nmea, ""));
+    if integer.len() < 3 || !integer.bytes().chain(fraction.bytes()).all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let integer = integer.parse::<u32>().ok()?;
+    let degrees = integer / 100;
+    // Minutes multiplied to 10^6, digits beyond that are truncated
+    let mut minutes = integer % 100;
+    let mut fraction = fraction.bytes();
+    for _ in 0..6 {
+        minutes = minutes * 10 + fraction.next().map(|c| c - b'0').unwrap_or(0) as u32;
+    }
+    if degrees > 180 || minutes >= 60_000_000 {
+        return None;
+    }
+    Some((degrees * 1_000_000 + (minutes + 30) / 60) as i32)
+}
+
+/// Converts coordinate, stored by the older firmware as ddmm.mmmm multiplied to 10^4,
+/// to the decimal degrees, multiplied to 10^6
+pub fn legacy_coords_to_decimal(legacy: i32) -> i32 {
+    let absolute = legacy.unsigned_abs();
+    let degrees = absolute / 1_000_000;
+    let minutes = absolute % 1_000_000;
+    let decimal = (degrees * 1_000_000 + (minutes * 100 + 30) / 60) as i32;
+    if legacy < 0 {
+        decimal.wrapping_neg()
+    } else {
+        decimal
+    }
 }
 
 fn calculate_checksum(message: &str) -> u8 {
@@ -103,58 +152,9 @@ fn calculate_checksum(message: &str) -> u8 {
     checksum
 }
 
-fn check_checksum(nmea: &str) -> bool {
-    nmea.get(1..).map(|s| s.split('*'))
-        .map(|mut parts| (parts.next(), parts.next()))
-        .map(|(message, checksum)| (message, checksum.and_then(|cs| u8::from_str_radix(cs, 16).ok())))
-        .and_then(|(message, checksum)| message.zip(checksum))
-        .map(|(message, checksum)| (calculate_checksum(message), checksum))
-        .map(|(actual_checksum, expected_checksum)| actual_checksum == expected_checksum)
-        .unwrap_or(false)
-}
-
-/// Takes a NMEA message, parses it and produces time and position information
-///
-/// May return double None is message is non-RMC or no data in RMC message
-/// Returns Date only is not fix available yet
-/// Returns both Data nd Position if stable fix is reported in RMC message
-pub fn parse_nmea_string(nmea: &str) -> (Option<(GpsDate, GpsTime)>, Option<GpsPosition>) {
-    let mut date = None;
-    let mut time = None;
-    let mut lon = None;
-    let mut lat = None;
-    if let Some(message) = nmea.get(3..6) {
-        if message == "RMC" {
-            if check_checksum(nmea) {
-                let mut current_part = Parts::UTC;
-                for part in nmea[7..].split(',') {
-                    match current_part {
-                        Parts::UTC => time = parse_nmea_time(part),
-                        Parts::Date => date = parse_nmea_date(part),
-                        Parts::Lon => lon = parse_nmea_coords(part),
-                        Parts::Lat => lat = parse_nmea_coords(part),
-                        Parts::LonDir => if part == "W" { lon = lon.map(|v| v.neg())},
-                        Parts::LatDir => if part == "S" { lat = lat.map(|v| v.neg())},
-                        Parts::Mode => { //This is our exit condition
-                            let position = match (lon, lat) {
-                                (Some(lo), Some(la)) => Some(GpsPosition{lon: lo, lat: la}),
-                                _ => None
-                            };
-                            return (date.zip(time), position)
-                        },
-                        _ => {/* ignore that part */}
-                    }
-                    current_part = current_part.next_part();
-                }
-            }
-        }
-    }
-    (None, None)
-}
-
 #[cfg(test)]
 mod tests {
-    use crate::{parse_nmea_coords, parse_nmea_date, parse_nmea_string};
+    use crate::{legacy_coords_to_decimal, parse_nmea_coords, parse_nmea_date, parse_nmea_time, GpsTime};
 
     #[test]
     fn date_too_short() {
@@ -165,96 +165,65 @@ mod tests {
         assert!(parse_nmea_date("0311aa").is_none())
     }
     #[test]
+    fn date_sign() {
+        assert!(parse_nmea_date("+1+1+1").is_none())
+    }
+    #[test]
     fn date_correct() {
         let date = parse_nmea_date("031121");
         assert!(date.is_some());
         let dt = date.unwrap();
-        assert_eq!(dt.date, 03);
+        assert_eq!(dt.date, 3);
         assert_eq!(dt.month, 11);
         assert_eq!(dt.year, 2021);
     }
 
     #[test]
-    fn parse_coords_non_float() {
-        assert!(parse_nmea_coords("3.14aa").is_none());
-    }
-    #[test]
-    fn parse_coords() {
-        let value = parse_nmea_coords("6005.84256");
-        assert!(value.is_some());
-        let v = value.unwrap();
-        assert_eq!(v/10, 6005842)
-    }
-
-    #[test]
-    fn skip_non_rmc_message() {
-        let (date, position) = parse_nmea_string("$GPGSV,3,1,12,01,15,170,20,02,08,326,18,03,63,126,22,04,66,205,*7D");
-        assert!(date.is_none());
-        assert!(position.is_none());
-    }
-
-    #[test]
-    fn empty_rmc_message_is_ignored() {
-        let (date, position) = parse_nmea_string("$GPRMC,,V,,,,,,,,,,N*53");
-        assert!(date.is_none());
-        assert!(position.is_none());
+    fn time_with_fraction() {
+        assert_eq!(parse_nmea_time("092618.51"), Some(GpsTime { hour: 9, minute: 26, second: 18, millisecond: 510 }));
+        assert_eq!(parse_nmea_time("092618.5"), Some(GpsTime { hour: 9, minute: 26, second: 18, millisecond: 500 }));
+        assert_eq!(parse_nmea_time("092618.1239"), Some(GpsTime { hour: 9, minute: 26, second: 18, millisecond: 123 }));
+        assert_eq!(parse_nmea_time("235960"), Some(GpsTime { hour: 23, minute: 59, second: 60, millisecond: 0 }));
     }
-
     #[test]
-    fn time_only_rmc_message_is_ignored() {
-        let (date, position) = parse_nmea_string("$GPRMC,092618.51,V,,,,,,,,,,N*7D");
-        assert!(date.is_none());
-        assert!(position.is_none());
+    fn time_invalid() {
+        assert!(parse_nmea_time("0926").is_none());
+        assert!(parse_nmea_time("092618.5x").is_none());
+        assert!(parse_nmea_time("246000").is_none());
+        assert!(parse_nmea_time("096100").is_none());
     }
 
     #[test]
-    fn no_time_without_date() {
-        let (date, position) = parse_nmea_string("$GPRMC,092618.51,V,,,,,,,,,,N*7D");
-        assert!(date.is_none());
-        assert!(position.is_none());
+    fn parse_coords_non_float() {
+        assert!(parse_nmea_coords("3.14aa").is_none());
+        assert!(parse_nmea_coords("-6005.84256").is_none());
     }
-
     #[test]
-    fn date_only_rmc_message_is_parsed() {
-        let (date, position) = parse_nmea_string("$GPRMC,092623.00,V,,,,,,,031121,,,N*71");
-        assert!(date.is_some());
-        assert!(position.is_none());
-
-        let (d,t) = date.unwrap();
-        assert_eq!(d.date, 03);
-        assert_eq!(d.month, 11);
-        assert_eq!(d.year, 2021);
-
-        assert_eq!(t.hour, 09);
-        assert_eq!(t.minute, 26);
-        assert_eq!(t.second, 23);
+    fn parse_coords() {
+        assert_eq!(parse_nmea_coords("6005.84256"), Some(60_097_376));
+        assert_eq!(parse_nmea_coords("02414.01597"), Some(24_233_600));
+        assert_eq!(parse_nmea_coords("18000.0000"), Some(180_000_000));
+        assert_eq!(parse_nmea_coords("0000.000001"), Some(0));
+        assert_eq!(parse_nmea_coords("4530"), Some(45_500_000));
     }
-
     #[test]
-    fn full_rmc_message_is_parsed() {
-        let (date, position) = parse_nmea_string("$GPRMC,093052.00,A,6005.84256,N,02414.01597,E,1.055,,031121,,,A*7B");
-        assert!(date.is_some());
-        assert!(position.is_some());
-
-        let (d, t) = date.unwrap();
-        assert_eq!(d.date, 03);
-        assert_eq!(d.month, 11);
-        assert_eq!(d.year, 2021);
-
-        assert_eq!(t.hour, 09);
-        assert_eq!(t.minute, 30);
-        assert_eq!(t.second, 52);
-
-        let pos = position.unwrap();
-        assert_eq!(pos.lon/10, 2414015);
-        assert_eq!(pos.lat/10, 6005842);
+    fn legacy_coords() {
+        // Default position of the older firmware
+        assert_eq!(legacy_coords_to_decimal(60058425), 60_097_375);
+        assert_eq!(legacy_coords_to_decimal(24140159), 24_233_598);
+        assert_eq!(legacy_coords_to_decimal(-34362240), -34_603_733);
+        assert_eq!(legacy_coords_to_decimal(-58229040), -58_381_733);
+        assert_eq!(legacy_coords_to_decimal(-1000000), -1_000_000);
+        assert_eq!(legacy_coords_to_decimal(0), 0);
+        // Garbage in the backup registers must not panic
+        legacy_coords_to_decimal(i32::MIN);
+        legacy_coords_to_decimal(i32::MAX);
+        legacy_coords_to_decimal(-2_147_290_189);
     }
-
     #[test]
-    fn wrong_checksum_ignored() {
-        let (date, position) = parse_nmea_string("$GPRMC,093052.00,A,6005.84256,N,02414.01597,E,1.055,,031121,,,A*B7");
-        assert!(date.is_none());
-        assert!(position.is_none());
+    fn parse_coords_out_of_range() {
+        assert!(parse_nmea_coords("6060.00000").is_none());
+        assert!(parse_nmea_coords("18100.00000").is_none());
+        assert!(parse_nmea_coords("12").is_none());
     }
-
-}
\ No newline at end of file
+}
@@ -0,0 +1,713 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Typed NMEA sentences
+//!
+//! Fractional values are kept as fixed point integers, so no
+//! floating point math is needed on the MCU. Empty fields are
+//! reported as None, malformed fields are reported as errors.
+
+use core::convert::TryFrom;
+
+use crate::talker::Talker;
+use crate::{calculate_checksum, parse_nmea_coords, parse_nmea_date, parse_nmea_time, GpsDate, GpsPosition, GpsTime};
+
+/// Maximal amount of satellites, reported in GSA sentence
+pub const GSA_SATELLITES: usize = 12;
+/// Maximal amount of satellites, reported in a single GSV sentence
+pub const GSV_SATELLITES: usize = 4;
+
+/// Reason, why sentence can not be parsed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// Sentence checksum does not match the data
+    BadChecksum,
+    /// Sentence has no checksum or checksum is not a hex number
+    MissingChecksum,
+    /// Sentence type is not supported
+    UnsupportedSentence,
+    /// Field value is malformed
+    FieldParse {
+        /// Name of the field
+        field: &'static str,
+    },
+    /// Sentence has less fields than expected
+    Truncated,
+    /// Proprietary sentence, like u-blox `$PUBX` or MediaTek `$PMTK`, which is not parsed
+    Proprietary,
+}
+
+/// Validity of the RMC data
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RmcStatus {
+    /// Data is valid
+    Active,
+    /// Navigation receiver warning, data is not valid
+    Void,
+}
+
+/// FAA positioning mode, reported since NMEA 2.3
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaaMode {
+    /// Autonomous GNSS fix
+    Autonomous,
+    /// Differential GNSS fix
+    Differential,
+    /// Dead reckoning
+    Estimated,
+    /// Real time kinematic, float integers
+    FloatRtk,
+    /// Manual input mode
+    Manual,
+    /// No fix
+    NotValid,
+    /// Precise positioning service fix
+    Precise,
+    /// Real time kinematic, fixed integers
+    Rtk,
+    /// Simulator mode
+    Simulator,
+}
+
+impl FaaMode {
+    /// Checks if the mode means a real satellite fix
+    pub fn is_fix(&self) -> bool {
+        matches!(self, FaaMode::Autonomous | FaaMode::Differential | FaaMode::FloatRtk | FaaMode::Precise | FaaMode::Rtk)
+    }
+}
+
+/// Recommended minimum data, RMC sentence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rmc {
+    /// Source of the sentence
+    pub talker: Talker,
+    /// UTC time of the fix
+    pub time: Option<GpsTime>,
+    /// Data validity
+    pub status: RmcStatus,
+    /// Position, if receiver has a fix
+    pub position: Option<GpsPosition>,
+    /// Date of the fix
+    pub date: Option<GpsDate>,
+    /// Positioning mode, None for receivers before NMEA 2.3
+    pub mode: Option<FaaMode>,
+}
+
+impl Rmc {
+    /// Checks if the position is valid, both by status and by the mode
+    pub fn is_valid(&self) -> bool {
+        self.status == RmcStatus::Active && self.mode.map(|m| m.is_fix()).unwrap_or(true)
+    }
+}
+
+/// GPS fix quality, reported in GGA sentence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FixQuality {
+    /// No fix
+    Invalid,
+    /// Autonomous GNSS fix
+    Gps,
+    /// Differential GNSS fix
+    Dgps,
+    /// Precise positioning service fix
+    Pps,
+    /// Real time kinematic, fixed integers
+    Rtk,
+    /// Real time kinematic, float integers
+    FloatRtk,
+    /// Dead reckoning
+    Estimated,
+    /// Manual input mode
+    Manual,
+    /// Simulator mode
+    Simulation,
+}
+
+/// Fix data, GGA sentence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Gga {
+    /// Source of the sentence
+    pub talker: Talker,
+    /// UTC time of the fix
+    pub time: Option<GpsTime>,
+    /// Position, if receiver has a fix
+    pub position: Option<GpsPosition>,
+    /// Fix quality
+    pub fix_quality: FixQuality,
+    /// Amount of satellites, used for the fix
+    pub satellites: Option<u8>,
+    /// Horizontal dilution of precision, multiplied by 100
+    pub hdop: Option<u16>,
+    /// Altitude above mean sea level in centimeters
+    pub altitude: Option<i32>,
+}
+
+/// Time and date, ZDA sentence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Zda {
+    /// Source of the sentence
+    pub talker: Talker,
+    /// UTC time
+    pub time: Option<GpsTime>,
+    /// UTC date
+    pub date: Option<GpsDate>,
+}
+
+/// Fix type, reported in GSA sentence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FixType {
+    /// No fix
+    NoFix,
+    /// 2D fix, without altitude
+    Fix2D,
+    /// 3D fix
+    Fix3D,
+}
+
+/// Active satellites and dilution of precision, GSA sentence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Gsa {
+    /// Source of the sentence
+    pub talker: Talker,
+    /// True if receiver switches between 2D and 3D modes automatically
+    pub automatic: bool,
+    /// Fix type
+    pub fix_type: FixType,
+    satellites: [u8; GSA_SATELLITES],
+    satellites_count: u8,
+    /// Position dilution of precision, multiplied by 100
+    pub pdop: Option<u16>,
+    /// Horizontal dilution of precision, multiplied by 100
+    pub hdop: Option<u16>,
+    /// Vertical dilution of precision, multiplied by 100
+    pub vdop: Option<u16>,
+}
+
+impl Gsa {
+    /// PRN numbers of the satellites, used for the fix
+    pub fn satellites(&self) -> &[u8] {
+        &self.satellites[..self.satellites_count as usize]
+    }
+}
+
+/// Satellite in view, reported in GSV sentence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SatelliteInfo {
+    /// Satellite PRN number
+    pub prn: u8,
+    /// Elevation in degrees
+    pub elevation: Option<u8>,
+    /// Azimuth in degrees from the true north
+    pub azimuth: Option<u16>,
+    /// Signal to noise ratio in dB, None if satellite is not tracked
+    pub snr: Option<u8>,
+}
+
+/// Satellites in view, GSV sentence
+///
+/// Satellites are reported in several sentences, up to 4 satellites per sentence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Gsv {
+    /// Source of the sentence
+    pub talker: Talker,
+    /// Total amount of GSV sentences in the group
+    pub sentences: u8,
+    /// Number of this sentence in the group, starting with 1
+    pub sentence_number: u8,
+    /// Total amount of satellites in view
+    pub satellites_in_view: u8,
+    satellites: [Option<SatelliteInfo>; GSV_SATELLITES],
+}
+
+impl Gsv {
+    /// Satellites, reported in this sentence
+    pub fn satellites(&self) -> impl Iterator<Item = &SatelliteInfo> {
+        self.satellites.iter().flatten()
+    }
+}
+
+/// Parsed NMEA sentence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sentence {
+    /// Recommended minimum data
+    Rmc(Rmc),
+    /// Fix data
+    Gga(Gga),
+    /// Time and date
+    Zda(Zda),
+    /// Active satellites
+    Gsa(Gsa),
+    /// Satellites in view
+    Gsv(Gsv),
+}
+
+/// Parses unsigned decimal number as a fixed point integer with the specified
+/// amount of fractional digits, extra digits are truncated
+fn parse_fixed(field: &str, digits: usize) -> Option<u32> {
+    let (integer, fraction) = field.split_once('.').unwrap_or((field, ""));
+    if integer.is_empty() || !integer.bytes().chain(fraction.bytes()).all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let mut value = integer.parse::<u32>().ok()?;
+    let mut fraction = fraction.bytes();
+    for _ in 0..digits {
+        let digit = fraction.next().map(|c| c - b'0').unwrap_or(0);
+        value = value.checked_mul(10)?.checked_add(digit as u32)?;
+    }
+    Some(value)
+}
+
+/// Parses signed decimal number as a fixed point integer
+fn parse_signed_fixed(field: &str, digits: usize) -> Option<i32> {
+    match field.strip_prefix('-') {
+        Some(negative) => parse_fixed(negative, digits).and_then(|v| i32::try_from(v).ok()).map(|v| -v),
+        None => parse_fixed(field, digits).and_then(|v| i32::try_from(v).ok()),
+    }
+}
+
+/// Parses dilution of precision, multiplied by 100
+fn parse_dop(field: &str) -> Option<u16> {
+    parse_fixed(field, 2).and_then(|v| u16::try_from(v).ok())
+}
+
+/// Parses unsigned integer, sign is not allowed
+fn parse_number<T: core::str::FromStr>(field: &str) -> Option<T> {
+    if !field.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    field.parse::<T>().ok()
+}
+
+/// Takes the next field, which must be present, but may be empty
+fn next_field<'a>(fields: &mut impl Iterator<Item = &'a str>) -> Result<&'a str, ParseError> {
+    fields.next().ok_or(ParseError::Truncated)
+}
+
+/// Parses the field value, empty field is not an error
+fn optional<T>(value: &str, field: &'static str, parse: impl Fn(&str) -> Option<T>) -> Result<Option<T>, ParseError> {
+    if value.is_empty() {
+        Ok(None)
+    } else {
+        parse(value).map(Some).ok_or(ParseError::FieldParse { field })
+    }
+}
+
+/// Parses a mandatory field
+fn required<T>(value: &str, field: &'static str, parse: impl Fn(&str) -> Option<T>) -> Result<T, ParseError> {
+    parse(value).ok_or(ParseError::FieldParse { field })
+}
+
+fn parse_position<'a>(fields: &mut impl Iterator<Item = &'a str>) -> Result<Option<GpsPosition>, ParseError> {
+    let lat = optional(next_field(fields)?, "latitude", parse_nmea_coords)?;
+    let lat_dir = next_field(fields)?;
+    let lon = optional(next_field(fields)?, "longitude", parse_nmea_coords)?;
+    let lon_dir = next_field(fields)?;
+    let (lat, lon) = match (lat, lon) {
+        (Some(lat), Some(lon)) => (lat, lon),
+        (None, None) => return Ok(None),
+        (None, _) => return Err(ParseError::FieldParse { field: "latitude" }),
+        (_, None) => return Err(ParseError::FieldParse { field: "longitude" }),
+    };
+    if lat > 90_000_000 {
+        return Err(ParseError::FieldParse { field: "latitude" });
+    }
+    let lat = match lat_dir {
+        "N" => lat,
+        "S" => -lat,
+        _ => return Err(ParseError::FieldParse { field: "latitude direction" }),
+    };
+    let lon = match lon_dir {
+        "E" => lon,
+        "W" => -lon,
+        _ => return Err(ParseError::FieldParse { field: "longitude direction" }),
+    };
+    Ok(Some(GpsPosition { lon, lat }))
+}
+
+impl RmcStatus {
+    fn parse(field: &str) -> Option<Self> {
+        match field {
+            "A" => Some(RmcStatus::Active),
+            "V" => Some(RmcStatus::Void),
+            _ => None,
+        }
+    }
+}
+
+impl FaaMode {
+    fn parse(field: &str) -> Option<Self> {
+        match field {
+            "A" => Some(FaaMode::Autonomous),
+            "D" => Some(FaaMode::Differential),
+            "E" => Some(FaaMode::Estimated),
+            "F" => Some(FaaMode::FloatRtk),
+            "M" => Some(FaaMode::Manual),
+            "N" => Some(FaaMode::NotValid),
+            "P" => Some(FaaMode::Precise),
+            "R" => Some(FaaMode::Rtk),
+            "S" => Some(FaaMode::Simulator),
+            _ => None,
+        }
+    }
+}
+
+impl Rmc {
+    fn parse<'a>(talker: Talker, fields: &mut impl Iterator<Item = &'a str>) -> Result<Self, ParseError> {
+        let time = optional(next_field(fields)?, "time", parse_nmea_time)?;
+        let status = required(next_field(fields)?, "status", RmcStatus::parse)?;
+        let position = parse_position(fields)?;
+        let _speed = next_field(fields)?;
+        let _track = next_field(fields)?;
+        let date = optional(next_field(fields)?, "date", parse_nmea_date)?;
+        let _variation = next_field(fields)?;
+        let _variation_direction = next_field(fields)?;
+        let mode = match fields.next() {
+            Some(mode) => optional(mode, "mode", FaaMode::parse)?,
+            None => None,
+        };
+        Ok(Rmc { talker, time, status, position, date, mode })
+    }
+}
+
+impl FixQuality {
+    fn parse(field: &str) -> Option<Self> {
+        match field {
+            "0" | "" => Some(FixQuality::Invalid),
+            "1" => Some(FixQuality::Gps),
+            "2" => Some(FixQuality::Dgps),
+            "3" => Some(FixQuality::Pps),
+            "4" => Some(FixQuality::Rtk),
+            "5" => Some(FixQuality::FloatRtk),
+            "6" => Some(FixQuality::Estimated),
+            "7" => Some(FixQuality::Manual),
+            "8" => Some(FixQuality::Simulation),
+            _ => None,
+        }
+    }
+}
+
+impl Gga {
+    fn parse<'a>(talker: Talker, fields: &mut impl Iterator<Item = &'a str>) -> Result<Self, ParseError> {
+        let time = optional(next_field(fields)?, "time", parse_nmea_time)?;
+        let position = parse_position(fields)?;
+        let fix_quality = required(next_field(fields)?, "fix quality", FixQuality::parse)?;
+        let satellites = optional(next_field(fields)?, "satellites", parse_number)?;
+        let hdop = optional(next_field(fields)?, "hdop", parse_dop)?;
+        let altitude = optional(next_field(fields)?, "altitude", |f| parse_signed_fixed(f, 2))?;
+        let position = if fix_quality == FixQuality::Invalid { None } else { position };
+        Ok(Gga { talker, time, position, fix_quality, satellites, hdop, altitude })
+    }
+}
+
+impl Zda {
+    fn parse<'a>(talker: Talker, fields: &mut impl Iterator<Item = &'a str>) -> Result<Self, ParseError> {
+        let time = optional(next_field(fields)?, "time", parse_nmea_time)?;
+        let day = optional(next_field(fields)?, "day", parse_number)?;
+        let month = optional(next_field(fields)?, "month", parse_number)?;
+        let year = optional(next_field(fields)?, "year", parse_number)?;
+        let date = match (day, month, year) {
+            (Some(date), Some(month), Some(year)) => Some(GpsDate { date, month, year }),
+            _ => None,
+        };
+        Ok(Zda { talker, time, date })
+    }
+}
+
+impl Gsa {
+    fn parse<'a>(talker: Talker, fields: &mut impl Iterator<Item = &'a str>) -> Result<Self, ParseError> {
+        let automatic = required(next_field(fields)?, "selection mode", |f| match f {
+            "A" => Some(true),
+            "M" => Some(false),
+            _ => None,
+        })?;
+        let fix_type = required(next_field(fields)?, "fix type", |f| match f {
+            "1" | "" => Some(FixType::NoFix),
+            "2" => Some(FixType::Fix2D),
+            "3" => Some(FixType::Fix3D),
+            _ => None,
+        })?;
+        let mut satellites = [0; GSA_SATELLITES];
+        let mut satellites_count = 0;
+        for _ in 0..GSA_SATELLITES {
+            if let Some(prn) = optional(next_field(fields)?, "satellite", parse_number)? {
+                satellites[satellites_count] = prn;
+                satellites_count += 1;
+            }
+        }
+        let pdop = optional(next_field(fields)?, "pdop", parse_dop)?;
+        let hdop = optional(next_field(fields)?, "hdop", parse_dop)?;
+        let vdop = optional(next_field(fields)?, "vdop", parse_dop)?;
+        Ok(Gsa { talker, automatic, fix_type, satellites, satellites_count: satellites_count as u8, pdop, hdop, vdop })
+    }
+}
+
+impl Gsv {
+    fn parse<'a>(talker: Talker, fields: &mut impl Iterator<Item = &'a str>) -> Result<Self, ParseError> {
+        let sentences = required(next_field(fields)?, "sentences", parse_number)?;
+        let sentence_number = required(next_field(fields)?, "sentence number", parse_number)?;
+        let satellites_in_view = required(next_field(fields)?, "satellites in view", parse_number)?;
+        let mut satellites = [None; GSV_SATELLITES];
+        for satellite in satellites.iter_mut() {
+            // Last sentence of the group may have less satellites, NMEA 4.10 receivers
+            // also add a single signal identifier field at the end
+            let (prn, elevation, azimuth, snr) = match (fields.next(), fields.next(), fields.next(), fields.next()) {
+                (Some(prn), Some(elevation), Some(azimuth), Some(snr)) => (prn, elevation, azimuth, snr),
+                _ => break,
+            };
+            let elevation = optional(elevation, "elevation", parse_number)?;
+            let azimuth = optional(azimuth, "azimuth", parse_number)?;
+            let snr = optional(snr, "snr", parse_number)?;
+            *satellite = optional(prn, "satellite", parse_number)?.map(|prn| SatelliteInfo { prn, elevation, azimuth, snr });
+        }
+        Ok(Gsv { talker, sentences, sentence_number, satellites_in_view, satellites })
+    }
+}
+
+/// Verifies sentence checksum and returns the data between the delimiter and the checksum
+fn checked_data(nmea: &str) -> Result<&str, ParseError> {
+    let data = nmea.strip_prefix('$').ok_or(ParseError::UnsupportedSentence)?;
+    let (data, checksum) = data.rsplit_once('*').ok_or(ParseError::MissingChecksum)?;
+    if checksum.len() != 2 || !checksum.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ParseError::MissingChecksum);
+    }
+    let checksum = u8::from_str_radix(checksum, 16).map_err(|_| ParseError::MissingChecksum)?;
+    if calculate_checksum(data) != checksum {
+        return Err(ParseError::BadChecksum);
+    }
+    Ok(data)
+}
+
+impl Sentence {
+    /// Source of the sentence
+    pub fn talker(&self) -> Talker {
+        match self {
+            Sentence::Rmc(rmc) => rmc.talker,
+            Sentence::Gga(gga) => gga.talker,
+            Sentence::Zda(zda) => zda.talker,
+            Sentence::Gsa(gsa) => gsa.talker,
+            Sentence::Gsv(gsv) => gsv.talker,
+        }
+    }
+}
+
+/// Parses NMEA sentence with a valid checksum into a typed sentence
+pub fn parse_sentence(nmea: &str) -> Result<Sentence, ParseError> {
+    let data = checked_data(nmea)?;
+    let mut fields = data.split(',');
+    let address = next_field(&mut fields)?;
+    if address.starts_with('P') {
+        return Err(ParseError::Proprietary);
+    }
+    let talker = address.get(0..2).and_then(Talker::parse).ok_or(ParseError::UnsupportedSentence)?;
+    match address.get(2..) {
+        Some("RMC") => Rmc::parse(talker, &mut fields).map(Sentence::Rmc),
+        Some("GGA") => Gga::parse(talker, &mut fields).map(Sentence::Gga),
+        Some("ZDA") => Zda::parse(talker, &mut fields).map(Sentence::Zda),
+        Some("GSA") => Gsa::parse(talker, &mut fields).map(Sentence::Gsa),
+        Some("GSV") => Gsv::parse(talker, &mut fields).map(Sentence::Gsv),
+        _ => Err(ParseError::UnsupportedSentence),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rmc(nmea: &str) -> Rmc {
+        match parse_sentence(nmea) {
+            Ok(Sentence::Rmc(rmc)) => rmc,
+            other => panic!("RMC expected, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn fixed_point() {
+        assert_eq!(parse_fixed("1.5", 2), Some(150));
+        assert_eq!(parse_fixed("0.987", 2), Some(98));
+        assert_eq!(parse_fixed("12", 1), Some(120));
+        assert_eq!(parse_fixed("", 2), None);
+        assert_eq!(parse_fixed(".5", 2), None);
+        assert_eq!(parse_fixed("1.a", 2), None);
+        assert_eq!(parse_signed_fixed("-12.34", 2), Some(-1234));
+    }
+
+    #[test]
+    fn empty_rmc() {
+        let rmc = rmc("$GPRMC,,V,,,,,,,,,,N*53");
+        assert_eq!(rmc, Rmc { talker: Talker::Gps, time: None, status: RmcStatus::Void, position: None, date: None, mode: Some(FaaMode::NotValid) });
+        assert!(!rmc.is_valid());
+    }
+
+    #[test]
+    fn time_only_rmc() {
+        let rmc = rmc("$GPRMC,092618.51,V,,,,,,,,,,N*7D");
+        assert_eq!(rmc.time, Some(GpsTime { hour: 9, minute: 26, second: 18, millisecond: 510 }));
+        assert_eq!(rmc.date, None);
+    }
+
+    #[test]
+    fn date_only_rmc() {
+        let rmc = rmc("$GPRMC,092623.00,V,,,,,,,031121,,,N*71");
+        assert_eq!(rmc.date, Some(GpsDate { date: 3, month: 11, year: 2021 }));
+        assert_eq!(rmc.time, Some(GpsTime { hour: 9, minute: 26, second: 23, millisecond: 0 }));
+        assert_eq!(rmc.position, None);
+    }
+
+    #[test]
+    fn full_rmc() {
+        let rmc = rmc("$GPRMC,093052.00,A,6005.84256,N,02414.01597,E,1.055,,031121,,,A*7B");
+        assert_eq!(rmc.date, Some(GpsDate { date: 3, month: 11, year: 2021 }));
+        assert_eq!(rmc.time, Some(GpsTime { hour: 9, minute: 30, second: 52, millisecond: 0 }));
+        assert_eq!(rmc.status, RmcStatus::Active);
+        assert_eq!(rmc.mode, Some(FaaMode::Autonomous));
+        assert!(rmc.is_valid());
+        let position = rmc.position.unwrap();
+        assert_eq!(position, GpsPosition { lon: 24_233_600, lat: 60_097_376 });
+    }
+
+    #[test]
+    fn rmc_without_mode() {
+        let rmc = rmc("$GPRMC,093052.00,A,6005.84256,S,02414.01597,W,1.055,,031121,,*19");
+        assert_eq!(rmc.mode, None);
+        assert!(rmc.is_valid());
+        let position = rmc.position.unwrap();
+        assert!(position.lon < 0 && position.lat < 0);
+    }
+
+    #[test]
+    fn gga() {
+        match parse_sentence("$GPGGA,092725.00,4717.11399,N,00833.91590,E,1,08,1.01,499.6,M,48.0,M,,*5B") {
+            Ok(Sentence::Gga(gga)) => {
+                assert_eq!(gga.time, Some(GpsTime { hour: 9, minute: 27, second: 25, millisecond: 0 }));
+                assert!(gga.position.is_some());
+                assert_eq!(gga.fix_quality, FixQuality::Gps);
+                assert_eq!(gga.satellites, Some(8));
+                assert_eq!(gga.hdop, Some(101));
+                assert_eq!(gga.altitude, Some(49960));
+            }
+            other => panic!("GGA expected, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn hemispheres() {
+        let cases = [
+            ("$GPGGA,120000.00,6010.39944,N,02456.46150,E,1,08,1.01,10.0,M,0.0,M,,*51", 24_941_025, 60_173_324), // Helsinki
+            ("$GPGGA,120000.00,4042.76800,N,07400.36000,W,1,08,1.01,10.0,M,0.0,M,,*49", -74_006_000, 40_712_800), // New York
+            ("$GPGGA,120000.00,3352.12800,S,15112.55800,E,1,08,1.01,10.0,M,0.0,M,,*49", 151_209_300, -33_868_800), // Sydney
+            ("$GPGGA,120000.00,3436.22400,S,05822.90400,W,1,08,1.01,10.0,M,0.0,M,,*5F", -58_381_733, -34_603_733), // Buenos Aires
+        ];
+        for (nmea, lon, lat) in cases.iter() {
+            match parse_sentence(nmea) {
+                Ok(Sentence::Gga(gga)) => assert_eq!(gga.position, Some(GpsPosition { lon: *lon, lat: *lat })),
+                other => panic!("GGA expected, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn gga_without_fix() {
+        match parse_sentence("$GPGGA,092725.00,,,,,0,00,99.99,,,,,,*6D") {
+            Ok(Sentence::Gga(gga)) => {
+                assert_eq!(gga.fix_quality, FixQuality::Invalid);
+                assert_eq!(gga.position, None);
+                assert_eq!(gga.satellites, Some(0));
+                assert_eq!(gga.altitude, None);
+            }
+            other => panic!("GGA expected, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn zda() {
+        assert_eq!(
+            parse_sentence("$GPZDA,082710.00,16,09,2002,00,00*64"),
+            Ok(Sentence::Zda(Zda {
+                talker: Talker::Gps,
+                time: Some(GpsTime { hour: 8, minute: 27, second: 10, millisecond: 0 }),
+                date: Some(GpsDate { date: 16, month: 9, year: 2002 }),
+            }))
+        );
+    }
+
+    #[test]
+    fn gsa() {
+        match parse_sentence("$GPGSA,A,3,23,29,07,08,09,18,26,28,,,,,1.94,1.18,1.54*0D") {
+            Ok(Sentence::Gsa(gsa)) => {
+                assert!(gsa.automatic);
+                assert_eq!(gsa.fix_type, FixType::Fix3D);
+                assert_eq!(gsa.satellites(), &[23, 29, 7, 8, 9, 18, 26, 28]);
+                assert_eq!((gsa.pdop, gsa.hdop, gsa.vdop), (Some(194), Some(118), Some(154)));
+            }
+            other => panic!("GSA expected, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn gsv() {
+        match parse_sentence("$GPGSV,3,1,12,01,15,170,20,02,08,326,18,03,63,126,22,04,66,205,*7D") {
+            Ok(Sentence::Gsv(gsv)) => {
+                assert_eq!((gsv.sentences, gsv.sentence_number, gsv.satellites_in_view), (3, 1, 12));
+                let satellites: Vec<&SatelliteInfo> = gsv.satellites().collect();
+                assert_eq!(satellites.len(), 4);
+                assert_eq!(*satellites[0], SatelliteInfo { prn: 1, elevation: Some(15), azimuth: Some(170), snr: Some(20) });
+                assert_eq!(satellites[3].snr, None);
+            }
+            other => panic!("GSV expected, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn partial_gsv() {
+        match parse_sentence("$GPGSV,3,3,10,25,11,041,,32,05,326,*79") {
+            Ok(Sentence::Gsv(gsv)) => assert_eq!(gsv.satellites().count(), 2),
+            other => panic!("GSV expected, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn checksum_errors() {
+        assert_eq!(parse_sentence("$GPRMC,093052.00,A,6005.84256,N,02414.01597,E,1.055,,031121,,,A*B7"), Err(ParseError::BadChecksum));
+        assert_eq!(parse_sentence("$GPRMC,,V,,,,,,,,,,N"), Err(ParseError::MissingChecksum));
+        assert_eq!(parse_sentence("$GPRMC,,V,,,,,,,,,,N*5"), Err(ParseError::MissingChecksum));
+        assert_eq!(parse_sentence("$GPRMC,,V,,,,,,,,,,N*XY"), Err(ParseError::MissingChecksum));
+        assert_eq!(parse_sentence("$GPRMC,,V,,,,,,,,,,N*+3"), Err(ParseError::MissingChecksum));
+        assert_eq!(parse_sentence("$GPRMC,,V,,,,,,,,,,N*ä"), Err(ParseError::MissingChecksum));
+    }
+
+    #[test]
+    fn talkers() {
+        assert_eq!(parse_sentence("$GNRMC,093052.00,A,6005.84256,N,02414.01597,E,1.055,,031121,,,A*65").map(|s| s.talker()), Ok(Talker::Combined));
+        assert_eq!(parse_sentence("$GAGSV,1,1,02,05,40,120,30,09,20,300,25,7*7F").map(|s| s.talker()), Ok(Talker::Galileo));
+        assert_eq!(parse_sentence("$GBGSA,A,3,19,20,,,,,,,,,,,1.94,1.18,1.54,4*06").map(|s| s.talker()), Ok(Talker::BeiDou));
+        assert_eq!(parse_sentence("$ECRMC,093052.00,A,6005.84256,N,02414.01597,E,1.055,,031121,,,A*6A").map(|s| s.talker()), Ok(Talker::Other(*b"EC")));
+    }
+
+    #[test]
+    fn proprietary_sentences() {
+        assert_eq!(parse_sentence("$PUBX,00,081350.00,4717.113210,N,00833.915187,E,546.589,G3,2.1,2.0,0.007,77.52,0.007,,0.92,1.19,0.77,9,0,0*5F"), Err(ParseError::Proprietary));
+        assert_eq!(parse_sentence("$PMTK001,314,3*36"), Err(ParseError::Proprietary));
+        assert_eq!(parse_sentence("$PMTK001,314,3*00"), Err(ParseError::BadChecksum));
+    }
+
+    #[test]
+    fn unsupported_sentences() {
+        assert_eq!(parse_sentence("$GPVTG,,T,,M,0.00,N,0.00,K,A*23"), Err(ParseError::UnsupportedSentence));
+        assert_eq!(parse_sentence("!AIVDM,1,1,,B,177KQJ5000G?tO`K>RA1wUbN0TKH,0*5C"), Err(ParseError::UnsupportedSentence));
+    }
+
+    #[test]
+    fn truncated_sentences() {
+        assert_eq!(parse_sentence("$GPRMC,093052.00,A,6005.84256,N*77"), Err(ParseError::Truncated));
+        assert_eq!(parse_sentence("$GPZDA,082710.00,16*6D"), Err(ParseError::Truncated));
+    }
+
+    #[test]
+    fn malformed_fields() {
+        assert_eq!(parse_sentence("$GPRMC,093052.00,X,,,,,,,031121,,,N*7E"), Err(ParseError::FieldParse { field: "status" }));
+        assert_eq!(parse_sentence("$GPRMC,093052.00,A,60x5.84256,N,02414.01597,E,,,031121,,,A*1C"), Err(ParseError::FieldParse { field: "latitude" }));
+        assert_eq!(parse_sentence("$GPRMC,093052.00,A,6005.84256,Q,02414.01597,E,,,031121,,,A*4B"), Err(ParseError::FieldParse { field: "latitude direction" }));
+        assert_eq!(parse_sentence("$GPGGA,092725.00,,,,,9,00,,,,,,,*4A"), Err(ParseError::FieldParse { field: "fix quality" }));
+        assert_eq!(parse_sentence("$GPGGA,120000.00,9100.00000,N,02456.46150,E,1,08,1.01,10.0,M,0.0,M,,*5D"), Err(ParseError::FieldParse { field: "latitude" }));
+    }
+}
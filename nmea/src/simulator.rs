@@ -0,0 +1,166 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Deterministic fake receiver
+//!
+//! Produces RMC, GGA and ZDA sentences for every second, like a real receiver
+//! after the cold start: first it only knows the time and reports void data,
+//! then it gets the fix. Sentences could be formatted and fed to the framer,
+//! so the whole sync path is tested without a receiver.
+
+use crate::clock::advance;
+use crate::sentence::{FaaMode, FixQuality, Gga, Rmc, RmcStatus, Sentence, Zda};
+use crate::talker::Talker;
+use crate::{GpsDate, GpsPosition, GpsTime};
+
+/// Satellites, used for the simulated fix
+const FIX_SATELLITES: u8 = 8;
+/// Horizontal dilution of precision of the simulated fix, multiplied by 100
+const FIX_HDOP: u16 = 101;
+/// Altitude of the simulated fix in centimeters
+const FIX_ALTITUDE: i32 = 1500;
+
+/// Fake receiver, iterates over an endless sentences stream
+#[derive(Clone, Copy, Debug)]
+pub struct Simulator {
+    talker: Talker,
+    date: GpsDate,
+    time: GpsTime,
+    position: GpsPosition,
+    acquisition_seconds: u32,
+    elapsed_seconds: u32,
+    // Index of the next sentence in the current second
+    sentence: u8,
+}
+
+impl Simulator {
+    /// Creates receiver, which has the fix from the very first second
+    pub fn new(date: GpsDate, time: GpsTime, position: GpsPosition) -> Self {
+        Simulator {
+            talker: Talker::Gps,
+            date,
+            time,
+            position,
+            acquisition_seconds: 0,
+            elapsed_seconds: 0,
+            sentence: 0,
+        }
+    }
+
+    /// Sets the talker of all sentences
+    pub fn with_talker(self, talker: Talker) -> Self {
+        Simulator { talker, ..self }
+    }
+
+    /// Reports void data for the specified amount of seconds before the fix
+    pub fn with_acquisition(self, seconds: u32) -> Self {
+        Simulator { acquisition_seconds: seconds, ..self }
+    }
+
+    /// Amount of full seconds, already reported
+    pub fn elapsed_seconds(&self) -> u32 {
+        self.elapsed_seconds
+    }
+
+    fn has_fix(&self) -> bool {
+        self.elapsed_seconds >= self.acquisition_seconds
+    }
+
+    fn rmc(&self) -> Rmc {
+        let fix = self.has_fix();
+        Rmc {
+            talker: self.talker,
+            time: Some(self.time),
+            status: if fix { RmcStatus::Active } else { RmcStatus::Void },
+            position: if fix { Some(self.position) } else { None },
+            date: Some(self.date),
+            mode: Some(if fix { FaaMode::Autonomous } else { FaaMode::NotValid }),
+        }
+    }
+
+    fn gga(&self) -> Gga {
+        let fix = self.has_fix();
+        Gga {
+            talker: self.talker,
+            time: Some(self.time),
+            position: if fix { Some(self.position) } else { None },
+            fix_quality: if fix { FixQuality::Gps } else { FixQuality::Invalid },
+            // Satellites are found one by one during acquisition, but never enough for a fix
+            satellites: Some(if fix { FIX_SATELLITES } else { (self.elapsed_seconds / 10).min(3) as u8 }),
+            hdop: if fix { Some(FIX_HDOP) } else { None },
+            altitude: if fix { Some(FIX_ALTITUDE) } else { None },
+        }
+    }
+
+    fn zda(&self) -> Zda {
+        Zda { talker: self.talker, time: Some(self.time), date: Some(self.date) }
+    }
+}
+
+impl Iterator for Simulator {
+    type Item = Sentence;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sentence = match self.sentence {
+            0 => Sentence::Rmc(self.rmc()),
+            1 => Sentence::Gga(self.gga()),
+            _ => Sentence::Zda(self.zda()),
+        };
+        if self.sentence < 2 {
+            self.sentence += 1;
+        } else {
+            self.sentence = 0;
+            self.elapsed_seconds = self.elapsed_seconds.saturating_add(1);
+            let (date, time) = advance(self.date, self.time, 1);
+            self.date = date;
+            self.time = time;
+        }
+        Some(sentence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_sentence, GpsSync, NmeaFramer};
+
+    fn simulator() -> Simulator {
+        Simulator::new(
+            GpsDate { date: 31, month: 12, year: 2021 },
+            GpsTime { hour: 23, minute: 59, second: 30, millisecond: 0 },
+            GpsPosition { lon: 24_941_025, lat: 60_173_324 },
+        )
+    }
+
+    #[test]
+    fn deterministic() {
+        let first: Vec<String> = simulator().with_acquisition(5).take(30).map(|s| s.to_string()).collect();
+        let second: Vec<String> = simulator().with_acquisition(5).take(30).map(|s| s.to_string()).collect();
+        assert_eq!(first, second);
+        assert_eq!(first[0], "$GPRMC,235930.00,V,,,,,,,311221,,,N*71");
+        assert_eq!(first[15], "$GPRMC,235935.00,A,6010.39944,N,02456.46150,E,,,311221,,,A*50");
+    }
+
+    #[test]
+    fn cold_start_sync() {
+        // Byte stream goes through the same path as in the firmware: framer, parser and sync
+        let mut simulator = simulator().with_talker(Talker::Combined).with_acquisition(40);
+        let mut framer = NmeaFramer::new();
+        let mut sync = GpsSync::new();
+        while !sync.is_complete() {
+            let sentence = simulator.next().unwrap().to_string();
+            for byte in sentence.bytes().chain(*b"\r\n") {
+                if let Some(Ok(received)) = framer.push(byte) {
+                    sync.push(&parse_sentence(received).unwrap());
+                }
+            }
+            assert!(simulator.elapsed_seconds() < 60, "no fix in a minute");
+        }
+        assert_eq!(simulator.elapsed_seconds(), 40);
+        // Fix is received after midnight
+        let (date, time) = sync.date_time().unwrap();
+        assert_eq!(date, GpsDate { date: 1, month: 1, year: 2022 });
+        assert_eq!(time, GpsTime { hour: 0, minute: 0, second: 10, millisecond: 0 });
+        assert_eq!(sync.position(), Some(GpsPosition { lon: 24_941_025, lat: 60_173_324 }));
+    }
+}
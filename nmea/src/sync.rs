@@ -0,0 +1,199 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Collects time and position from the sentence stream
+//!
+//! Receiver reports different parts of the fix in different sentences,
+//! so the latest values of each kind are kept till the fix is good enough.
+//! Sentences from the talkers, rejected by the policy, are ignored.
+
+use crate::sentence::{FixQuality, FixType, Sentence};
+use crate::talker::TalkerPolicy;
+use crate::{GpsDate, GpsPosition, GpsTime};
+
+/// Minimal amount of satellites for a trusted position
+pub const MIN_SATELLITES: u8 = 4;
+/// Maximal horizontal dilution of precision for a trusted position, multiplied by 100
+pub const MAX_HDOP: u16 = 500;
+
+/// Accumulates the latest date, time and position, reported by the receiver
+#[derive(Clone, Copy, Debug)]
+pub struct GpsSync {
+    policy: TalkerPolicy,
+    // Rank of the best talker seen so far
+    source_rank: usize,
+    date_time: Option<(GpsDate, GpsTime)>,
+    position: Option<GpsPosition>,
+    satellites: Option<u8>,
+    hdop: Option<u16>,
+}
+
+impl Default for GpsSync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GpsSync {
+    /// Creates an empty accumulator with the default talker policy
+    pub const fn new() -> Self {
+        Self::with_policy(TalkerPolicy::DEFAULT)
+    }
+
+    /// Creates an empty accumulator with the specified talker policy
+    pub const fn with_policy(policy: TalkerPolicy) -> Self {
+        GpsSync { policy, source_rank: usize::MAX, date_time: None, position: None, satellites: None, hdop: None }
+    }
+
+    /// Updates the state with the sentence data
+    ///
+    /// Returns false if sentence is ignored due to the talker policy
+    pub fn push(&mut self, sentence: &Sentence) -> bool {
+        match self.policy.rank(sentence.talker()) {
+            Some(rank) if rank <= self.source_rank => self.source_rank = rank,
+            _ => return false,
+        }
+        match sentence {
+            Sentence::Rmc(rmc) => {
+                // Date and time are only taken together, so they are never from different seconds
+                if let Some(date_time) = rmc.date.zip(rmc.time) {
+                    self.date_time = Some(date_time);
+                }
+                if !rmc.is_valid() {
+                    self.position = None;
+                } else if rmc.position.is_some() {
+                    self.position = rmc.position;
+                }
+            }
+            Sentence::Zda(zda) => {
+                if let Some(date_time) = zda.date.zip(zda.time) {
+                    self.date_time = Some(date_time);
+                }
+            }
+            Sentence::Gga(gga) => {
+                if gga.fix_quality == FixQuality::Invalid {
+                    self.position = None;
+                } else if gga.position.is_some() {
+                    self.position = gga.position;
+                }
+                self.satellites = gga.satellites.or(self.satellites);
+                self.hdop = gga.hdop.or(self.hdop);
+            }
+            Sentence::Gsa(gsa) => {
+                if gsa.fix_type == FixType::NoFix {
+                    self.position = None;
+                } else {
+                    self.hdop = gsa.hdop.or(self.hdop);
+                }
+            }
+            Sentence::Gsv(_) => {}
+        }
+        true
+    }
+
+    /// Latest reported UTC date and time
+    pub fn date_time(&self) -> Option<(GpsDate, GpsTime)> {
+        self.date_time
+    }
+
+    /// Latest reported position
+    pub fn position(&self) -> Option<GpsPosition> {
+        self.position
+    }
+
+    /// Checks if position is trusted
+    ///
+    /// Satellites count and HDOP are checked only if receiver reports them
+    pub fn has_good_fix(&self) -> bool {
+        self.position.is_some()
+            && self.satellites.map(|s| s >= MIN_SATELLITES).unwrap_or(true)
+            && self.hdop.map(|h| h <= MAX_HDOP).unwrap_or(true)
+    }
+
+    /// Checks if both date/time and trusted position are received
+    pub fn is_complete(&self) -> bool {
+        self.date_time.is_some() && self.has_good_fix()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_sentence;
+    use crate::talker::Talker;
+
+    fn feed(sync: &mut GpsSync, sentences: &[&str]) {
+        for sentence in sentences {
+            sync.push(&parse_sentence(sentence).unwrap());
+        }
+    }
+
+    #[test]
+    fn date_without_fix() {
+        let mut sync = GpsSync::new();
+        feed(&mut sync, &["$GPZDA,082710.00,16,09,2002,00,00*64", "$GPGGA,092725.00,,,,,0,00,99.99,,,,,,*6D"]);
+        assert_eq!(sync.date_time().map(|(d, _)| d), Some(GpsDate { date: 16, month: 9, year: 2002 }));
+        assert_eq!(sync.position(), None);
+        assert!(!sync.is_complete());
+    }
+
+    #[test]
+    fn complete_fix() {
+        let mut sync = GpsSync::new();
+        feed(
+            &mut sync,
+            &[
+                "$GPRMC,093052.00,A,6005.84256,N,02414.01597,E,1.055,,031121,,,A*7B",
+                "$GPGGA,092725.00,4717.11399,N,00833.91590,E,1,08,1.01,499.6,M,48.0,M,,*5B",
+            ],
+        );
+        assert!(sync.is_complete());
+    }
+
+    #[test]
+    fn poor_fix() {
+        let mut sync = GpsSync::new();
+        feed(
+            &mut sync,
+            &[
+                "$GPRMC,093052.00,A,6005.84256,N,02414.01597,E,1.055,,031121,,,A*7B",
+                "$GPGSA,A,3,23,29,07,08,09,18,26,28,,,,,1.94,7.18,1.54*0B",
+            ],
+        );
+        assert!(sync.position().is_some());
+        assert!(!sync.is_complete());
+    }
+
+    #[test]
+    fn void_rmc_drops_position() {
+        let mut sync = GpsSync::new();
+        feed(
+            &mut sync,
+            &[
+                "$GPRMC,093052.00,A,6005.84256,N,02414.01597,E,1.055,,031121,,,A*7B",
+                "$GPRMC,093053.00,V,6005.84256,N,02414.01597,E,1.055,,031121,,,N*62",
+            ],
+        );
+        assert_eq!(sync.date_time().map(|(_, t)| t.second), Some(53));
+        assert_eq!(sync.position(), None);
+    }
+
+    #[test]
+    fn preferred_talker() {
+        let mut sync = GpsSync::new();
+        feed(&mut sync, &["$GNRMC,093052.00,A,6005.84256,N,02414.01597,E,1.055,,031121,,,A*65"]);
+        // Lower ranked GPS only solution is ignored after the combined one
+        let gps = parse_sentence("$GPRMC,093053.00,V,6005.84256,N,02414.01597,E,1.055,,031121,,,N*62").unwrap();
+        assert!(!sync.push(&gps));
+        assert!(sync.position().is_some());
+        assert_eq!(sync.date_time().map(|(_, t)| t.second), Some(52));
+    }
+
+    #[test]
+    fn only_talker() {
+        let mut sync = GpsSync::with_policy(TalkerPolicy::Only(Talker::Galileo));
+        let gps = parse_sentence("$GPRMC,093052.00,A,6005.84256,N,02414.01597,E,1.055,,031121,,,A*7B").unwrap();
+        assert!(!sync.push(&gps));
+        assert_eq!(sync.date_time(), None);
+    }
+}
@@ -0,0 +1,120 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Talker identifiers
+//!
+//! Multi-constellation receivers report the same data from several
+//! sources, like `$GPRMC` and `$GNRMC`, so the source of every sentence
+//! is decoded and the preferred one could be selected.
+
+/// Source of the sentence, first two characters of the address field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Talker {
+    /// GPS, `GP`
+    Gps,
+    /// GLONASS, `GL`
+    Glonass,
+    /// Galileo, `GA`
+    Galileo,
+    /// BeiDou, `BD` or `GB`
+    BeiDou,
+    /// QZSS, `GQ` or `QZ`
+    Qzss,
+    /// NavIC, `GI`
+    Navic,
+    /// Combined solution of several constellations, `GN`
+    Combined,
+    /// Any other talker, like `EC` for ECDIS
+    Other([u8; 2]),
+}
+
+impl Talker {
+    /// Decodes talker identifier, returns None if it is not two characters
+    pub fn parse(id: &str) -> Option<Self> {
+        match id {
+            "GP" => Some(Talker::Gps),
+            "GL" => Some(Talker::Glonass),
+            "GA" => Some(Talker::Galileo),
+            "BD" | "GB" => Some(Talker::BeiDou),
+            "GQ" | "QZ" => Some(Talker::Qzss),
+            "GI" => Some(Talker::Navic),
+            "GN" => Some(Talker::Combined),
+            _ => match id.as_bytes() {
+                [first, second] => Some(Talker::Other([*first, *second])),
+                _ => None,
+            },
+        }
+    }
+
+    /// Two characters talker identifier, NMEA 4.11 `GB` is used for BeiDou
+    pub fn id(&self) -> [u8; 2] {
+        match self {
+            Talker::Gps => *b"GP",
+            Talker::Glonass => *b"GL",
+            Talker::Galileo => *b"GA",
+            Talker::BeiDou => *b"GB",
+            Talker::Qzss => *b"GQ",
+            Talker::Navic => *b"GI",
+            Talker::Combined => *b"GN",
+            Talker::Other(id) => *id,
+        }
+    }
+}
+
+/// Selects talkers, which data is trusted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TalkerPolicy {
+    /// Data from all talkers is used
+    Any,
+    /// Data only from the specified talker is used
+    Only(Talker),
+    /// Talkers are ranked by the position in the list, unlisted talkers are ranked last.
+    /// Once data from some talker is used, data from lower ranked talkers is ignored
+    Prefer(&'static [Talker]),
+}
+
+impl TalkerPolicy {
+    /// Combined solution first, then GPS, then any other talker
+    pub const DEFAULT: TalkerPolicy = TalkerPolicy::Prefer(&[Talker::Combined, Talker::Gps]);
+
+    /// Rank of the talker, lower is better, None if talker is not accepted at all
+    pub fn rank(&self, talker: Talker) -> Option<usize> {
+        match self {
+            TalkerPolicy::Any => Some(0),
+            TalkerPolicy::Only(only) if *only == talker => Some(0),
+            TalkerPolicy::Only(_) => None,
+            TalkerPolicy::Prefer(talkers) => Some(talkers.iter().position(|t| *t == talker).unwrap_or(talkers.len())),
+        }
+    }
+}
+
+impl Default for TalkerPolicy {
+    fn default() -> Self {
+        TalkerPolicy::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn talkers() {
+        assert_eq!(Talker::parse("GN"), Some(Talker::Combined));
+        assert_eq!(Talker::parse("GB"), Some(Talker::BeiDou));
+        assert_eq!(Talker::parse("BD"), Some(Talker::BeiDou));
+        assert_eq!(Talker::parse("EC"), Some(Talker::Other(*b"EC")));
+        assert_eq!(Talker::parse("G"), None);
+        assert_eq!(Talker::parse("BD").map(|t| t.id()), Some(*b"GB"));
+        assert_eq!(Talker::parse("EC").map(|t| t.id()), Some(*b"EC"));
+    }
+
+    #[test]
+    fn ranks() {
+        assert_eq!(TalkerPolicy::Any.rank(Talker::Glonass), Some(0));
+        assert_eq!(TalkerPolicy::Only(Talker::Gps).rank(Talker::Glonass), None);
+        assert_eq!(TalkerPolicy::DEFAULT.rank(Talker::Combined), Some(0));
+        assert_eq!(TalkerPolicy::DEFAULT.rank(Talker::Gps), Some(1));
+        assert_eq!(TalkerPolicy::DEFAULT.rank(Talker::Galileo), Some(2));
+    }
+}
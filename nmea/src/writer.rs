@@ -0,0 +1,302 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! NMEA sentence serialization
+//!
+//! Typed sentences are formatted with `Display`, including the
+//! start delimiter and the checksum, but without CR/LF. Fields, that are not
+//! kept in the typed sentences, like speed over ground, are left empty.
+
+use core::fmt::{self, Display, Formatter, Write};
+
+use crate::sentence::{FaaMode, FixQuality, FixType, Gga, Gsa, Gsv, Rmc, RmcStatus, Sentence, Zda, GSA_SATELLITES};
+use crate::talker::Talker;
+use crate::{GpsDate, GpsPosition, GpsTime};
+
+/// Passes data through and calculates its checksum
+struct ChecksumWriter<'a, 'b> {
+    out: &'a mut Formatter<'b>,
+    checksum: u8,
+}
+
+impl Write for ChecksumWriter<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.checksum = s.bytes().fold(self.checksum, |checksum, byte| checksum ^ byte);
+        self.out.write_str(s)
+    }
+}
+
+/// Writes the delimiter, the address, the data and the checksum
+fn write_sentence(
+    out: &mut Formatter,
+    talker: Talker,
+    sentence: &str,
+    data: impl FnOnce(&mut ChecksumWriter) -> fmt::Result,
+) -> fmt::Result {
+    out.write_char('$')?;
+    let mut writer = ChecksumWriter { out, checksum: 0 };
+    for byte in talker.id() {
+        writer.write_char(char::from(byte))?;
+    }
+    writer.write_str(sentence)?;
+    data(&mut writer)?;
+    write!(writer.out, "*{:02X}", writer.checksum)
+}
+
+fn write_time(out: &mut impl Write, time: Option<GpsTime>) -> fmt::Result {
+    match time {
+        Some(t) => write!(out, ",{:02}{:02}{:02}.{:02}", t.hour, t.minute, t.second, t.millisecond / 10),
+        None => out.write_char(','),
+    }
+}
+
+/// Writes coordinate in ddmm.mmmmm format, which is lossless for the microdegrees
+fn write_coordinate(out: &mut impl Write, value: i32, degree_digits: usize, positive: char, negative: char) -> fmt::Result {
+    let absolute = value.unsigned_abs();
+    let minutes = absolute % 1_000_000 * 60;
+    write!(
+        out,
+        ",{:0width$}{:02}.{:05},{}",
+        absolute / 1_000_000,
+        minutes / 1_000_000,
+        minutes % 1_000_000 / 10,
+        if value < 0 { negative } else { positive },
+        width = degree_digits
+    )
+}
+
+fn write_position(out: &mut impl Write, position: Option<GpsPosition>) -> fmt::Result {
+    match position {
+        Some(p) => {
+            write_coordinate(out, p.lat, 2, 'N', 'S')?;
+            write_coordinate(out, p.lon, 3, 'E', 'W')
+        }
+        None => out.write_str(",,,,"),
+    }
+}
+
+/// Writes fixed point value with two fractional digits
+fn write_fixed(out: &mut impl Write, value: Option<i32>) -> fmt::Result {
+    match value {
+        Some(v) => {
+            let sign = if v < 0 { "-" } else { "" };
+            write!(out, ",{}{}.{:02}", sign, v.unsigned_abs() / 100, v.unsigned_abs() % 100)
+        }
+        None => out.write_char(','),
+    }
+}
+
+fn write_number(out: &mut impl Write, value: Option<impl Display>, width: usize) -> fmt::Result {
+    match value {
+        Some(v) => write!(out, ",{:0width$}", v, width = width),
+        None => out.write_char(','),
+    }
+}
+
+fn write_date(out: &mut impl Write, date: Option<GpsDate>) -> fmt::Result {
+    match date {
+        Some(d) => write!(out, ",{:02}{:02}{:02}", d.date, d.month, d.year % 100),
+        None => out.write_char(','),
+    }
+}
+
+impl RmcStatus {
+    fn symbol(&self) -> char {
+        match self {
+            RmcStatus::Active => 'A',
+            RmcStatus::Void => 'V',
+        }
+    }
+}
+
+impl FaaMode {
+    fn symbol(&self) -> char {
+        match self {
+            FaaMode::Autonomous => 'A',
+            FaaMode::Differential => 'D',
+            FaaMode::Estimated => 'E',
+            FaaMode::FloatRtk => 'F',
+            FaaMode::Manual => 'M',
+            FaaMode::NotValid => 'N',
+            FaaMode::Precise => 'P',
+            FaaMode::Rtk => 'R',
+            FaaMode::Simulator => 'S',
+        }
+    }
+}
+
+impl FixQuality {
+    fn symbol(&self) -> char {
+        match self {
+            FixQuality::Invalid => '0',
+            FixQuality::Gps => '1',
+            FixQuality::Dgps => '2',
+            FixQuality::Pps => '3',
+            FixQuality::Rtk => '4',
+            FixQuality::FloatRtk => '5',
+            FixQuality::Estimated => '6',
+            FixQuality::Manual => '7',
+            FixQuality::Simulation => '8',
+        }
+    }
+}
+
+impl Display for Rmc {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_sentence(f, self.talker, "RMC", |out| {
+            write_time(out, self.time)?;
+            write!(out, ",{}", self.status.symbol())?;
+            write_position(out, self.position)?;
+            // Speed and track
+            out.write_str(",,")?;
+            write_date(out, self.date)?;
+            // Magnetic variation
+            out.write_str(",,")?;
+            if let Some(mode) = self.mode {
+                write!(out, ",{}", mode.symbol())?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Display for Gga {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_sentence(f, self.talker, "GGA", |out| {
+            write_time(out, self.time)?;
+            write_position(out, self.position)?;
+            write!(out, ",{}", self.fix_quality.symbol())?;
+            write_number(out, self.satellites, 2)?;
+            write_fixed(out, self.hdop.map(i32::from))?;
+            write_fixed(out, self.altitude)?;
+            // Altitude units, geoid separation, differential data age and station
+            out.write_str(",M,,M,,")
+        })
+    }
+}
+
+impl Display for Zda {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_sentence(f, self.talker, "ZDA", |out| {
+            write_time(out, self.time)?;
+            match self.date {
+                Some(d) => write!(out, ",{:02},{:02},{:04}", d.date, d.month, d.year)?,
+                None => out.write_str(",,,")?,
+            }
+            // Local zone is always UTC
+            out.write_str(",00,00")
+        })
+    }
+}
+
+impl Display for Gsa {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_sentence(f, self.talker, "GSA", |out| {
+            out.write_str(if self.automatic { ",A" } else { ",M" })?;
+            let fix_type = match self.fix_type {
+                FixType::NoFix => 1,
+                FixType::Fix2D => 2,
+                FixType::Fix3D => 3,
+            };
+            write!(out, ",{}", fix_type)?;
+            let mut satellites = self.satellites().iter();
+            for _ in 0..GSA_SATELLITES {
+                write_number(out, satellites.next(), 2)?;
+            }
+            for dop in [self.pdop, self.hdop, self.vdop] {
+                write_fixed(out, dop.map(i32::from))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Display for Gsv {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_sentence(f, self.talker, "GSV", |out| {
+            write!(out, ",{},{},{:02}", self.sentences, self.sentence_number, self.satellites_in_view)?;
+            for satellite in self.satellites() {
+                write!(out, ",{:02}", satellite.prn)?;
+                write_number(out, satellite.elevation, 2)?;
+                write_number(out, satellite.azimuth, 3)?;
+                write_number(out, satellite.snr, 2)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Display for Sentence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Sentence::Rmc(rmc) => rmc.fmt(f),
+            Sentence::Gga(gga) => gga.fmt(f),
+            Sentence::Zda(zda) => zda.fmt(f),
+            Sentence::Gsa(gsa) => gsa.fmt(f),
+            Sentence::Gsv(gsv) => gsv.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_sentence, Sentence};
+
+    #[test]
+    fn rmc() {
+        let rmc = Rmc {
+            talker: Talker::Gps,
+            time: Some(GpsTime { hour: 9, minute: 30, second: 52, millisecond: 0 }),
+            status: RmcStatus::Active,
+            position: Some(GpsPosition { lon: 24_233_600, lat: 60_097_376 }),
+            date: Some(GpsDate { date: 3, month: 11, year: 2021 }),
+            mode: Some(FaaMode::Autonomous),
+        };
+        assert_eq!(rmc.to_string(), "$GPRMC,093052.00,A,6005.84256,N,02414.01600,E,,,031121,,,A*59");
+        assert_eq!(parse_sentence(&rmc.to_string()), Ok(Sentence::Rmc(rmc)));
+    }
+
+    #[test]
+    fn empty_rmc() {
+        let rmc = Rmc { talker: Talker::Gps, time: None, status: RmcStatus::Void, position: None, date: None, mode: None };
+        assert_eq!(parse_sentence(&rmc.to_string()), Ok(Sentence::Rmc(rmc)));
+    }
+
+    #[test]
+    fn gga() {
+        let gga = Gga {
+            talker: Talker::Combined,
+            time: Some(GpsTime { hour: 23, minute: 59, second: 59, millisecond: 990 }),
+            position: Some(GpsPosition { lon: -58_381_733, lat: -34_603_733 }),
+            fix_quality: FixQuality::Dgps,
+            satellites: Some(7),
+            hdop: Some(95),
+            altitude: Some(-1205),
+        };
+        assert_eq!(gga.to_string(), "$GNGGA,235959.99,3436.22398,S,05822.90398,W,2,07,0.95,-12.05,M,,M,,*77");
+        assert_eq!(parse_sentence(&gga.to_string()), Ok(Sentence::Gga(gga)));
+    }
+
+    #[test]
+    fn zda() {
+        let zda = Zda {
+            talker: Talker::Galileo,
+            time: Some(GpsTime { hour: 8, minute: 27, second: 10, millisecond: 500 }),
+            date: Some(GpsDate { date: 16, month: 9, year: 2002 }),
+        };
+        assert_eq!(zda.to_string(), "$GAZDA,082710.50,16,09,2002,00,00*70");
+        assert_eq!(parse_sentence(&zda.to_string()), Ok(Sentence::Zda(zda)));
+    }
+
+    #[test]
+    fn satellites() {
+        for nmea in [
+            "$GPGSA,A,3,23,29,07,08,09,18,26,28,,,,,1.94,1.18,1.54*0D",
+            "$GPGSV,3,1,12,01,15,170,20,02,08,326,18,03,63,126,22,04,66,205,*7D",
+            "$GPGSV,3,3,10,25,11,041,,32,05,326,*79",
+        ] {
+            assert_eq!(parse_sentence(nmea).map(|s| s.to_string()).as_deref(), Ok(nmea));
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Property based tests of the parser
+//!
+//! * serialized sentences always parse back to the same values
+//! * no input makes the parser, the framer or the RTC helper panic
+
+use nmea::{
+    parse_sentence, rtc_setting, FaaMode, FixQuality, Gga, GpsDate, GpsPosition, GpsTime, NmeaFramer, Rmc, RmcStatus,
+    Sentence, Talker, Zda,
+};
+use proptest::prelude::*;
+
+fn talker() -> impl Strategy<Value = Talker> {
+    prop_oneof![
+        Just(Talker::Gps),
+        Just(Talker::Glonass),
+        Just(Talker::Galileo),
+        Just(Talker::BeiDou),
+        Just(Talker::Qzss),
+        Just(Talker::Navic),
+        Just(Talker::Combined),
+    ]
+}
+
+fn time() -> impl Strategy<Value = GpsTime> {
+    // Sentences keep only hundredths of a second
+    (0..24u32, 0..60u32, 0..61u32, 0..100u32).prop_map(|(hour, minute, second, centisecond)| GpsTime {
+        hour,
+        minute,
+        second,
+        millisecond: centisecond * 10,
+    })
+}
+
+fn date(years: core::ops::Range<u32>) -> impl Strategy<Value = GpsDate> {
+    (1..=31u32, 1..=12u32, years).prop_map(|(date, month, year)| GpsDate { date, month, year })
+}
+
+fn position() -> impl Strategy<Value = GpsPosition> {
+    (-180_000_000..=180_000_000i32, -90_000_000..=90_000_000i32).prop_map(|(lon, lat)| GpsPosition { lon, lat })
+}
+
+fn faa_mode() -> impl Strategy<Value = FaaMode> {
+    prop_oneof![
+        Just(FaaMode::Autonomous),
+        Just(FaaMode::Differential),
+        Just(FaaMode::Estimated),
+        Just(FaaMode::FloatRtk),
+        Just(FaaMode::Manual),
+        Just(FaaMode::NotValid),
+        Just(FaaMode::Precise),
+        Just(FaaMode::Rtk),
+        Just(FaaMode::Simulator),
+    ]
+}
+
+fn fix_quality() -> impl Strategy<Value = FixQuality> {
+    prop_oneof![
+        Just(FixQuality::Invalid),
+        Just(FixQuality::Gps),
+        Just(FixQuality::Dgps),
+        Just(FixQuality::Pps),
+        Just(FixQuality::Rtk),
+        Just(FixQuality::FloatRtk),
+        Just(FixQuality::Estimated),
+        Just(FixQuality::Manual),
+        Just(FixQuality::Simulation),
+    ]
+}
+
+fn rmc() -> impl Strategy<Value = Rmc> {
+    (
+        talker(),
+        proptest::option::of(time()),
+        prop_oneof![Just(RmcStatus::Active), Just(RmcStatus::Void)],
+        proptest::option::of(position()),
+        proptest::option::of(date(2000..2100)),
+        proptest::option::of(faa_mode()),
+    )
+        .prop_map(|(talker, time, status, position, date, mode)| Rmc { talker, time, status, position, date, mode })
+}
+
+fn gga() -> impl Strategy<Value = Gga> {
+    (
+        talker(),
+        proptest::option::of(time()),
+        proptest::option::of(position()),
+        fix_quality(),
+        proptest::option::of(any::<u8>()),
+        proptest::option::of(any::<u16>()),
+        proptest::option::of(-100_000_000..100_000_000i32),
+    )
+        .prop_map(|(talker, time, position, fix_quality, satellites, hdop, altitude)| Gga {
+            talker,
+            time,
+            // Position is never reported without a fix
+            position: position.filter(|_| fix_quality != FixQuality::Invalid),
+            fix_quality,
+            satellites,
+            hdop,
+            altitude,
+        })
+}
+
+fn zda() -> impl Strategy<Value = Zda> {
+    (talker(), proptest::option::of(time()), proptest::option::of(date(0..10000)))
+        .prop_map(|(talker, time, date)| Zda { talker, time, date })
+}
+
+fn with_checksum(data: &str) -> String {
+    let checksum = data.bytes().fold(0u8, |checksum, byte| checksum ^ byte);
+    format!("${}*{:02X}", data, checksum)
+}
+
+proptest! {
+    #[test]
+    fn rmc_round_trip(rmc in rmc()) {
+        prop_assert_eq!(parse_sentence(&rmc.to_string()), Ok(Sentence::Rmc(rmc)));
+    }
+
+    #[test]
+    fn gga_round_trip(gga in gga()) {
+        prop_assert_eq!(parse_sentence(&gga.to_string()), Ok(Sentence::Gga(gga)));
+    }
+
+    #[test]
+    fn zda_round_trip(zda in zda()) {
+        prop_assert_eq!(parse_sentence(&zda.to_string()), Ok(Sentence::Zda(zda)));
+    }
+
+    #[test]
+    fn serialized_sentences_pass_framer(rmc in rmc()) {
+        let sentence = rmc.to_string();
+        prop_assert!(sentence.len() + 2 <= nmea::MAX_SENTENCE_LENGTH);
+        let mut framer = NmeaFramer::new();
+        let received: Vec<String> = format!("{}\r\n", sentence)
+            .bytes()
+            .filter_map(|byte| framer.push(byte).and_then(|r| r.ok()).map(|s| s.to_string()))
+            .collect();
+        prop_assert_eq!(received, vec![sentence]);
+    }
+
+    #[test]
+    fn any_string_never_panics(input in any::<String>()) {
+        let _ = parse_sentence(&input);
+    }
+
+    #[test]
+    fn any_fields_never_panic(
+        address in prop_oneof![Just("GPRMC"), Just("GNGGA"), Just("GPZDA"), Just("GPGSA"), Just("GLGSV"), Just("PUBX"), Just("\u{e4}RMC")],
+        fields in "[0-9A-Z.,+\\-\u{e4}]{0,80}",
+    ) {
+        // Valid checksum makes the parser go through all the fields
+        let _ = parse_sentence(&with_checksum(&format!("{},{}", address, fields)));
+    }
+
+    #[test]
+    fn any_bytes_never_panic(bytes in proptest::collection::vec(any::<u8>(), 0..512)) {
+        let mut framer = NmeaFramer::new();
+        for byte in bytes {
+            if let Some(Ok(sentence)) = framer.push(byte) {
+                let _ = parse_sentence(sentence);
+            }
+        }
+    }
+
+    #[test]
+    fn rtc_setting_never_panics(
+        (date, month, year) in (any::<u32>(), any::<u32>(), any::<u32>()),
+        (hour, minute, second, millisecond) in (any::<u32>(), any::<u32>(), any::<u32>(), any::<u32>()),
+        latency_ms in any::<u32>(),
+        elapsed_ms in any::<u32>(),
+    ) {
+        let setting = rtc_setting(GpsDate { date, month, year }, GpsTime { hour, minute, second, millisecond }, latency_ms, elapsed_ms);
+        if let Some(setting) = setting {
+            prop_assert!(setting.delay_ms < 1000);
+            prop_assert_eq!(setting.time.millisecond, 0);
+        }
+    }
+
+    #[test]
+    fn rtc_setting_of_valid_time(date in date(2000..2100), time in time(), latency_ms in 0..1000u32, elapsed_ms in 0..10_000u32) {
+        prop_assume!(date.date <= 28);
+        let setting = rtc_setting(date, time, latency_ms, elapsed_ms).unwrap();
+        prop_assert!(setting.delay_ms < 1000);
+        prop_assert!(setting.time.hour < 24 && setting.time.minute < 60 && setting.time.second < 60);
+    }
+}
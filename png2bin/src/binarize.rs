@@ -0,0 +1,312 @@
+//! Conversion of arbitrary PNG pixels into black and white
+//!
+//! Pixels are reduced to 8-bit luminance first, transparent pixels are
+//! blended over the white paper. Luminance is adjusted with gamma and contrast
+//! and then every pixel is classified as black or white by one of the
+//! binarization methods. Fixed threshold suits already binarized images
+//! and line art, dithering keeps halftones of the photos.
+
+use crate::ConversionError;
+use png::ColorType;
+use std::str::FromStr;
+
+/// Ordered dithering matrix, values are in 0..64
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// Way to decide, whether pixel is black or white
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Binarization {
+    /// Pixels brighter than the fixed threshold are white
+    Threshold,
+    /// Threshold is selected for every image with Otsu's method
+    Otsu,
+    /// Ordered dithering with 8x8 Bayer matrix
+    Bayer,
+    /// Error diffusion dithering
+    FloydSteinberg,
+}
+
+impl FromStr for Binarization {
+    type Err = ConversionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "threshold" => Ok(Binarization::Threshold),
+            "otsu" => Ok(Binarization::Otsu),
+            "bayer" => Ok(Binarization::Bayer),
+            "floyd-steinberg" => Ok(Binarization::FloydSteinberg),
+            _ => Err(ConversionError::Binarization),
+        }
+    }
+}
+
+pub(crate) fn blend_over_white(level: u8, alpha: u8) -> u8 {
+    ((level as u32 * alpha as u32 + 255 * (255 - alpha as u32) + 127) / 255) as u8
+}
+
+pub(crate) fn luma(r: u8, g: u8, b: u8) -> u8 {
+    ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114 + 500) / 1000) as u8
+}
+
+/// Converts 8-bit pixels into luminance, one byte per pixel.
+/// Palette images should be expanded to RGB by the decoder.
+pub(crate) fn to_luminance(pixels: &[u8], color_type: ColorType) -> Option<Vec<u8>> {
+    match color_type {
+        ColorType::Grayscale => Some(pixels.to_vec()),
+        ColorType::GrayscaleAlpha => Some(
+            pixels
+                .chunks_exact(2)
+                .map(|p| blend_over_white(p[0], p[1]))
+                .collect(),
+        ),
+        ColorType::Rgb => Some(
+            pixels
+                .chunks_exact(3)
+                .map(|p| luma(p[0], p[1], p[2]))
+                .collect(),
+        ),
+        ColorType::Rgba => Some(
+            pixels
+                .chunks_exact(4)
+                .map(|p| blend_over_white(luma(p[0], p[1], p[2]), p[3]))
+                .collect(),
+        ),
+        ColorType::Indexed => None,
+    }
+}
+
+/// Applies gamma and contrast to the luminance.
+///
+/// Gamma above 1 brightens mid tones, contrast above 1 pushes levels away
+/// from the middle gray. Both equal to 1 keep luminance as is.
+pub(crate) fn adjust(luminance: &mut [u8], gamma: f64, contrast: f64) {
+    let table: Vec<u8> = (0..=255u8)
+        .map(|level| {
+            let value = (level as f64 / 255.0).powf(1.0 / gamma);
+            let value = (value - 0.5) * contrast + 0.5;
+            (value * 255.0).round().clamp(0.0, 255.0) as u8
+        })
+        .collect();
+    luminance
+        .iter_mut()
+        .for_each(|level| *level = table[*level as usize]);
+}
+
+/// Threshold, maximizing between class variance of the black and white pixels
+fn otsu_threshold(luminance: &[u8]) -> u8 {
+    let mut histogram = [0u64; 256];
+    luminance
+        .iter()
+        .for_each(|level| histogram[*level as usize] += 1);
+    let total = luminance.len() as f64;
+    let sum: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(level, count)| level as f64 * *count as f64)
+        .sum();
+
+    let mut threshold = 0;
+    let mut best_variance = 0.0;
+    let mut black_count = 0.0;
+    let mut black_sum = 0.0;
+    for (level, count) in histogram.iter().enumerate() {
+        black_count += *count as f64;
+        black_sum += level as f64 * *count as f64;
+        let white_count = total - black_count;
+        if black_count == 0.0 || white_count == 0.0 {
+            continue;
+        }
+        let difference = black_sum / black_count - (sum - black_sum) / white_count;
+        let variance = black_count * white_count * difference * difference;
+        if variance > best_variance {
+            best_variance = variance;
+            threshold = level as u8;
+        }
+    }
+    threshold
+}
+
+/// Diffuses quantization error to the neighbours, serpentine scan avoids directional artifacts
+fn floyd_steinberg(luminance: &[u8], width: usize) -> Vec<bool> {
+    let mut levels: Vec<i16> = luminance.iter().map(|level| *level as i16).collect();
+    let mut white = vec![false; levels.len()];
+    let height = levels.len() / width;
+    for y in 0..height {
+        let reverse = y % 2 == 1;
+        for step in 0..width {
+            let x = if reverse { width - 1 - step } else { step };
+            let index = y * width + x;
+            white[index] = levels[index] > 127;
+            let error = levels[index] - if white[index] { 255 } else { 0 };
+
+            let ahead = if reverse {
+                x.checked_sub(1)
+            } else {
+                Some(x + 1).filter(|x| *x < width)
+            };
+            let behind = if reverse {
+                Some(x + 1).filter(|x| *x < width)
+            } else {
+                x.checked_sub(1)
+            };
+            let (right, below_left, below) = (error * 7 / 16, error * 3 / 16, error * 5 / 16);
+            let below_right = error - right - below_left - below;
+            if let Some(ahead) = ahead {
+                levels[y * width + ahead] += right;
+            }
+            if y + 1 < height {
+                let next_row = (y + 1) * width;
+                if let Some(behind) = behind {
+                    levels[next_row + behind] += below_left;
+                }
+                levels[next_row + x] += below;
+                if let Some(ahead) = ahead {
+                    levels[next_row + ahead] += below_right;
+                }
+            }
+        }
+    }
+    white
+}
+
+/// Classifies pixels, returns true for the white ones
+///
+/// `threshold` is used only by the fixed threshold method, pixels
+/// brighter than it are white.
+pub(crate) fn binarize(
+    luminance: &[u8],
+    width: usize,
+    method: Binarization,
+    threshold: u8,
+) -> Vec<bool> {
+    match method {
+        Binarization::Threshold => luminance.iter().map(|level| *level > threshold).collect(),
+        Binarization::Otsu => {
+            let threshold = otsu_threshold(luminance);
+            luminance.iter().map(|level| *level > threshold).collect()
+        }
+        Binarization::Bayer => luminance
+            .iter()
+            .enumerate()
+            .map(|(index, level)| {
+                let cell = BAYER[index / width % 8][index % width % 8];
+                // Matrix values are mapped to the middle of their luminance range
+                *level > cell * 4 + 2
+            })
+            .collect(),
+        Binarization::FloydSteinberg => floyd_steinberg(luminance, width),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn white_share(white: &[bool]) -> f64 {
+        white.iter().filter(|white| **white).count() as f64 / white.len() as f64
+    }
+
+    #[test]
+    fn luminance() {
+        let rgba = [0, 0, 0, 0, 0, 0, 0, 255, 0, 0, 0, 128, 255, 0, 0, 255];
+        assert_eq!(
+            to_luminance(&rgba, ColorType::Rgba),
+            Some(vec![255, 0, 127, 76])
+        );
+        let gray_alpha = [0, 0, 100, 255, 200, 0];
+        assert_eq!(
+            to_luminance(&gray_alpha, ColorType::GrayscaleAlpha),
+            Some(vec![255, 100, 255])
+        );
+        assert_eq!(
+            to_luminance(&[255, 255, 255, 0, 0, 255], ColorType::Rgb),
+            Some(vec![255, 29])
+        );
+        assert_eq!(to_luminance(&[0, 1, 2], ColorType::Indexed), None);
+    }
+
+    #[test]
+    fn neutral_adjustment() {
+        let mut levels: Vec<u8> = (0..=255).collect();
+        adjust(&mut levels, 1.0, 1.0);
+        assert_eq!(levels, (0..=255).collect::<Vec<u8>>());
+
+        let mut levels = vec![0, 64, 128, 192, 255];
+        adjust(&mut levels, 2.0, 1.0);
+        assert!(levels[1] > 64 && levels[2] > 128);
+        assert_eq!((levels[0], levels[4]), (0, 255));
+        let mut levels = vec![0, 64, 192, 255];
+        adjust(&mut levels, 1.0, 2.0);
+        assert_eq!(levels, vec![0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn default_threshold() {
+        // Old conversion made any non-zero pixel white
+        let levels: Vec<u8> = (0..=255).collect();
+        let white = binarize(&levels, 16, Binarization::Threshold, 0);
+        assert!(levels
+            .iter()
+            .zip(&white)
+            .all(|(level, white)| (*level > 0) == *white));
+        let white = binarize(&levels, 16, Binarization::Threshold, 200);
+        assert_eq!(white.iter().filter(|white| **white).count(), 55);
+    }
+
+    #[test]
+    fn otsu_two_peaks() {
+        let levels: Vec<u8> = (0..1000)
+            .map(|i| {
+                if i % 3 == 0 {
+                    30 + (i % 20) as u8
+                } else {
+                    190 + (i % 20) as u8
+                }
+            })
+            .collect();
+        let threshold = otsu_threshold(&levels);
+        assert!((49..190).contains(&threshold), "threshold {}", threshold);
+        let white = binarize(&levels, 10, Binarization::Otsu, 0);
+        assert!(levels
+            .iter()
+            .zip(&white)
+            .all(|(level, white)| (*level >= 190) == *white));
+    }
+
+    #[test]
+    fn dithering_of_mid_gray() {
+        let levels = vec![128u8; 32 * 32];
+        for method in [Binarization::Bayer, Binarization::FloydSteinberg] {
+            let share = white_share(&binarize(&levels, 32, method, 0));
+            assert!((0.45..=0.55).contains(&share), "{:?}: {}", method, share);
+        }
+        let share = white_share(&binarize(
+            &vec![64u8; 32 * 32],
+            32,
+            Binarization::FloydSteinberg,
+            0,
+        ));
+        assert!((0.2..=0.3).contains(&share), "{}", share);
+    }
+
+    #[test]
+    fn dithering_of_solid_colors() {
+        for method in [Binarization::Bayer, Binarization::FloydSteinberg] {
+            assert!(binarize(&[0; 24 * 5], 24, method, 0)
+                .iter()
+                .all(|white| !white));
+            assert!(binarize(&[255; 24 * 5], 24, method, 0)
+                .iter()
+                .all(|white| *white));
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! Glyph sheet to font conversion
+//!
+//! Sheet pixels are split into glyphs by the marker row and packed into
+//! the font, which is read by the firmware without decompression.
+
+use crate::ConversionError;
+use anyhow::Result;
+use bit_field::BitField;
+use byteorder::{LittleEndian, WriteBytesExt};
+
+/// Encoded font
+pub(crate) struct Font {
+    /// Font data, without the length prefix
+    pub data: Vec<u8>,
+    /// Amount of glyphs in the font
+    pub glyphs: usize,
+    /// Glyph height in pixels
+    pub height: usize,
+}
+
+/// Encodes 8-bit grayscale glyph sheet into the font
+///
+/// `description` is the content of the _txt_ file: listed characters on the first line
+/// and optional baseline on the second one.
+pub(crate) fn encode_font(pixels: &[u8], width: usize, description: &str) -> Result<Font> {
+    let height = (pixels.len() / width).saturating_sub(1); //First row is a marker row
+
+    let mut lines = description.lines();
+    let characters: Vec<char> = lines.next().unwrap_or("").chars().collect();
+    let baseline = match lines.next().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        Some(line) => line
+            .parse::<usize>()
+            .map_err(|_| ConversionError::Baseline)?,
+        None => height.saturating_sub(1),
+    };
+    if height == 0 || height > u8::MAX as usize || baseline >= height {
+        return Err(ConversionError::Baseline.into());
+    }
+
+    let markers: Vec<usize> = (0..width).filter(|x| pixels[*x] == 0).collect();
+    if markers.first() != Some(&0) {
+        return Err(ConversionError::NoFirstMarker.into());
+    }
+    if markers.len() != characters.len() {
+        return Err(ConversionError::GlyphCount(markers.len(), characters.len()).into());
+    }
+
+    let mut glyphs: Vec<(char, usize, Vec<u8>)> = Vec::with_capacity(characters.len());
+    for (index, character) in characters.iter().enumerate() {
+        let start = markers[index];
+        let end = markers.get(index + 1).copied().unwrap_or(width);
+        let glyph_width = end - start;
+        if glyph_width > u8::MAX as usize {
+            return Err(ConversionError::GlyphTooWide(*character).into());
+        }
+        let glyph_pixels = glyph_width * height;
+        let mut bitmap = vec![0xFF_u8; glyph_pixels.div_ceil(8)];
+        for pixel in 0..glyph_pixels {
+            let x = start + pixel % glyph_width;
+            let y = 1 + pixel / glyph_width;
+            bitmap[pixel / 8].set_bit(pixel % 8, pixels[y * width + x] > 0);
+        }
+        glyphs.push((*character, glyph_width, bitmap));
+    }
+    glyphs.sort_by_key(|(character, _, _)| *character);
+    if let Some(pair) = glyphs.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        return Err(ConversionError::DuplicateCharacter(pair[0].0).into());
+    }
+
+    let mut font: Vec<u8> = vec![height as u8, baseline as u8];
+    font.write_u16::<LittleEndian>(glyphs.len() as u16)?;
+    let mut bitmaps: Vec<u8> = Vec::new();
+    for (character, glyph_width, bitmap) in &glyphs {
+        if bitmaps.len() > u16::MAX as usize {
+            return Err(ConversionError::FontTooLarge.into());
+        }
+        font.write_u32::<LittleEndian>(*character as u32)?;
+        font.push(*glyph_width as u8);
+        font.write_u16::<LittleEndian>(bitmaps.len() as u16)?;
+        bitmaps.extend_from_slice(bitmap);
+    }
+    font.extend_from_slice(&bitmaps);
+    if font.len() > u16::MAX as usize {
+        return Err(ConversionError::FontTooLarge.into());
+    }
+    Ok(Font {
+        data: font,
+        glyphs: glyphs.len(),
+        height,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const B: u8 = 0;
+    const W: u8 = 255;
+
+    fn error(result: Result<Font>) -> ConversionError {
+        result
+            .err()
+            .and_then(|e| e.downcast::<ConversionError>().ok())
+            .unwrap()
+    }
+
+    /// Two glyphs, 'b' is 3 pixels wide and listed first, 'a' is 2 pixels wide
+    #[rustfmt::skip]
+    const SHEET: [u8; 15] = [
+        B, W, W, B, W,
+        B, W, B, W, B,
+        W, B, W, B, W,
+    ];
+
+    #[test]
+    fn glyphs() {
+        let font = encode_font(&SHEET, 5, "ba").unwrap();
+        assert_eq!(font.glyphs, 2);
+        assert_eq!(font.height, 2);
+        // Height, default baseline on the last row, glyph count
+        assert_eq!(font.data[0..4], [2, 1, 2, 0]);
+        // Table and bitmaps are sorted by the character
+        assert_eq!(font.data[4..11], [b'a', 0, 0, 0, 2, 0, 0]);
+        assert_eq!(font.data[11..18], [b'b', 0, 0, 0, 3, 1, 0]);
+        // 'a' pixels: W B / B W, first pixel is the lowest bit, padding is white
+        assert_eq!(font.data[18], 0b1111_1001);
+        // 'b' pixels: B W B / W B W
+        assert_eq!(font.data[19], 0b1110_1010);
+        assert_eq!(font.data.len(), 20);
+    }
+
+    #[test]
+    fn baseline() {
+        assert_eq!(encode_font(&SHEET, 5, "ba\n0").unwrap().data[1], 0);
+        assert_eq!(encode_font(&SHEET, 5, "ba\n \n").unwrap().data[1], 1);
+        assert!(matches!(
+            error(encode_font(&SHEET, 5, "ba\n2")),
+            ConversionError::Baseline
+        ));
+        assert!(matches!(
+            error(encode_font(&SHEET, 5, "ba\nx")),
+            ConversionError::Baseline
+        ));
+        // Marker row only, no glyph rows
+        assert!(matches!(
+            error(encode_font(&SHEET[0..5], 5, "ba")),
+            ConversionError::Baseline
+        ));
+    }
+
+    #[test]
+    fn markers() {
+        assert!(matches!(
+            error(encode_font(&SHEET, 5, "b")),
+            ConversionError::GlyphCount(2, 1)
+        ));
+        assert!(matches!(
+            error(encode_font(&SHEET, 5, "abc")),
+            ConversionError::GlyphCount(2, 3)
+        ));
+        let mut sheet = SHEET;
+        sheet[0] = W;
+        assert!(matches!(
+            error(encode_font(&sheet, 5, "a")),
+            ConversionError::NoFirstMarker
+        ));
+    }
+
+    #[test]
+    fn duplicate_character() {
+        assert!(matches!(
+            error(encode_font(&SHEET, 5, "aa")),
+            ConversionError::DuplicateCharacter('a')
+        ));
+    }
+
+    #[test]
+    fn glyph_width_limit() {
+        let width = 257;
+        let mut sheet = vec![W; width * 2];
+        sheet[0] = B;
+        sheet[255] = B;
+        let font = encode_font(&sheet, width, "ab").unwrap();
+        assert_eq!(font.data[8], 255);
+        assert_eq!(font.data[15], 2);
+        sheet[255] = W;
+        assert!(matches!(
+            error(encode_font(&sheet, width, "a")),
+            ConversionError::GlyphTooWide('a')
+        ));
+    }
+}
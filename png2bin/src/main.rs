@@ -4,7 +4,28 @@
 //! Converts PNG images into WallCalendar image format
 //!
 //! Usage:
-//! `png2bin <dir>` - will conert each _png_ file in _dir_ into bin file
+//! `png2bin [options] <dir>` - will conert each _png_ file in _dir_ into bin file, glyph sheets are
+//! converted into fonts
+//!
+//! Images could be grayscale, RGB or palette based, with or without alpha channel,
+//! they are reduced to luminance and binarized with one of the methods:
+//! * `-b threshold` - pixels brighter than `-t <0-255>` are white, default threshold 0
+//!   keeps only pure black pixels black
+//! * `-b otsu` - threshold is selected for every image automatically
+//! * `-b bayer` - ordered dithering
+//! * `-b floyd-steinberg` - error diffusion dithering, best for the photos
+//!
+//! `-g <gamma>` and `-c <contrast>` adjust luminance before the binarization,
+//! gamma above 1 brightens mid tones, contrast above 1 makes the image more contrast.
+//!
+//! Tri-color pages:
+//! With `--tricolor` RGB and palette images are split into two planes, _name_`-black.bin`
+//! and _name_`-red.bin`, so `01-01-a.png` becomes the `01-01-a-black.bin` and `01-01-a-red.bin`
+//! pair. Pixel is red, when its hue is in the `--red-hue <from,to>` range of degrees (`290,50` by default,
+//! range wraps around 0), its saturation is at least `--red-saturation` (100 by default) and
+//! its value is at least `--red-value` (20 by default). Saturation and value are in 0-255 range.
+//! Red pixels are white on the black plane, the rest of the image is binarized as usual.
+//! Grayscale images are converted into a single plane.
 //!
 //! Format specification:
 //! * First two bytes - length of the encoded data in bytes, little endian
@@ -12,23 +33,49 @@
 //!
 //! Image is scanned from left to right, top to bottom. Each pixel
 //! is represented as a bit in a data stream and will be set to 0 for
-//! black pixel and 1 for any other color. In case amount of pixels is not
+//! black pixel and 1 for any other color, red plane has 0 for red pixel
+//! and 1 for any other color. In case amount of pixels is not
 //! dividable by 8, missing pixels will be stuffed with value 1. Resulting
 //! bitstream will be compressed using LSZZ algorithm.
 //!
 //! Pay attention, that we do not store image dimension, as they are standardized
 //! and are well known by the firmware.
+//!
+//! Fonts:
+//! PNG file with a _txt_ file of the same name is treated as a glyph sheet and
+//! converted into a font. Glyphs are placed on a sheet from left to right, the top row
+//! of the sheet is a marker row, where a black pixel marks the first column of each glyph,
+//! so glyphs may have different widths. First line of the _txt_ file lists glyphs characters
+//! in the same order as they are placed on the sheet, optional second line is a baseline
+//! row, counted from the top of glyph, by default it is the last row of the glyph.
+//!
+//! Font format specification:
+//! * First two bytes - length of the encoded data in bytes, little endian
+//! * glyph height and baseline, one byte each
+//! * u16 LE amount of glyphs
+//! * glyphs table, sorted by character. Each entry is u32 LE unicode code point,
+//!   glyph width in one byte and u16 LE offset of the glyph bitmap
+//! * glyphs bitmaps, uncompressed, encoded the same way as images, each bitmap
+//!   starts on a byte boundary.
+
+mod binarize;
+mod font;
+mod tricolor;
 
 use anyhow::{Context, Result};
+use binarize::{adjust, binarize, to_luminance, Binarization};
 use bit_field::BitField;
 use byteorder::{LittleEndian, WriteBytesExt};
 use clap::Parser;
+use font::encode_font;
 use humansize::{file_size_opts as options, FileSize};
 use lzss::{Lzss, SliceReader, VecWriter};
-use png::{BitDepth, ColorType, OutputInfo};
+use png::{BitDepth, ColorType, OutputInfo, Transformations};
 use std::fs::{DirEntry, File, OpenOptions};
 use std::io::Write;
+use std::path::PathBuf;
 use thiserror::Error;
+use tricolor::{split_planes, to_rgb, HueRange, RedClassifier};
 
 #[macro_use]
 extern crate log;
@@ -39,10 +86,30 @@ enum ConversionError {
     UnprocessablePath,
     #[error("File is not a Grayscale image")]
     NotGrayscale,
+    #[error("File has unsupported color type")]
+    UnsupportedColor,
+    #[error("Binarization should be one of threshold, otsu, bayer or floyd-steinberg")]
+    Binarization,
+    #[error("Gamma should be positive and contrast should not be negative")]
+    Adjustment,
+    #[error("Red hue should be from,to in degrees, each in 0-359")]
+    HueRange,
     #[error("File is not a 8-bit image")]
     NotEightBit,
     #[error("File's width is not divisible by 8")]
     NotEightPixels,
+    #[error("Glyph sheet has {0} glyphs, while {1} characters are listed")]
+    GlyphCount(usize, usize),
+    #[error("Glyph sheet marker row does not start with a marker")]
+    NoFirstMarker,
+    #[error("Character '{0}' is listed twice")]
+    DuplicateCharacter(char),
+    #[error("Glyph '{0}' is wider than 255 pixels")]
+    GlyphTooWide(char),
+    #[error("Baseline is malformed or out of the glyph")]
+    Baseline,
+    #[error("Font is larger than 64KiB")]
+    FontTooLarge,
 }
 
 #[derive(Parser)]
@@ -50,16 +117,36 @@ enum ConversionError {
 struct Opts {
     // Input directory
     input: String,
+    /// Binarization method: threshold, otsu, bayer or floyd-steinberg
+    #[clap(short, long, default_value = "threshold")]
+    binarization: Binarization,
+    /// Luminance threshold of the threshold binarization, brighter pixels are white
+    #[clap(short, long, default_value = "0")]
+    threshold: u8,
+    /// Gamma correction, applied before the binarization
+    #[clap(short, long, default_value = "1.0")]
+    gamma: f64,
+    /// Contrast adjustment, applied before the binarization
+    #[clap(short, long, default_value = "1.0")]
+    contrast: f64,
+    /// Split color images into the black and the red planes
+    #[clap(short = 'r', long)]
+    tricolor: bool,
+    /// Hue range of the red color in degrees, as from,to, wraps around 0
+    #[clap(long, default_value = "290,50")]
+    red_hue: HueRange,
+    /// Minimal saturation of the red color, 0-255
+    #[clap(long, default_value = "100")]
+    red_saturation: u8,
+    /// Minimal value of the red color, 0-255
+    #[clap(long, default_value = "20")]
+    red_value: u8,
 }
 
 type MyLzss = Lzss<10, 4, 0x20, { 1 << 10 }, { 2 << 10 }>;
 
 fn validate_image(image: &OutputInfo) -> Result<bool> {
-    if image.color_type != ColorType::Grayscale {
-        Err(ConversionError::NotGrayscale.into())
-    } else if image.bit_depth != BitDepth::Eight {
-        Err(ConversionError::NotEightBit.into())
-    } else if image.width % 8 != 0 {
+    if !image.width.is_multiple_of(8) {
         Err(ConversionError::NotEightPixels.into())
     } else {
         Ok(true)
@@ -68,7 +155,7 @@ fn validate_image(image: &OutputInfo) -> Result<bool> {
 
 fn allocate_bitstream(image: &OutputInfo) -> Vec<u8> {
     let image_size = image.width * image.height;
-    let bitstream_size = if image_size % 8 == 0 {
+    let bitstream_size = if image_size.is_multiple_of(8) {
         (image_size / 8) as usize
     } else {
         (image_size / 8 + 1) as usize
@@ -87,9 +174,34 @@ fn read_png(input: &DirEntry) -> Result<(Vec<u8>, OutputInfo)> {
     Ok((buf, image))
 }
 
-fn write_bin(input: &DirEntry, data: &[u8]) -> Result<()> {
+/// Reads image of any color type and bit depth as 8-bit grayscale or RGB, with or without alpha
+fn read_expanded(input: &DirEntry) -> Result<(Vec<u8>, OutputInfo)> {
+    let mut decoder = png::Decoder::new(File::open(input.path())?);
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let image = reader.next_frame(&mut buf)?;
+    buf.truncate(image.buffer_size());
+    Ok((buf, image))
+}
+
+/// Output file name, plane name is appended to the name of the multi plane images
+fn bin_filename(input: &DirEntry, plane: Option<&str>) -> PathBuf {
     let mut output_filename = input.path();
+    if let Some(plane) = plane {
+        let mut name = output_filename
+            .file_stem()
+            .unwrap_or_default()
+            .to_os_string();
+        name.push("-");
+        name.push(plane);
+        output_filename.set_file_name(name);
+    }
     output_filename.set_extension("bin");
+    output_filename
+}
+
+fn write_bin(output_filename: PathBuf, data: &[u8]) -> Result<()> {
     let mut output = OpenOptions::new()
         .create(true)
         .truncate(true)
@@ -100,7 +212,21 @@ fn write_bin(input: &DirEntry, data: &[u8]) -> Result<()> {
     output.flush().context("Bin output")
 }
 
-fn compress_image(input: DirEntry) -> Result<()> {
+/// Packs pixels into the bitstream, white pixels are 1, and compresses it
+fn compress_plane(image: &OutputInfo, white: &[bool]) -> Result<Vec<u8>> {
+    let mut bitstream = allocate_bitstream(image);
+
+    for (byte_no, byte) in bitstream.iter_mut().enumerate() {
+        for bit in 0..8 {
+            let image_index = byte_no * 8 + bit;
+            byte.set_bit(bit, white[image_index]);
+        }
+    }
+    let compressed = VecWriter::with_capacity(32_768);
+    Ok(MyLzss::compress(SliceReader::new(&bitstream), compressed)?)
+}
+
+fn compress_image(input: DirEntry, opts: &Opts) -> Result<()> {
     let basename = input
         .path()
         .file_name()
@@ -108,23 +234,71 @@ fn compress_image(input: DirEntry) -> Result<()> {
         .map(|s| s.to_owned())
         .ok_or(ConversionError::UnprocessablePath)?;
 
-    let (bytes, image) = read_png(&input)?;
+    let (pixels, image) = read_expanded(&input)?;
 
     validate_image(&image)?;
-    let mut bitstream = allocate_bitstream(&image);
-
-    for (byte_no, byte) in bitstream.iter_mut().enumerate() {
-        for bit in 0..8 {
-            let image_index = byte_no * 8 + bit;
-            byte.set_bit(bit, bytes[image_index] > 0);
+    let rgb = if opts.tricolor {
+        to_rgb(&pixels, image.color_type)
+    } else {
+        None
+    };
+    let planes = match rgb {
+        Some(rgb) => {
+            let classifier = RedClassifier {
+                hue: opts.red_hue,
+                saturation: opts.red_saturation,
+                value: opts.red_value,
+            };
+            let (black, red) = split_planes(
+                &rgb,
+                &classifier,
+                |luminance| adjust(luminance, opts.gamma, opts.contrast),
+                |luminance| {
+                    binarize(
+                        luminance,
+                        image.width as usize,
+                        opts.binarization,
+                        opts.threshold,
+                    )
+                },
+            );
+            vec![
+                (Some("black"), compress_plane(&image, &black)?),
+                (Some("red"), compress_plane(&image, &red)?),
+            ]
         }
-    }
-    let compressed = VecWriter::with_capacity(32_768);
-    let compressed_bytes = MyLzss::compress(SliceReader::new(&bitstream), compressed)?;
+        None => {
+            let mut luminance =
+                to_luminance(&pixels, image.color_type).ok_or(ConversionError::UnsupportedColor)?;
+            adjust(&mut luminance, opts.gamma, opts.contrast);
+            let white = binarize(
+                &luminance,
+                image.width as usize,
+                opts.binarization,
+                opts.threshold,
+            );
+            vec![(None, compress_plane(&image, &white)?)]
+        }
+    };
 
-    write_bin(&input, &compressed_bytes)?;
+    for (plane, compressed_bytes) in &planes {
+        write_bin(bin_filename(&input, *plane), compressed_bytes)?;
+    }
 
     let file_size = input.path().metadata()?.len();
+    let bin_sizes: Vec<String> = planes
+        .iter()
+        .map(|(plane, compressed_bytes)| {
+            let size = compressed_bytes
+                .len()
+                .file_size(options::CONVENTIONAL)
+                .unwrap_or_else(|_| "Unknown".to_string());
+            match plane {
+                Some(plane) => format!("{} {}", size, plane),
+                None => size,
+            }
+        })
+        .collect();
     info!(
         "{} {}x{}, PNG: {}, BIN: {}",
         basename,
@@ -133,7 +307,47 @@ fn compress_image(input: DirEntry) -> Result<()> {
         file_size
             .file_size(options::CONVENTIONAL)
             .unwrap_or_else(|_| "Unknown".to_string()),
-        compressed_bytes
+        bin_sizes.join(", ")
+    );
+    Ok(())
+}
+
+fn font_characters(input: &DirEntry) -> Option<PathBuf> {
+    let mut characters_filename = input.path();
+    characters_filename.set_extension("txt");
+    if characters_filename.exists() {
+        Some(characters_filename)
+    } else {
+        None
+    }
+}
+
+fn convert_font(input: DirEntry, characters_filename: PathBuf) -> Result<()> {
+    let basename = input
+        .path()
+        .file_name()
+        .and_then(|f| f.to_str())
+        .map(|s| s.to_owned())
+        .ok_or(ConversionError::UnprocessablePath)?;
+
+    let (bytes, image) = read_png(&input)?;
+    if image.color_type != ColorType::Grayscale {
+        return Err(ConversionError::NotGrayscale.into());
+    } else if image.bit_depth != BitDepth::Eight {
+        return Err(ConversionError::NotEightBit.into());
+    }
+    let description = std::fs::read_to_string(&characters_filename)
+        .with_context(|| format!("Failed to read {}", characters_filename.display()))?;
+    let font = encode_font(&bytes, image.width as usize, &description)?;
+
+    write_bin(bin_filename(&input, None), &font.data)?;
+
+    info!(
+        "{} font, {} glyphs, {}px high, BIN: {}",
+        basename,
+        font.glyphs,
+        font.height,
+        font.data
             .len()
             .file_size(options::CONVENTIONAL)
             .unwrap_or_else(|_| "Unknown".to_string())
@@ -141,6 +355,13 @@ fn compress_image(input: DirEntry) -> Result<()> {
     Ok(())
 }
 
+fn convert(input: DirEntry, opts: &Opts) -> Result<()> {
+    match font_characters(&input) {
+        Some(characters_filename) => convert_font(input, characters_filename),
+        None => compress_image(input, opts),
+    }
+}
+
 fn main() {
     if std::env::var("RUST_LOG").is_err() {
         std::env::set_var("RUST_LOG", "info")
@@ -149,8 +370,12 @@ fn main() {
 
     let opts: Opts = Opts::parse();
     info!("Input directory: {}", opts.input);
+    if !(opts.gamma > 0.0 && opts.contrast >= 0.0) {
+        error!("{}", ConversionError::Adjustment);
+        return;
+    }
 
-    std::fs::read_dir(opts.input)
+    std::fs::read_dir(&opts.input)
         .unwrap()
         .filter(|f| {
             f.as_ref()
@@ -163,7 +388,10 @@ fn main() {
                 })
                 .unwrap_or(false)
         })
-        .map(|file| file.context("File access").and_then(compress_image))
+        .map(|file| {
+            file.context("File access")
+                .and_then(|file| convert(file, &opts))
+        })
         .filter(|r| r.is_err())
         .for_each(|e| error!("{}", e.err().unwrap()));
 }
@@ -0,0 +1,217 @@
+//! Separation of the red color
+//!
+//! Tri-color pages are drawn from two planes, the black one and the red one.
+//! Pixels are classified as red by their hue, saturation and value, the same way
+//! as the OpenCV HSV mask did it, all other pixels go to the black plane.
+
+use crate::binarize::{blend_over_white, luma};
+use crate::ConversionError;
+use png::ColorType;
+use std::str::FromStr;
+
+/// Range of hues in degrees, wraps around 0 when `from` is greater than `to`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct HueRange {
+    from: u16,
+    to: u16,
+}
+
+impl HueRange {
+    fn contains(&self, hue: u16) -> bool {
+        if self.from <= self.to {
+            self.from <= hue && hue <= self.to
+        } else {
+            hue >= self.from || hue <= self.to
+        }
+    }
+}
+
+impl FromStr for HueRange {
+    type Err = ConversionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|v| v.trim().parse::<u16>())
+            .collect::<Result<Vec<u16>, _>>()
+            .map_err(|_| ConversionError::HueRange)?;
+        match values.as_slice() {
+            [from, to] if *from < 360 && *to < 360 => Ok(HueRange {
+                from: *from,
+                to: *to,
+            }),
+            _ => Err(ConversionError::HueRange),
+        }
+    }
+}
+
+/// Decides, whether pixel is red
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RedClassifier {
+    /// Hues of the red color
+    pub hue: HueRange,
+    /// Minimal saturation, 0-255
+    pub saturation: u8,
+    /// Minimal value, 0-255
+    pub value: u8,
+}
+
+impl RedClassifier {
+    pub(crate) fn is_red(&self, [r, g, b]: [u8; 3]) -> bool {
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        if max == min {
+            // Gray pixels have no hue
+            return false;
+        }
+        let delta = (max - min) as i32;
+        let (r, g, b) = (r as i32, g as i32, b as i32);
+        let hue = if max as i32 == r {
+            60 * (g - b) / delta
+        } else if max as i32 == g {
+            120 + 60 * (b - r) / delta
+        } else {
+            240 + 60 * (r - g) / delta
+        };
+        let saturation = delta * 255 / max as i32;
+        self.hue.contains(hue.rem_euclid(360) as u16)
+            && saturation >= self.saturation as i32
+            && max >= self.value
+    }
+}
+
+/// Converts 8-bit color pixels into RGB triplets, transparent pixels are blended over the white paper.
+/// Palette images should be expanded to RGB by the decoder.
+pub(crate) fn to_rgb(pixels: &[u8], color_type: ColorType) -> Option<Vec<[u8; 3]>> {
+    match color_type {
+        ColorType::Rgb => Some(pixels.chunks_exact(3).map(|p| [p[0], p[1], p[2]]).collect()),
+        ColorType::Rgba => Some(
+            pixels
+                .chunks_exact(4)
+                .map(|p| {
+                    [
+                        blend_over_white(p[0], p[3]),
+                        blend_over_white(p[1], p[3]),
+                        blend_over_white(p[2], p[3]),
+                    ]
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Splits pixels into the black and the red planes, white pixels are true on both planes,
+/// so red pixels are false on the red plane. Luminance of the black plane is adjusted
+/// and binarized with the supplied functions, red pixels are white on it.
+pub(crate) fn split_planes(
+    rgb: &[[u8; 3]],
+    classifier: &RedClassifier,
+    adjust: impl FnOnce(&mut [u8]),
+    binarize: impl FnOnce(&[u8]) -> Vec<bool>,
+) -> (Vec<bool>, Vec<bool>) {
+    let red: Vec<bool> = rgb.iter().map(|pixel| classifier.is_red(*pixel)).collect();
+    let mut luminance: Vec<u8> = rgb.iter().map(|[r, g, b]| luma(*r, *g, *b)).collect();
+    adjust(&mut luminance);
+    // Red pixels are drawn from the red plane only
+    luminance
+        .iter_mut()
+        .zip(&red)
+        .filter(|(_, red)| **red)
+        .for_each(|(level, _)| *level = u8::MAX);
+    let black = binarize(&luminance);
+    let not_red = red.iter().map(|red| !red).collect();
+    (black, not_red)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binarize::{adjust, binarize, Binarization};
+
+    fn classifier() -> RedClassifier {
+        RedClassifier {
+            hue: "290,50".parse().unwrap(),
+            saturation: 100,
+            value: 20,
+        }
+    }
+
+    #[test]
+    fn hue_range() {
+        let range: HueRange = "290, 50".parse().unwrap();
+        assert!(
+            range.contains(0) && range.contains(290) && range.contains(50) && range.contains(359)
+        );
+        assert!(!range.contains(51) && !range.contains(289) && !range.contains(180));
+        let range: HueRange = "100,140".parse().unwrap();
+        assert!(range.contains(120) && !range.contains(0) && !range.contains(141));
+        for invalid in ["360,10", "10,360", "1,2,3", "10", "", "a,b", "-10,20"] {
+            assert!(invalid.parse::<HueRange>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn red_pixels() {
+        let classifier = classifier();
+        assert!(classifier.is_red([255, 0, 0]));
+        // Both sides of the 0 degrees
+        assert!(classifier.is_red([200, 60, 20]));
+        assert!(classifier.is_red([200, 20, 60]));
+        assert!(classifier.is_red([150, 10, 90]));
+        // Yellow, amber orange at 55 degrees and blue are out of the range
+        assert!(!classifier.is_red([255, 255, 0]));
+        assert!(!classifier.is_red([255, 234, 0]));
+        assert!(!classifier.is_red([0, 0, 255]));
+        // Orange at 30 degrees is red by default, but not with a narrower range
+        assert!(classifier.is_red([255, 128, 0]));
+        let narrow = RedClassifier {
+            hue: "340,20".parse().unwrap(),
+            ..classifier
+        };
+        assert!(!narrow.is_red([255, 128, 0]));
+        assert!(narrow.is_red([255, 0, 40]));
+        // Gray, low saturation and too dark pixels
+        assert!(!classifier.is_red([128, 128, 128]));
+        assert!(!classifier.is_red([0, 0, 0]));
+        assert!(!classifier.is_red([255, 200, 200]));
+        assert!(!classifier.is_red([15, 0, 0]));
+    }
+
+    #[test]
+    fn rgb_conversion() {
+        assert_eq!(
+            to_rgb(&[1, 2, 3, 4, 5, 6], ColorType::Rgb),
+            Some(vec![[1, 2, 3], [4, 5, 6]])
+        );
+        assert_eq!(
+            to_rgb(&[255, 0, 0, 255, 255, 0, 0, 0], ColorType::Rgba),
+            Some(vec![[255, 0, 0], [255, 255, 255]])
+        );
+        assert_eq!(to_rgb(&[0, 255], ColorType::Grayscale), None);
+    }
+
+    #[test]
+    fn planes() {
+        // White, black, red and dark red pixels, two of each
+        let rgb: Vec<[u8; 3]> = [[255, 255, 255], [0, 0, 0], [220, 30, 30], [120, 0, 20]]
+            .iter()
+            .flat_map(|pixel| [*pixel, *pixel])
+            .collect();
+        let (black, red) = split_planes(
+            &rgb,
+            &classifier(),
+            |luminance| adjust(luminance, 1.0, 1.0),
+            |luminance| binarize(luminance, 8, Binarization::Threshold, 128),
+        );
+        // Red pixels are white on the black plane and are 0 bits on the red plane
+        assert_eq!(
+            black,
+            vec![true, true, false, false, true, true, true, true]
+        );
+        assert_eq!(
+            red,
+            vec![true, true, true, true, false, false, false, false]
+        );
+    }
+}
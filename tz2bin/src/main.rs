@@ -0,0 +1,405 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Converts timezone boundaries into WallCalendar timezone map format
+//!
+//! Usage:
+//! `tz2bin --region <min_lon,min_lat,max_lon,max_lat> [--tolerance <degrees>] <input>` - will
+//! convert timezone polygons from the _input_ GeoJSON file into `tzmap.bin`
+//!
+//! Input file is a GeoJSON feature collection from the
+//! [timezone-boundary-builder](https://github.com/evansiroky/timezone-boundary-builder) project,
+//! each feature has a `tzid` property with IANA timezone name and a polygon or multipolygon geometry.
+//! Only polygons, which bounding boxes intersect the region, are converted. Polygons are simplified
+//! with Douglas-Peucker algorithm, tolerance is set in degrees, `0.01` by default.
+//!
+//! Current rules of each timezone are taken from the POSIX TZ string at the end of
+//! the compiled zoneinfo file, so zoneinfo database is needed, `/usr/share/zoneinfo` by default.
+//!
+//! Format specification:
+//! * First two bytes - length of the encoded data in bytes, little endian
+//! * data - timezone map, see `tzmap` crate for details
+
+use anyhow::{Context, Result};
+use byteorder::{LittleEndian, WriteBytesExt};
+use celestial::TimeZone;
+use clap::Parser;
+use geojson::{GeoJson, Geometry, Value};
+use humansize::{file_size_opts as options, FileSize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+use thiserror::Error;
+use tzmap::{encode_polygon, encode_zones, polygon_encoded_size, Point, Zone};
+
+#[macro_use]
+extern crate log;
+
+#[derive(Error, Debug)]
+enum ConversionError {
+    #[error("Region should be min_lon,min_lat,max_lon,max_lat")]
+    Region,
+    #[error("Input is not a GeoJSON feature collection")]
+    NotFeatureCollection,
+    #[error("Zoneinfo file {0} has no POSIX TZ string")]
+    NoPosixString(String),
+    #[error("Timezone map is larger than 64KiB, increase tolerance or reduce region")]
+    TooLarge,
+    #[error("Timezone map can not be encoded")]
+    Encoding,
+}
+
+#[derive(Parser)]
+#[clap(version = "1.0", author = "Denis Chaplygin <akashihi@gmail.com>")]
+struct Opts {
+    /// Input GeoJSON file
+    input: String,
+    /// Region to convert, as min_lon,min_lat,max_lon,max_lat in degrees
+    #[clap(short, long)]
+    region: Region,
+    /// Simplification tolerance in degrees
+    #[clap(short, long, default_value = "0.01")]
+    tolerance: f64,
+    /// Compiled zoneinfo database directory
+    #[clap(short, long, default_value = "/usr/share/zoneinfo")]
+    zoneinfo: String,
+    /// Output file
+    #[clap(short, long, default_value = "tzmap.bin")]
+    output: String,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Region {
+    min: Point,
+    max: Point,
+}
+
+impl FromStr for Region {
+    type Err = ConversionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|v| v.trim().parse::<f64>().map(to_microdegrees))
+            .collect::<Result<Vec<i32>, _>>()
+            .map_err(|_| ConversionError::Region)?;
+        match values.as_slice() {
+            [min_lon, min_lat, max_lon, max_lat] if min_lon < max_lon && min_lat < max_lat => Ok(Region {
+                min: Point::new(*min_lon, *min_lat),
+                max: Point::new(*max_lon, *max_lat),
+            }),
+            _ => Err(ConversionError::Region),
+        }
+    }
+}
+
+impl Region {
+    /// Checks bounding box of the GeoJSON ring, ring is not converted, as it could be huge
+    fn intersects(&self, ring: &[Vec<f64>]) -> bool {
+        let points = ring.iter().filter(|position| position.len() >= 2).map(|position| to_point(position));
+        let (min, max) = points.fold(
+            (Point::new(i32::MAX, i32::MAX), Point::new(i32::MIN, i32::MIN)),
+            |(min, max), point| {
+                (
+                    Point::new(min.lon.min(point.lon), min.lat.min(point.lat)),
+                    Point::new(max.lon.max(point.lon), max.lat.max(point.lat)),
+                )
+            },
+        );
+        min.lon <= self.max.lon && max.lon >= self.min.lon && min.lat <= self.max.lat && max.lat >= self.min.lat
+    }
+}
+
+fn to_microdegrees(degrees: f64) -> i32 {
+    (degrees * 1_000_000.0).round() as i32
+}
+
+/// Converts GeoJSON position, which has at least longitude and latitude
+fn to_point(position: &[f64]) -> Point {
+    Point::new(to_microdegrees(position[0]), to_microdegrees(position[1]))
+}
+
+/// Distance from the point to the segment, in microdegrees
+fn segment_distance(point: Point, a: Point, b: Point) -> f64 {
+    let (px, py) = ((point.lon - a.lon) as f64, (point.lat - a.lat) as f64);
+    let (dx, dy) = ((b.lon - a.lon) as f64, (b.lat - a.lat) as f64);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0.0 { 0.0 } else { ((px * dx + py * dy) / length).clamp(0.0, 1.0) };
+    ((px - t * dx).powi(2) + (py - t * dy).powi(2)).sqrt()
+}
+
+/// Douglas-Peucker polyline simplification, first and last points are always kept
+fn simplify(points: &[Point], tolerance: f64) -> Vec<Point> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    let mut ranges = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = ranges.pop() {
+        let farthest = (first + 1..last)
+            .map(|index| (index, segment_distance(points[index], points[first], points[last])))
+            .fold((first, 0.0), |max, current| if current.1 > max.1 { current } else { max });
+        if farthest.1 > tolerance {
+            keep[farthest.0] = true;
+            ranges.push((first, farthest.0));
+            ranges.push((farthest.0, last));
+        }
+    }
+    points.iter().zip(keep).filter(|(_, keep)| *keep).map(|(point, _)| *point).collect()
+}
+
+/// Converts GeoJSON ring to the simplified ring without closing point
+fn convert_ring(ring: &[Vec<f64>], tolerance: f64) -> Vec<Point> {
+    let points: Vec<Point> = ring
+        .iter()
+        .filter(|position| position.len() >= 2)
+        .map(|position| to_point(position))
+        .collect();
+    let mut simplified = simplify(&points, tolerance);
+    if simplified.len() > 1 && simplified.first() == simplified.last() {
+        simplified.pop();
+    }
+    simplified
+}
+
+/// Converts GeoJSON polygon, holes, which are simplified away, are dropped.
+/// Returns None if the outer ring is simplified away, as holes can not be kept without it
+fn convert_polygon(polygon: &[Vec<Vec<f64>>], tolerance: f64) -> Option<Vec<Vec<Point>>> {
+    let outer = convert_ring(polygon.first()?, tolerance);
+    if outer.len() < 3 {
+        return None;
+    }
+    let holes = polygon[1..].iter().map(|ring| convert_ring(ring, tolerance)).filter(|ring| ring.len() >= 3);
+    Some(std::iter::once(outer).chain(holes).collect())
+}
+
+/// Reads POSIX TZ string, which is the last line of the TZif version 2+ file
+fn posix_string(zoneinfo: &str, name: &str) -> Result<String> {
+    let fname: PathBuf = [zoneinfo, name].iter().collect();
+    let bytes = std::fs::read(&fname).with_context(|| format!("Failed to read {}", fname.display()))?;
+    let footer = bytes
+        .strip_suffix(b"\n")
+        .and_then(|b| b.rsplit(|c| *c == b'\n').next())
+        .and_then(|line| std::str::from_utf8(line).ok())
+        .filter(|line| !line.is_empty())
+        .ok_or_else(|| ConversionError::NoPosixString(name.to_string()))?;
+    Ok(footer.to_string())
+}
+
+fn polygons(geometry: &Geometry) -> Vec<&Vec<Vec<Vec<f64>>>> {
+    match &geometry.value {
+        Value::Polygon(polygon) => vec![polygon],
+        Value::MultiPolygon(polygons) => polygons.iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn convert(opts: &Opts) -> Result<Vec<u8>> {
+    let input = std::fs::read_to_string(&opts.input).with_context(|| format!("Failed to read {}", opts.input))?;
+    let collection = match input.parse::<GeoJson>()? {
+        GeoJson::FeatureCollection(collection) => collection,
+        _ => return Err(ConversionError::NotFeatureCollection.into()),
+    };
+    let tolerance = opts.tolerance * 1_000_000.0;
+
+    let mut zones: Vec<(String, String)> = Vec::new();
+    let mut zone_polygons: Vec<(u16, Vec<Vec<Point>>)> = Vec::new();
+    for feature in &collection.features {
+        let name = match feature.property("tzid").and_then(|v| v.as_str()) {
+            Some(name) => name,
+            None => continue,
+        };
+        let rings: Vec<Vec<Vec<Point>>> = feature
+            .geometry
+            .iter()
+            .flat_map(polygons)
+            .filter(|polygon| polygon.first().map(|outer| opts.region.intersects(outer)).unwrap_or(false))
+            .filter_map(|polygon| convert_polygon(polygon, tolerance))
+            .collect();
+        if rings.is_empty() {
+            continue;
+        }
+        let posix = match posix_string(&opts.zoneinfo, name) {
+            Ok(posix) => posix,
+            Err(e) => {
+                warn!("{}: {:#}, skipping", name, e);
+                continue;
+            }
+        };
+        if TimeZone::parse(&posix).is_none() {
+            warn!("{}: unsupported POSIX TZ string {}, skipping", name, posix);
+            continue;
+        }
+        let index = match zones.iter().position(|(zone, _)| zone == name) {
+            Some(index) => index,
+            None => {
+                zones.push((name.to_string(), posix));
+                zones.len() - 1
+            }
+        } as u16;
+        let count = zone_polygons.len();
+        zone_polygons.extend(rings.into_iter().map(|polygon| (index, polygon)));
+        info!("{}: {} polygons", name, zone_polygons.len() - count);
+    }
+
+    let zones: Vec<Zone> = zones.iter().map(|(name, posix)| Zone { name, posix }).collect();
+    let zones_size = 2 + zones.iter().map(Zone::encoded_size).sum::<usize>();
+    let polygons_size: usize = zone_polygons
+        .iter()
+        .map(|(_, polygon)| polygon_encoded_size(&polygon.iter().map(|ring| ring.as_slice()).collect::<Vec<_>>()))
+        .sum();
+    if zones_size + polygons_size > u16::MAX as usize {
+        return Err(ConversionError::TooLarge.into());
+    }
+    let mut map = vec![0u8; zones_size + polygons_size];
+    let mut size = encode_zones(&zones, &mut map).ok_or(ConversionError::Encoding)?;
+    for (zone, polygon) in &zone_polygons {
+        let rings: Vec<&[Point]> = polygon.iter().map(|ring| ring.as_slice()).collect();
+        size += encode_polygon(*zone, &rings, &mut map[size..]).ok_or(ConversionError::Encoding)?;
+    }
+    info!("{} zones, {} polygons", zones.len(), zone_polygons.len());
+    Ok(map)
+}
+
+fn write_bin(fname: &str, data: &[u8]) -> Result<()> {
+    let mut output = OpenOptions::new().create(true).truncate(true).write(true).open(fname)?;
+    output.write_u16::<LittleEndian>(data.len() as u16)?;
+    output.write_all(data)?;
+    output.flush().context("Bin output")
+}
+
+fn main() {
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var("RUST_LOG", "info")
+    }
+    pretty_env_logger::init();
+
+    let opts: Opts = Opts::parse();
+    info!("Input file: {}", opts.input);
+
+    match convert(&opts).and_then(|map| write_bin(&opts.output, &map).map(|_| map.len())) {
+        Ok(size) => info!("{}, size: {}", opts.output, size.file_size(options::CONVENTIONAL).unwrap_or_else(|_| "Unknown".to_string())),
+        Err(e) => error!("{}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(points: &[(f64, f64)]) -> Vec<Vec<f64>> {
+        points.iter().map(|(lon, lat)| vec![*lon, *lat]).collect()
+    }
+
+    #[test]
+    fn distance_to_segment() {
+        let (a, b) = (Point::new(0, 0), Point::new(10, 0));
+        assert_eq!(segment_distance(Point::new(5, 3), a, b), 3.0);
+        // Beyond the segment ends the distance is to the nearest end
+        assert_eq!(segment_distance(Point::new(13, 4), a, b), 5.0);
+        assert_eq!(segment_distance(Point::new(-3, -4), a, b), 5.0);
+        // Degenerated segment
+        assert_eq!(segment_distance(Point::new(3, 4), a, a), 5.0);
+    }
+
+    #[test]
+    fn simplification() {
+        let points = [Point::new(0, 0), Point::new(5, 1), Point::new(10, 0), Point::new(15, 8), Point::new(20, 0)];
+        assert_eq!(simplify(&points, 0.5).len(), 5);
+        assert_eq!(simplify(&points, 2.0), vec![Point::new(0, 0), Point::new(10, 0), Point::new(15, 8), Point::new(20, 0)]);
+        assert_eq!(simplify(&points, 10.0), vec![Point::new(0, 0), Point::new(20, 0)]);
+        assert_eq!(simplify(&points[0..2], 10.0), points[0..2].to_vec());
+    }
+
+    #[test]
+    fn region() {
+        let region: Region = "20, 59.5,31.5,70".parse().unwrap();
+        assert_eq!(region.min, Point::new(20_000_000, 59_500_000));
+        assert_eq!(region.max, Point::new(31_500_000, 70_000_000));
+        assert!("20,59,31".parse::<Region>().is_err());
+        assert!("31,59,20,70".parse::<Region>().is_err());
+        assert!("20,59,31,x".parse::<Region>().is_err());
+
+        assert!(region.intersects(&ring(&[(10.0, 60.0), (25.0, 60.0), (25.0, 65.0)])));
+        assert!(region.intersects(&ring(&[(0.0, 0.0), (40.0, 80.0)])));
+        assert!(!region.intersects(&ring(&[(10.0, 60.0), (15.0, 60.0), (15.0, 65.0)])));
+        assert!(!region.intersects(&ring(&[(25.0, 71.0), (26.0, 72.0), (25.0, 72.0)])));
+        assert!(!region.intersects(&[]));
+    }
+
+    #[test]
+    fn ring_conversion() {
+        let square = ring(&[(20.0, 59.0), (31.0, 59.0), (31.0, 70.0), (20.0, 70.0), (20.0, 59.0)]);
+        assert_eq!(
+            convert_ring(&square, 0.0),
+            vec![
+                Point::new(20_000_000, 59_000_000),
+                Point::new(31_000_000, 59_000_000),
+                Point::new(31_000_000, 70_000_000),
+                Point::new(20_000_000, 70_000_000),
+            ]
+        );
+        // Open ring is kept as is
+        assert_eq!(convert_ring(&square[0..4], 0.0).len(), 4);
+        // Altitude is ignored, positions without latitude are skipped
+        assert_eq!(convert_ring(&[vec![24.5, 60.25, 10.0], vec![25.0]], 0.0), vec![Point::new(24_500_000, 60_250_000)]);
+    }
+
+    #[test]
+    fn polygon_conversion() {
+        let outer = ring(&[(20.0, 59.0), (31.0, 59.0), (31.0, 70.0), (20.0, 70.0), (20.0, 59.0)]);
+        let hole = ring(&[(24.0, 60.0), (24.001, 60.0), (24.001, 60.001), (24.0, 60.0)]);
+        let polygon = convert_polygon(&[outer.clone(), hole.clone()], 0.0).unwrap();
+        assert_eq!(polygon.len(), 2);
+        // Small hole is simplified away, outer ring is kept
+        assert_eq!(convert_polygon(&[outer, hole.clone()], 10_000.0).unwrap().len(), 1);
+        // Holes are not kept without the outer ring
+        let small = ring(&[(20.0, 59.0), (20.001, 59.0), (20.001, 59.001), (20.0, 59.0)]);
+        assert_eq!(convert_polygon(&[small, hole], 10_000.0), None);
+        assert_eq!(convert_polygon(&[], 0.0), None);
+    }
+
+    #[test]
+    fn posix_footer() {
+        let zoneinfo = std::env::temp_dir().join(format!("tz2bin-test-{}", std::process::id()));
+        std::fs::create_dir_all(&zoneinfo).unwrap();
+        std::fs::write(zoneinfo.join("Helsinki"), b"TZif2\0\x01\xff\nEET-2EEST,M3.5.0/3,M10.5.0/4\n").unwrap();
+        std::fs::write(zoneinfo.join("Empty"), b"TZif2\0\x01\xff\n\n").unwrap();
+        std::fs::write(zoneinfo.join("Unterminated"), b"TZif2\0\x01\xff\nUTC0").unwrap();
+        let dir = zoneinfo.to_str().unwrap();
+
+        assert_eq!(posix_string(dir, "Helsinki").unwrap(), "EET-2EEST,M3.5.0/3,M10.5.0/4");
+        assert!(posix_string(dir, "Empty").is_err());
+        assert!(posix_string(dir, "Unterminated").is_err());
+        assert!(posix_string(dir, "Missing").is_err());
+        std::fs::remove_dir_all(&zoneinfo).unwrap();
+    }
+
+    #[test]
+    fn missing_zone_file() {
+        let dir = std::env::temp_dir().join(format!("tz2bin-convert-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Helsinki"), b"TZif2\0\nEET-2EEST,M3.5.0/3,M10.5.0/4\n").unwrap();
+        let square = "[[[24,60],[26,60],[26,62],[24,62],[24,60]]]";
+        let feature = |name: &str| {
+            format!(r#"{{"type":"Feature","properties":{{"tzid":"{}"}},"geometry":{{"type":"Polygon","coordinates":{}}}}}"#, name, square)
+        };
+        let input = dir.join("zones.json");
+        std::fs::write(&input, format!(r#"{{"type":"FeatureCollection","features":[{},{}]}}"#, feature("Nowhere"), feature("Helsinki"))).unwrap();
+        let opts = Opts {
+            input: input.to_string_lossy().into_owned(),
+            region: "20,55,30,65".parse().unwrap(),
+            tolerance: 0.01,
+            zoneinfo: dir.to_string_lossy().into_owned(),
+            output: String::new(),
+        };
+        let map = convert(&opts).unwrap();
+        // Only the zone with the zone file is written
+        assert_eq!(map[0..3], [1, 0, 8]);
+        assert_eq!(&map[3..11], b"Helsinki");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,225 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+#![cfg_attr(not(test), no_std)]
+
+//! Timezone lookup by the geographic position
+//!
+//! * compact binary map of timezone polygons, produced by `tz2bin`
+//! * integer point-in-polygon lookup, coordinates are in microdegrees
+//!
+//! Map format, all numbers are little endian:
+//! * u16 amount of zones
+//! * zones, each is u8 length and IANA name, followed by u8 length and POSIX TZ string
+//! * polygons till the end of the map, each is u16 zone index, bounding box as four i32
+//!   (minimal longitude, minimal latitude, maximal longitude, maximal latitude),
+//!   u16 amount of rings and the rings. Ring is u16 amount of vertices, followed by
+//!   vertices as i32 longitude and i32 latitude. First ring is the outer boundary,
+//!   other rings are holes.
+
+mod polygon;
+
+pub use polygon::Polygon as Polygon;
+pub use polygon::Polygons as Polygons;
+pub use polygon::encode_polygon as encode_polygon;
+pub use polygon::polygon_encoded_size as polygon_encoded_size;
+
+/// Size of the polygon record without rings
+pub const POLYGON_HEADER_SIZE: usize = 20;
+
+/// Geographic position in microdegrees, east and north are positive
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Point {
+    /// Longitude
+    pub lon: i32,
+    /// Latitude
+    pub lat: i32,
+}
+
+impl Point {
+    /// Creates a point from the longitude and latitude in microdegrees
+    pub const fn new(lon: i32, lat: i32) -> Self {
+        Point { lon, lat }
+    }
+}
+
+/// Timezone of the map
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Zone<'a> {
+    /// IANA timezone name, like `Europe/Helsinki`
+    pub name: &'a str,
+    /// POSIX TZ string with current rules of the timezone
+    pub posix: &'a str,
+}
+
+impl<'a> Zone<'a> {
+    /// Size of the binary record of the zone
+    pub fn encoded_size(&self) -> usize {
+        2 + self.name.len() + self.posix.len()
+    }
+
+    fn decode(bytes: &'a [u8]) -> Option<(Self, usize)> {
+        let name_end = 1 + *bytes.first()? as usize;
+        let name = core::str::from_utf8(bytes.get(1..name_end)?).ok()?;
+        let posix_end = name_end + 1 + *bytes.get(name_end)? as usize;
+        let posix = core::str::from_utf8(bytes.get(name_end + 1..posix_end)?).ok()?;
+        Some((Zone { name, posix }, posix_end))
+    }
+}
+
+/// Writes zones list to the buffer
+///
+/// Returns number of bytes written or None if buffer is too small or
+/// some name is longer than 255 bytes
+pub fn encode_zones(zones: &[Zone], buffer: &mut [u8]) -> Option<usize> {
+    let size = 2 + zones.iter().map(Zone::encoded_size).sum::<usize>();
+    if buffer.len() < size || zones.len() > u16::MAX as usize {
+        return None;
+    }
+    buffer[0..2].copy_from_slice(&(zones.len() as u16).to_le_bytes());
+    let mut position = 2;
+    for zone in zones {
+        for text in [zone.name, zone.posix] {
+            if text.len() > u8::MAX as usize {
+                return None;
+            }
+            buffer[position] = text.len() as u8;
+            buffer[position + 1..position + 1 + text.len()].copy_from_slice(text.as_bytes());
+            position += 1 + text.len();
+        }
+    }
+    Some(size)
+}
+
+/// Timezone map, stored in the binary format
+#[derive(Clone, Copy, Debug)]
+pub struct TimeZoneMap<'a> {
+    zones: &'a [u8],
+    zones_count: u16,
+    polygons: &'a [u8],
+}
+
+impl<'a> TimeZoneMap<'a> {
+    /// Reads map header, returns None if zones list is malformed
+    pub fn new(bytes: &'a [u8]) -> Option<Self> {
+        let zones_count = u16::from_le_bytes([*bytes.first()?, *bytes.get(1)?]);
+        let mut position = 2;
+        for _ in 0..zones_count {
+            let (_, size) = Zone::decode(bytes.get(position..)?)?;
+            position += size;
+        }
+        Some(TimeZoneMap {
+            zones: &bytes[2..position],
+            zones_count,
+            polygons: &bytes[position..],
+        })
+    }
+
+    /// Amount of zones in the map
+    pub fn zones_count(&self) -> u16 {
+        self.zones_count
+    }
+
+    /// Zone by its index in the map
+    pub fn zone(&self, index: u16) -> Option<Zone<'a>> {
+        if index >= self.zones_count {
+            return None;
+        }
+        let mut position = 0;
+        for _ in 0..index {
+            position += Zone::decode(&self.zones[position..])?.1;
+        }
+        Zone::decode(&self.zones[position..]).map(|(zone, _)| zone)
+    }
+
+    /// Iterator over the map polygons
+    pub fn polygons(&self) -> Polygons<'a> {
+        Polygons::new(self.polygons)
+    }
+
+    /// Finds index of the zone, which contains the point
+    pub fn lookup(&self, point: Point) -> Option<u16> {
+        self.polygons()
+            .find(|polygon| polygon.contains(point))
+            .map(|polygon| polygon.zone())
+            .filter(|zone| *zone < self.zones_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(lon: i32, lat: i32, size: i32) -> [Point; 4] {
+        [
+            Point::new(lon, lat),
+            Point::new(lon + size, lat),
+            Point::new(lon + size, lat + size),
+            Point::new(lon, lat + size),
+        ]
+    }
+
+    fn test_map(buffer: &mut [u8]) -> usize {
+        let zones = [
+            Zone { name: "Europe/Helsinki", posix: "EET-2EEST,M3.5.0/3,M10.5.0/4" },
+            Zone { name: "Europe/Stockholm", posix: "CET-1CEST,M3.5.0,M10.5.0/3" },
+        ];
+        let mut size = encode_zones(&zones, buffer).unwrap();
+        // Helsinki is a 10x10 degrees square with a 2x2 Stockholm enclave in the middle
+        let outer = square(20_000_000, 55_000_000, 10_000_000);
+        let hole = square(27_000_000, 56_000_000, 2_000_000);
+        size += encode_polygon(0, &[&outer, &hole], &mut buffer[size..]).unwrap();
+        size += encode_polygon(1, &[&hole], &mut buffer[size..]).unwrap();
+        // Stockholm is a triangle to the west
+        let triangle = [Point::new(10_000_000, 55_000_000), Point::new(19_000_000, 55_000_000), Point::new(19_000_000, 69_000_000)];
+        size += encode_polygon(1, &[&triangle], &mut buffer[size..]).unwrap();
+        size
+    }
+
+    #[test]
+    fn zones() {
+        let mut buffer = [0u8; 512];
+        let size = test_map(&mut buffer);
+        let map = TimeZoneMap::new(&buffer[..size]).unwrap();
+        assert_eq!(map.zones_count(), 2);
+        assert_eq!(map.zone(1).unwrap().name, "Europe/Stockholm");
+        assert_eq!(map.zone(0).unwrap().posix, "EET-2EEST,M3.5.0/3,M10.5.0/4");
+        assert_eq!(map.zone(2), None);
+        assert_eq!(map.polygons().count(), 3);
+    }
+
+    #[test]
+    fn lookup() {
+        let mut buffer = [0u8; 512];
+        let size = test_map(&mut buffer);
+        let map = TimeZoneMap::new(&buffer[..size]).unwrap();
+        // Helsinki
+        assert_eq!(map.lookup(Point::new(24_941_025, 60_173_324)), Some(0));
+        // Enclave
+        assert_eq!(map.lookup(Point::new(28_000_000, 57_000_000)), Some(1));
+        // Stockholm
+        assert_eq!(map.lookup(Point::new(18_068_581, 59_329_323)), Some(1));
+        // Above the triangle diagonal
+        assert_eq!(map.lookup(Point::new(11_000_000, 68_000_000)), None);
+        // Southern and western hemispheres
+        assert_eq!(map.lookup(Point::new(-74_006_000, 40_712_800)), None);
+        assert_eq!(map.lookup(Point::new(151_209_300, -33_868_800)), None);
+    }
+
+    #[test]
+    fn malformed_maps() {
+        assert!(TimeZoneMap::new(&[]).is_none());
+        // Zone name is truncated
+        assert!(TimeZoneMap::new(&[1, 0, 5, b'U', b'T']).is_none());
+        // Zone without polygons
+        let map = TimeZoneMap::new(&[1, 0, 3, b'U', b'T', b'C', 4, b'U', b'T', b'C', b'0']).unwrap();
+        assert_eq!(map.zone(0).unwrap().posix, "UTC0");
+        assert_eq!(map.lookup(Point::new(0, 0)), None);
+    }
+
+    #[test]
+    fn small_buffer() {
+        let zones = [Zone { name: "Europe/Helsinki", posix: "EET-2EEST,M3.5.0/3,M10.5.0/4" }];
+        let mut buffer = [0u8; 16];
+        assert_eq!(encode_zones(&zones, &mut buffer), None);
+    }
+}
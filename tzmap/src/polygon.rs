@@ -0,0 +1,199 @@
+#![deny(missing_docs)]
+#![deny(unsafe_code)]
+
+//! Timezone polygons and point-in-polygon test
+//!
+//! Test uses even-odd rule over all rings of the polygon, so holes
+//! are handled without any special care. All the math is done in integers.
+
+use crate::{Point, POLYGON_HEADER_SIZE};
+
+fn read_i32(bytes: &[u8], position: usize) -> i32 {
+    i32::from_le_bytes([bytes[position], bytes[position + 1], bytes[position + 2], bytes[position + 3]])
+}
+
+fn read_point(bytes: &[u8], position: usize) -> Point {
+    Point::new(read_i32(bytes, position), read_i32(bytes, position + 4))
+}
+
+/// Size of the binary record of the polygon
+pub fn polygon_encoded_size(rings: &[&[Point]]) -> usize {
+    POLYGON_HEADER_SIZE + rings.iter().map(|ring| 2 + ring.len() * 8).sum::<usize>()
+}
+
+/// Writes polygon record to the buffer
+///
+/// Returns number of bytes written or None if buffer is too small,
+/// there are no rings or there are more than 65535 rings or vertices in a ring
+pub fn encode_polygon(zone: u16, rings: &[&[Point]], buffer: &mut [u8]) -> Option<usize> {
+    let size = polygon_encoded_size(rings);
+    let outer = rings.first()?;
+    if buffer.len() < size || rings.len() > u16::MAX as usize || outer.is_empty() {
+        return None;
+    }
+    let min_lon = outer.iter().map(|p| p.lon).min()?;
+    let min_lat = outer.iter().map(|p| p.lat).min()?;
+    let max_lon = outer.iter().map(|p| p.lon).max()?;
+    let max_lat = outer.iter().map(|p| p.lat).max()?;
+    buffer[0..2].copy_from_slice(&zone.to_le_bytes());
+    for (index, value) in [min_lon, min_lat, max_lon, max_lat].iter().enumerate() {
+        buffer[2 + index * 4..6 + index * 4].copy_from_slice(&value.to_le_bytes());
+    }
+    buffer[18..20].copy_from_slice(&(rings.len() as u16).to_le_bytes());
+    let mut position = POLYGON_HEADER_SIZE;
+    for ring in rings {
+        if ring.len() > u16::MAX as usize {
+            return None;
+        }
+        buffer[position..position + 2].copy_from_slice(&(ring.len() as u16).to_le_bytes());
+        position += 2;
+        for point in ring.iter() {
+            buffer[position..position + 4].copy_from_slice(&point.lon.to_le_bytes());
+            buffer[position + 4..position + 8].copy_from_slice(&point.lat.to_le_bytes());
+            position += 8;
+        }
+    }
+    Some(size)
+}
+
+/// Polygon of the timezone, stored in the binary format
+#[derive(Clone, Copy, Debug)]
+pub struct Polygon<'a> {
+    zone: u16,
+    min: Point,
+    max: Point,
+    rings: &'a [u8],
+}
+
+impl<'a> Polygon<'a> {
+    /// Reads a polygon from the beginning of the binary record
+    ///
+    /// Returns the polygon and the record size
+    pub fn decode(bytes: &'a [u8]) -> Option<(Self, usize)> {
+        let header = bytes.get(0..POLYGON_HEADER_SIZE)?;
+        let rings_count = u16::from_le_bytes([header[18], header[19]]);
+        let mut size = POLYGON_HEADER_SIZE;
+        for _ in 0..rings_count {
+            let vertices = u16::from_le_bytes([*bytes.get(size)?, *bytes.get(size + 1)?]) as usize;
+            size += 2 + vertices * 8;
+        }
+        let polygon = Polygon {
+            zone: u16::from_le_bytes([header[0], header[1]]),
+            min: read_point(header, 2),
+            max: read_point(header, 10),
+            rings: bytes.get(POLYGON_HEADER_SIZE..size)?,
+        };
+        Some((polygon, size))
+    }
+
+    /// Index of the zone of the polygon
+    pub fn zone(&self) -> u16 {
+        self.zone
+    }
+
+    /// Checks if the point is inside the polygon, points on the boundary may go either way
+    pub fn contains(&self, point: Point) -> bool {
+        if point.lon < self.min.lon || point.lon > self.max.lon || point.lat < self.min.lat || point.lat > self.max.lat {
+            return false;
+        }
+        let mut inside = false;
+        let mut position = 0;
+        while position < self.rings.len() {
+            let vertices = u16::from_le_bytes([self.rings[position], self.rings[position + 1]]) as usize;
+            let ring = &self.rings[position + 2..position + 2 + vertices * 8];
+            position += 2 + vertices * 8;
+            if vertices == 0 {
+                continue;
+            }
+            // Ring is implicitly closed, so the first edge goes from the last vertex
+            let mut previous = read_point(ring, (vertices - 1) * 8);
+            for index in 0..vertices {
+                let current = read_point(ring, index * 8);
+                if crosses(previous, current, point) {
+                    inside = !inside;
+                }
+                previous = current;
+            }
+        }
+        inside
+    }
+}
+
+/// Checks if a ray from the point to the east crosses the edge
+fn crosses(a: Point, b: Point, point: Point) -> bool {
+    if (a.lat > point.lat) == (b.lat > point.lat) {
+        return false;
+    }
+    // Compare point longitude with the edge longitude at the point latitude,
+    // multiplied by the latitude difference to stay in integers
+    let edge_lat = b.lat as i64 - a.lat as i64;
+    let lhs = (point.lon as i64 - a.lon as i64) * edge_lat;
+    let rhs = (point.lat as i64 - a.lat as i64) * (b.lon as i64 - a.lon as i64);
+    if edge_lat > 0 {
+        lhs < rhs
+    } else {
+        lhs > rhs
+    }
+}
+
+/// Iterator over the polygons, stored as a sequence of binary records
+///
+/// Iteration stops at the first malformed record
+pub struct Polygons<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Polygons<'a> {
+    /// Creates iterator over the binary records
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Polygons { bytes }
+    }
+}
+
+impl<'a> Iterator for Polygons<'a> {
+    type Item = Polygon<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (polygon, size) = Polygon::decode(self.bytes)?;
+        self.bytes = &self.bytes[size..];
+        Some(polygon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edge_crossing() {
+        let a = Point::new(0, 0);
+        let b = Point::new(10, 10);
+        assert!(crosses(a, b, Point::new(2, 5)));
+        assert!(crosses(b, a, Point::new(2, 5)));
+        assert!(!crosses(a, b, Point::new(7, 5)));
+        assert!(!crosses(a, b, Point::new(2, 11)));
+    }
+
+    #[test]
+    fn large_coordinates() {
+        let ring = [Point::new(-180_000_000, -90_000_000), Point::new(180_000_000, -90_000_000), Point::new(180_000_000, 90_000_000), Point::new(-180_000_000, 90_000_000)];
+        let mut buffer = [0u8; 64];
+        let size = encode_polygon(3, &[&ring], &mut buffer).unwrap();
+        assert_eq!(size, polygon_encoded_size(&[&ring]));
+        let (polygon, decoded_size) = Polygon::decode(&buffer).unwrap();
+        assert_eq!(decoded_size, size);
+        assert_eq!(polygon.zone(), 3);
+        assert!(polygon.contains(Point::new(179_999_999, 89_999_999)));
+        assert!(polygon.contains(Point::new(-179_999_999, -89_999_999)));
+    }
+
+    #[test]
+    fn truncated_polygon() {
+        let ring = [Point::new(0, 0), Point::new(10, 0), Point::new(0, 10)];
+        let mut buffer = [0u8; 64];
+        let size = encode_polygon(0, &[&ring], &mut buffer).unwrap();
+        assert!(Polygon::decode(&buffer[..size - 1]).is_none());
+        assert_eq!(encode_polygon(0, &[], &mut buffer), None);
+        assert_eq!(encode_polygon(0, &[&ring], &mut buffer[..size - 1]), None);
+    }
+}